@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+import "@openzeppelin/contracts/utils/cryptography/MessageHashUtils.sol";
+
+/**
+ * @title BatchCallAndSponsorHardened
+ * @notice `BatchCallAndSponsor` with the signed digest bound to the chain and the account:
+ *    keccak256(abi.encode(block.chainid, address(this), nonce, keccak256(abi.encode(calls))))
+ * A signature made for one chain, or for another account sharing the key's nonce, is rejected.
+ * Unlike the packed `to ‖ value ‖ data` of `BatchCallAndSponsor`, the ABI encoding keeps call
+ * boundaries, so the same bytes cannot be split into a different batch.
+ */
+contract BatchCallAndSponsorHardened {
+    /// @notice A nonce used for replay protection.
+    uint256 public nonce;
+
+    /// @notice Represents a single call within a batch.
+    struct Call {
+        address to;
+        uint256 value;
+        bytes data;
+    }
+
+    /// @notice Emitted for every individual call executed.
+    event CallExecuted(address indexed sender, address indexed to, uint256 value, bytes data);
+    /// @notice Emitted when a full batch is executed.
+    event BatchExecuted(uint256 indexed nonce, Call[] calls);
+
+    /**
+     * @notice Executes a batch of calls using an off–chain signature.
+     * @param calls An array of Call structs containing destination, ETH value, and calldata.
+     * @param signature The account's ECDSA signature over the chain id, the account, the current
+     * nonce and the calls.
+     */
+    function execute(Call[] calldata calls, bytes calldata signature) external payable {
+        bytes32 digest = keccak256(abi.encode(block.chainid, address(this), nonce, keccak256(abi.encode(calls))));
+
+        bytes32 ethSignedMessageHash = MessageHashUtils.toEthSignedMessageHash(digest);
+        address recovered = ECDSA.recover(ethSignedMessageHash, signature);
+        require(recovered == address(this), "Invalid signature");
+
+        _executeBatch(calls);
+    }
+
+    /**
+     * @notice Executes a batch of calls directly, when the account calls itself.
+     * @param calls An array of Call structs containing destination, ETH value, and calldata.
+     */
+    function execute(Call[] calldata calls) external payable {
+        require(msg.sender == address(this), "Invalid authority");
+        _executeBatch(calls);
+    }
+
+    function _executeBatch(Call[] calldata calls) internal {
+        uint256 currentNonce = nonce;
+        nonce++;
+
+        for (uint256 i = 0; i < calls.length; i++) {
+            _executeCall(calls[i]);
+        }
+
+        emit BatchExecuted(currentNonce, calls);
+    }
+
+    function _executeCall(Call calldata callItem) internal {
+        (bool success,) = callItem.to.call{value: callItem.value}(callItem.data);
+        require(success, "Call reverted");
+        emit CallExecuted(msg.sender, callItem.to, callItem.value, callItem.data);
+    }
+
+    fallback() external payable {}
+    receive() external payable {}
+}
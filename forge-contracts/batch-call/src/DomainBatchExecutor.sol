@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+import "@openzeppelin/contracts/utils/cryptography/MessageHashUtils.sol";
+
+/**
+ * @title DomainBatchExecutor
+ * @notice Batch executor for EIP-7702 accounts whose signatures are bound to one chain, one
+ * account and a deadline. The account signs, as an EIP-191 message:
+ *    keccak256(abi.encode(block.chainid, address(this), nonce, validUntil, keccak256(calls)))
+ * where `keccak256(calls)` is `hashCalls(calls)`, the hash of the ABI-encoded array.
+ */
+contract DomainBatchExecutor {
+    /// @notice A nonce used for replay protection.
+    uint256 public nonce;
+
+    /// @notice Represents a single call within a batch.
+    struct Call {
+        address to;
+        uint256 value;
+        bytes data;
+    }
+
+    /// @notice Emitted for every individual call executed.
+    event CallExecuted(address indexed sender, address indexed to, uint256 value, bytes data);
+    /// @notice Emitted when a full batch is executed.
+    event BatchExecuted(uint256 indexed nonce, Call[] calls);
+
+    /**
+     * @notice Executes a batch of calls using an off–chain signature.
+     * @param calls An array of Call structs containing destination, ETH value, and calldata.
+     * @param validUntil Last block timestamp at which the signature is accepted.
+     * @param signature The account's ECDSA signature over the digest described above.
+     */
+    function execute(Call[] calldata calls, uint256 validUntil, bytes calldata signature) external payable {
+        require(block.timestamp <= validUntil, "Signature expired");
+
+        bytes32 digest = keccak256(abi.encode(block.chainid, address(this), nonce, validUntil, hashCalls(calls)));
+        address recovered = ECDSA.recover(MessageHashUtils.toEthSignedMessageHash(digest), signature);
+        require(recovered == address(this), "Invalid signature");
+
+        _executeBatch(calls);
+    }
+
+    /**
+     * @notice Executes a batch of calls directly, when the account calls itself.
+     * @param calls An array of Call structs containing destination, ETH value, and calldata.
+     */
+    function execute(Call[] calldata calls) external payable {
+        require(msg.sender == address(this), "Invalid authority");
+        _executeBatch(calls);
+    }
+
+    /// @notice Hash of the ABI-encoded calls, the last field of the signed digest.
+    function hashCalls(Call[] calldata calls) public pure returns (bytes32) {
+        return keccak256(abi.encode(calls));
+    }
+
+    function _executeBatch(Call[] calldata calls) internal {
+        uint256 currentNonce = nonce;
+        nonce++;
+
+        for (uint256 i = 0; i < calls.length; i++) {
+            _executeCall(calls[i]);
+        }
+
+        emit BatchExecuted(currentNonce, calls);
+    }
+
+    function _executeCall(Call calldata callItem) internal {
+        (bool success,) = callItem.to.call{value: callItem.value}(callItem.data);
+        require(success, "Call reverted");
+        emit CallExecuted(msg.sender, callItem.to, callItem.value, callItem.data);
+    }
+
+    fallback() external payable {}
+    receive() external payable {}
+}
@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+import "@openzeppelin/contracts/utils/cryptography/MessageHashUtils.sol";
+
+/**
+ * @title ERC7821Executor
+ * @notice Reference ERC-7821 minimal batch executor for EIP-7702 accounts.
+ *
+ * `execute(mode, executionData)` supports the batch call type (0x01) with the default (0x00) and
+ * try (0x01) execution types:
+ *    0x01000000000000000000…  executionData = abi.encode(calls)
+ *    0x01000000000078210001…  executionData = abi.encode(calls, opData)
+ * and the same two with the second byte set to 0x01 for try mode, where a failing call is
+ * reported with `TryExecuteFailed` instead of reverting the batch.
+ *
+ * As ERC-7821 specifies, a call to `address(0)` is a call to the account itself.
+ *
+ * Without opData only the account itself may execute. With opData, opData is the account's
+ * signature, as an EIP-191 message, over:
+ *    keccak256(abi.encode(block.chainid, address(this), nonce, mode, calls))
+ * so any sponsor can submit the batch.
+ */
+contract ERC7821Executor {
+    /// @notice A nonce used for replay protection of signed batches.
+    uint256 public nonce;
+
+    /// @notice Represents a single call within a batch.
+    struct Call {
+        address to;
+        uint256 value;
+        bytes data;
+    }
+
+    /// @notice Emitted in try mode for every call that reverted.
+    event TryExecuteFailed(uint256 indexed index, bytes returnData);
+    /// @notice Emitted when a full batch is executed.
+    event BatchExecuted(bytes32 indexed mode, uint256 failed);
+
+    /// @notice The execution mode is not supported.
+    error UnsupportedExecutionMode();
+
+    bytes10 private constant BATCH = 0x01000000000000000000;
+    bytes10 private constant BATCH_OPDATA = 0x01000000000078210001;
+
+    /**
+     * @notice Executes the calls encoded in `executionData` as selected by `mode`.
+     * @param mode The ERC-7821 execution mode.
+     * @param executionData `abi.encode(calls)` or `abi.encode(calls, opData)`.
+     */
+    function execute(bytes32 mode, bytes calldata executionData) external payable {
+        uint256 id = _executionModeId(mode);
+        if (id == 0) revert UnsupportedExecutionMode();
+        bool tryMode = mode[1] == 0x01;
+
+        Call[] memory calls;
+        if (id == 1) {
+            require(msg.sender == address(this), "Invalid authority");
+            calls = abi.decode(executionData, (Call[]));
+        } else {
+            bytes memory opData;
+            (calls, opData) = abi.decode(executionData, (Call[], bytes));
+            bytes32 digest = keccak256(abi.encode(block.chainid, address(this), nonce, mode, calls));
+            address recovered = ECDSA.recover(MessageHashUtils.toEthSignedMessageHash(digest), opData);
+            require(recovered == address(this), "Invalid signature");
+            nonce++;
+        }
+
+        uint256 failed;
+        for (uint256 i = 0; i < calls.length; i++) {
+            address to = calls[i].to == address(0) ? address(this) : calls[i].to;
+            (bool success, bytes memory returnData) = to.call{value: calls[i].value}(calls[i].data);
+            if (success) continue;
+            if (!tryMode) {
+                assembly {
+                    revert(add(returnData, 0x20), mload(returnData))
+                }
+            }
+            failed++;
+            emit TryExecuteFailed(i, returnData);
+        }
+
+        emit BatchExecuted(mode, failed);
+    }
+
+    /// @notice Whether `mode` is one of the modes described above.
+    function supportsExecutionMode(bytes32 mode) external pure returns (bool) {
+        return _executionModeId(mode) != 0;
+    }
+
+    /// @dev 1 for a plain batch, 2 for a batch with opData, 0 if unsupported.
+    function _executionModeId(bytes32 mode) internal pure returns (uint256) {
+        if (mode[1] != 0x00 && mode[1] != 0x01) return 0;
+        bytes10 id = bytes10(mode) & ~bytes10(0x00ff0000000000000000);
+        if (id == BATCH) return 1;
+        if (id == BATCH_OPDATA) return 2;
+        return 0;
+    }
+
+    fallback() external payable {}
+    receive() external payable {}
+}
@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "@openzeppelin/contracts/utils/cryptography/EIP712.sol";
+
+/// @notice ERC-4337 v0.7/v0.8 packed user operation.
+struct PackedUserOperation {
+    address sender;
+    uint256 nonce;
+    bytes initCode;
+    bytes callData;
+    bytes32 accountGasLimits;
+    uint256 preVerificationGas;
+    bytes32 gasFees;
+    bytes paymasterAndData;
+    bytes signature;
+}
+
+interface IAccount {
+    function validateUserOp(PackedUserOperation calldata userOp, bytes32 userOpHash, uint256 missingAccountFunds)
+        external
+        returns (uint256 validationData);
+}
+
+/**
+ * @title MinimalEntryPoint
+ * @notice A small stand-in for the ERC-4337 v0.8 EntryPoint, for local testing on Anvil.
+ *
+ * It follows v0.8 where the toolkit depends on it: the EIP-712 user operation hash in the
+ * ("ERC4337", "1") domain, the `0x7702` initCode marker for EIP-7702 accounts, 2D nonces,
+ * deposits and prefunds, and the `handleOps` ABI and events. It has no factories, paymasters,
+ * aggregators, validity windows or staking.
+ */
+contract MinimalEntryPoint is EIP712 {
+    bytes32 public constant PACKED_USEROP_TYPEHASH = keccak256(
+        "PackedUserOperation(address sender,uint256 nonce,bytes initCode,bytes callData,bytes32 accountGasLimits,uint256 preVerificationGas,bytes32 gasFees,bytes paymasterAndData)"
+    );
+
+    /// @notice The first 20 bytes of the initCode of an EIP-7702 account.
+    bytes20 public constant INITCODE_EIP7702_MARKER = bytes20(bytes2(0x7702));
+
+    mapping(address => uint256) public balanceOf;
+    mapping(address => mapping(uint192 => uint256)) public nonceSequenceNumber;
+
+    bool private locked;
+
+    event Deposited(address indexed account, uint256 totalDeposit);
+    event BeforeExecution();
+    event UserOperationEvent(
+        bytes32 indexed userOpHash,
+        address indexed sender,
+        address indexed paymaster,
+        uint256 nonce,
+        bool success,
+        uint256 actualGasCost,
+        uint256 actualGasUsed
+    );
+    event UserOperationRevertReason(
+        bytes32 indexed userOpHash, address indexed sender, uint256 nonce, bytes revertReason
+    );
+
+    /// @notice Operation `opIndex` of the bundle is invalid, with an "AAxx" reason as in v0.8.
+    error FailedOp(uint256 opIndex, string reason);
+
+    constructor() EIP712("ERC4337", "1") {}
+
+    function depositTo(address account) external payable {
+        balanceOf[account] += msg.value;
+        emit Deposited(account, balanceOf[account]);
+    }
+
+    function getNonce(address sender, uint192 key) external view returns (uint256) {
+        return nonceSequenceNumber[sender][key] | (uint256(key) << 64);
+    }
+
+    function getUserOpHash(PackedUserOperation calldata userOp) public view returns (bytes32) {
+        bytes32 initCodeHash = keccak256(userOp.initCode);
+        if (_isEip7702InitCode(userOp.initCode)) {
+            address delegate = _delegateOf(userOp.sender);
+            initCodeHash = userOp.initCode.length <= 20
+                ? keccak256(abi.encodePacked(delegate))
+                : keccak256(abi.encodePacked(delegate, userOp.initCode[20:]));
+        }
+        bytes32 structHash = keccak256(
+            abi.encode(
+                PACKED_USEROP_TYPEHASH,
+                userOp.sender,
+                userOp.nonce,
+                initCodeHash,
+                keccak256(userOp.callData),
+                userOp.accountGasLimits,
+                userOp.preVerificationGas,
+                userOp.gasFees,
+                keccak256(userOp.paymasterAndData)
+            )
+        );
+        return _hashTypedDataV4(structHash);
+    }
+
+    /**
+     * @notice Validates every operation, then executes them and pays the collected fees to
+     * `beneficiary`. Any invalid operation reverts the whole bundle with `FailedOp`.
+     */
+    function handleOps(PackedUserOperation[] calldata ops, address payable beneficiary) external {
+        require(!locked, "Reentrancy");
+        locked = true;
+
+        bytes32[] memory hashes = new bytes32[](ops.length);
+        uint256[] memory prefunds = new uint256[](ops.length);
+        for (uint256 i = 0; i < ops.length; i++) {
+            (hashes[i], prefunds[i]) = _validate(i, ops[i]);
+        }
+
+        emit BeforeExecution();
+        uint256 collected;
+        for (uint256 i = 0; i < ops.length; i++) {
+            collected += _execute(ops[i], hashes[i], prefunds[i]);
+        }
+
+        (bool paid,) = beneficiary.call{value: collected}("");
+        require(paid, "AA91 failed send to beneficiary");
+        locked = false;
+    }
+
+    function _validate(uint256 index, PackedUserOperation calldata op)
+        internal
+        returns (bytes32 userOpHash, uint256 prefund)
+    {
+        if (op.initCode.length != 0) {
+            if (!_isEip7702InitCode(op.initCode)) revert FailedOp(index, "AA13 factories not supported");
+            if (_delegateOf(op.sender) == address(0)) revert FailedOp(index, "AA20 account not delegated");
+        } else if (op.sender.code.length == 0) {
+            revert FailedOp(index, "AA20 account not deployed");
+        }
+        if (op.paymasterAndData.length != 0) revert FailedOp(index, "AA30 paymasters not supported");
+
+        (uint256 verificationGasLimit, uint256 callGasLimit) = _unpack(op.accountGasLimits);
+        (, uint256 maxFeePerGas) = _unpack(op.gasFees);
+        prefund = (verificationGasLimit + callGasLimit + op.preVerificationGas) * maxFeePerGas;
+        userOpHash = getUserOpHash(op);
+
+        uint256 deposit = balanceOf[op.sender];
+        uint256 missing = prefund > deposit ? prefund - deposit : 0;
+        try IAccount(op.sender).validateUserOp{gas: verificationGasLimit}(op, userOpHash, missing) returns (
+            uint256 validationData
+        ) {
+            if (validationData == 1) revert FailedOp(index, "AA24 signature error");
+            if (validationData != 0) revert FailedOp(index, "AA22 validity windows not supported");
+        } catch {
+            revert FailedOp(index, "AA23 reverted");
+        }
+        if (balanceOf[op.sender] < prefund) revert FailedOp(index, "AA21 didn't pay prefund");
+        balanceOf[op.sender] -= prefund;
+
+        uint192 key = uint192(op.nonce >> 64);
+        if (nonceSequenceNumber[op.sender][key]++ != uint64(op.nonce)) {
+            revert FailedOp(index, "AA25 invalid account nonce");
+        }
+    }
+
+    function _execute(PackedUserOperation calldata op, bytes32 userOpHash, uint256 prefund)
+        internal
+        returns (uint256 actualGasCost)
+    {
+        uint256 preGas = gasleft();
+        (, uint256 callGasLimit) = _unpack(op.accountGasLimits);
+        (bool success, bytes memory result) = op.sender.call{gas: callGasLimit}(op.callData);
+        if (!success) emit UserOperationRevertReason(userOpHash, op.sender, op.nonce, result);
+
+        (uint256 maxPriorityFeePerGas, uint256 maxFeePerGas) = _unpack(op.gasFees);
+        uint256 gasPrice = maxFeePerGas < maxPriorityFeePerGas + block.basefee
+            ? maxFeePerGas
+            : maxPriorityFeePerGas + block.basefee;
+        uint256 actualGasUsed = preGas - gasleft() + op.preVerificationGas;
+        actualGasCost = actualGasUsed * gasPrice;
+        if (actualGasCost > prefund) actualGasCost = prefund;
+        balanceOf[op.sender] += prefund - actualGasCost;
+
+        emit UserOperationEvent(userOpHash, op.sender, address(0), op.nonce, success, actualGasCost, actualGasUsed);
+    }
+
+    function _isEip7702InitCode(bytes calldata initCode) internal pure returns (bool) {
+        if (initCode.length < 2) return false;
+        bytes20 start;
+        assembly {
+            start := calldataload(initCode.offset)
+        }
+        if (initCode.length < 20) start &= bytes20(bytes2(0xffff));
+        return start == INITCODE_EIP7702_MARKER;
+    }
+
+    /// @dev The address `account` delegates to, or zero if its code is not a 7702 designator.
+    function _delegateOf(address account) internal view returns (address) {
+        bytes memory code = account.code;
+        if (code.length != 23 || bytes3(code) != 0xef0100) return address(0);
+        address delegate;
+        assembly {
+            delegate := shr(96, mload(add(code, 0x23)))
+        }
+        return delegate;
+    }
+
+    /// @dev Splits a packed pair of uint128s, high half first.
+    function _unpack(bytes32 packed) internal pure returns (uint256 high, uint256 low) {
+        return (uint256(packed) >> 128, uint128(uint256(packed)));
+    }
+
+    receive() external payable {
+        balanceOf[msg.sender] += msg.value;
+        emit Deposited(msg.sender, balanceOf[msg.sender]);
+    }
+}
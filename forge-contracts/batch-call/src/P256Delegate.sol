@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "@openzeppelin/contracts/utils/Base64.sol";
+import "@openzeppelin/contracts/utils/cryptography/P256.sol";
+import "./SignedBatchExecutor.sol";
+
+/**
+ * @title P256Delegate
+ * @notice Batch executor for EIP-7702 accounts controlled by a P-256 key, such as a passkey.
+ *
+ * The key signs the batch digest
+ *    keccak256(abi.encode(BATCH, block.chainid, address(this), nonce, calls))
+ * either directly, with `execute`, or as the challenge of a WebAuthn assertion, with
+ * `executeWithPasskey`. For an assertion, `clientDataJSON` must be a `webauthn.get` for that
+ * challenge, `authenticatorData` must have the user-present flag set, and the key signs
+ * sha256(authenticatorData || sha256(clientDataJSON)). Signatures are checked with
+ * OpenZeppelin's `P256`, which uses the RIP-7212 precompile where the chain has it and a Solidity
+ * implementation otherwise, and which only accepts low-s signatures.
+ *
+ * The public key is set once, from a transaction the account sends to itself (normally the one
+ * carrying its authorization), and can be changed later from a batch calling `setPublicKey`.
+ */
+contract P256Delegate is SignedBatchExecutor {
+    /// @notice Tags the digest, so that a challenge signed for another delegate never passes here.
+    bytes32 public constant BATCH = keccak256("P256Delegate.batch");
+
+    struct WebAuthnAssertion {
+        bytes authenticatorData;
+        string clientDataJSON;
+        /// Offset of `"challenge":"..."` in `clientDataJSON`.
+        uint256 challengeIndex;
+        /// Offset of `"type":"webauthn.get"` in `clientDataJSON`.
+        uint256 typeIndex;
+        bytes32 r;
+        bytes32 s;
+    }
+
+    /// @custom:storage-location erc7201:eip7702.p256delegate
+    struct P256Storage {
+        bytes32 publicKeyX;
+        bytes32 publicKeyY;
+    }
+
+    // keccak256(abi.encode(uint256(keccak256("eip7702.p256delegate")) - 1)) & ~bytes32(uint256(0xff))
+    bytes32 private constant STORAGE_LOCATION = 0xbb5f224512c4177d099a6d14dd8f94e6e4ffe9cb8b0489978c52dedd53002f00;
+
+    bytes1 internal constant USER_PRESENT = 0x01;
+    bytes32 internal constant WEBAUTHN_GET = keccak256('"type":"webauthn.get"');
+
+    event PublicKeySet(bytes32 x, bytes32 y);
+    event BatchExecuted(uint256 indexed nonce);
+
+    error NoPublicKey();
+    error InvalidP256Signature();
+    error InvalidClientData();
+    error UserNotPresent();
+
+    function _storage() private pure returns (P256Storage storage $) {
+        assembly {
+            $.slot := STORAGE_LOCATION
+        }
+    }
+
+    function setPublicKey(bytes32 x, bytes32 y) external onlySelf {
+        require(P256.isValidPublicKey(x, y), "Invalid public key");
+        P256Storage storage $ = _storage();
+        $.publicKeyX = x;
+        $.publicKeyY = y;
+        emit PublicKeySet(x, y);
+    }
+
+    function publicKeyX() external view returns (bytes32) {
+        return _storage().publicKeyX;
+    }
+
+    function publicKeyY() external view returns (bytes32) {
+        return _storage().publicKeyY;
+    }
+
+    /// @notice The digest the key signs for `calls` at the current nonce.
+    function hashBatch(Call[] calldata calls) public view returns (bytes32) {
+        return _hashBatch(BATCH, calls);
+    }
+
+    /// @notice Executes a batch whose digest the key signed directly.
+    function execute(Call[] calldata calls, bytes32 r, bytes32 s) external payable {
+        _verify(hashBatch(calls), r, s);
+        emit BatchExecuted(_executeBatch(calls));
+    }
+
+    /// @notice Executes a batch whose digest is the challenge of a WebAuthn assertion.
+    function executeWithPasskey(Call[] calldata calls, WebAuthnAssertion calldata assertion) external payable {
+        bytes calldata clientData = bytes(assertion.clientDataJSON);
+        if (keccak256(clientData[assertion.typeIndex:assertion.typeIndex + 21]) != WEBAUTHN_GET) {
+            revert InvalidClientData();
+        }
+        bytes memory challenge =
+            abi.encodePacked('"challenge":"', Base64.encodeURL(abi.encodePacked(hashBatch(calls))), '"');
+        uint256 start = assertion.challengeIndex;
+        if (keccak256(clientData[start:start + challenge.length]) != keccak256(challenge)) {
+            revert InvalidClientData();
+        }
+        if (assertion.authenticatorData.length < 37 || assertion.authenticatorData[32] & USER_PRESENT == 0) {
+            revert UserNotPresent();
+        }
+
+        bytes32 message = sha256(abi.encodePacked(assertion.authenticatorData, sha256(clientData)));
+        _verify(message, assertion.r, assertion.s);
+        emit BatchExecuted(_executeBatch(calls));
+    }
+
+    function _verify(bytes32 hash, bytes32 r, bytes32 s) internal view {
+        P256Storage storage $ = _storage();
+        bytes32 x = $.publicKeyX;
+        bytes32 y = $.publicKeyY;
+        if (x == 0 && y == 0) revert NoPublicKey();
+        if (!P256.verify(hash, r, s, x, y)) revert InvalidP256Signature();
+    }
+}
@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+import "@openzeppelin/contracts/utils/cryptography/MessageHashUtils.sol";
+import "./SignedBatchExecutor.sol";
+
+/**
+ * @title RecoveryDelegate
+ * @notice Batch executor for EIP-7702 accounts whose signing key guardians can replace after a
+ * timelock.
+ *
+ * Batches are signed by the account's signing key, which is the EOA itself until a recovery
+ * replaces it, as an EIP-191 message:
+ *    keccak256(abi.encode(BATCH, block.chainid, address(this), nonce, calls))
+ * Guardians approve a new signing key by signing, also as an EIP-191 message:
+ *    keccak256(abi.encode(RECOVERY, block.chainid, address(this), recoveryNonce, newSigner))
+ * With `guardianThreshold` approvals, ordered by ascending guardian address, anyone can start a
+ * recovery. The current signing key can cancel it with a batch calling `cancelRecovery` until
+ * `recoveryDelay` has passed; after that anyone can finalize it. Starting, cancelling and
+ * finalizing all bump `recoveryNonce`, so approvals are good for one attempt only. Changing the
+ * guardians bumps it too and drops any pending recovery, and the timelock is never shorter than
+ * `MIN_RECOVERY_DELAY`.
+ *
+ * Recovery rotates the key that signs batches. It cannot take control of the EOA's own key,
+ * which can still send transactions and re-delegate the account.
+ */
+contract RecoveryDelegate is SignedBatchExecutor {
+    bytes32 public constant RECOVERY = keccak256("RecoveryDelegate.recovery");
+    /// @notice Tags the batch digest, so that signatures for another delegate never pass here.
+    bytes32 public constant BATCH = keccak256("RecoveryDelegate.batch");
+    /// @notice The shortest timelock, so the signing key always has time to cancel a recovery.
+    uint64 public constant MIN_RECOVERY_DELAY = 1 days;
+
+    /// @custom:storage-location erc7201:eip7702.recoverydelegate
+    struct RecoveryStorage {
+        /// A nonce used for replay protection of guardian approvals.
+        uint256 recoveryNonce;
+        address signingKey;
+        mapping(address => bool) isGuardian;
+        address[] guardians;
+        uint256 guardianThreshold;
+        uint64 recoveryDelay;
+        address pendingSigner;
+        uint64 recoveryExecuteAfter;
+    }
+
+    // keccak256(abi.encode(uint256(keccak256("eip7702.recoverydelegate")) - 1)) & ~bytes32(uint256(0xff))
+    bytes32 private constant STORAGE_LOCATION = 0x14370632688887bef468074e3639bd34113c4cd068f1cac888464e5842523700;
+
+    event GuardiansChanged(address[] guardians, uint256 threshold, uint64 delay);
+    event RecoveryInitiated(address indexed newSigner, uint64 executeAfter);
+    event RecoveryCancelled(address indexed newSigner);
+    event RecoveryFinalized(address indexed oldSigner, address indexed newSigner);
+    event BatchExecuted(uint256 indexed nonce, address indexed signer);
+
+    error NoGuardians();
+    error NotEnoughApprovals(uint256 count, uint256 threshold);
+    error NotAGuardian(address signer);
+    error GuardiansNotAscending(address signer, address previous);
+    error RecoveryPending(address newSigner);
+    error NoRecoveryPending();
+    error RecoveryTimelocked(uint64 executeAfter);
+
+    function _storage() private pure returns (RecoveryStorage storage $) {
+        assembly {
+            $.slot := STORAGE_LOCATION
+        }
+    }
+
+    /// @notice The key that signs batches.
+    function signer() public view returns (address) {
+        address key = _storage().signingKey;
+        return key == address(0) ? address(this) : key;
+    }
+
+    /// @notice A nonce used for replay protection of guardian approvals.
+    function recoveryNonce() external view returns (uint256) {
+        return _storage().recoveryNonce;
+    }
+
+    function isGuardian(address guardian) external view returns (bool) {
+        return _storage().isGuardian[guardian];
+    }
+
+    function guardians() external view returns (address[] memory) {
+        return _storage().guardians;
+    }
+
+    function guardianThreshold() external view returns (uint256) {
+        return _storage().guardianThreshold;
+    }
+
+    function recoveryDelay() external view returns (uint64) {
+        return _storage().recoveryDelay;
+    }
+
+    function pendingSigner() external view returns (address) {
+        return _storage().pendingSigner;
+    }
+
+    function recoveryExecuteAfter() external view returns (uint64) {
+        return _storage().recoveryExecuteAfter;
+    }
+
+    /// @notice Replaces the guardians, how many of them must approve and the timelock. Approvals
+    /// collected so far and a pending recovery are dropped, since the old guardians gave them.
+    function setGuardians(address[] calldata guardians_, uint256 threshold_, uint64 delay) external onlySelf {
+        require(threshold_ > 0 && threshold_ <= guardians_.length, "Invalid threshold");
+        require(delay >= MIN_RECOVERY_DELAY, "Delay too short");
+        RecoveryStorage storage $ = _storage();
+        for (uint256 i = 0; i < $.guardians.length; i++) {
+            $.isGuardian[$.guardians[i]] = false;
+        }
+        delete $.guardians;
+        for (uint256 i = 0; i < guardians_.length; i++) {
+            address guardian = guardians_[i];
+            require(guardian != address(0) && guardian != address(this) && !$.isGuardian[guardian], "Invalid guardian");
+            $.isGuardian[guardian] = true;
+            $.guardians.push(guardian);
+        }
+        $.guardianThreshold = threshold_;
+        $.recoveryDelay = delay;
+
+        $.recoveryNonce++;
+        address pending = $.pendingSigner;
+        if (pending != address(0)) {
+            delete $.pendingSigner;
+            delete $.recoveryExecuteAfter;
+            emit RecoveryCancelled(pending);
+        }
+        emit GuardiansChanged(guardians_, threshold_, delay);
+    }
+
+    /// @notice The digest guardians sign, before the EIP-191 prefix, to approve `newSigner`.
+    function hashRecovery(address newSigner) public view returns (bytes32) {
+        return keccak256(abi.encode(RECOVERY, block.chainid, address(this), _storage().recoveryNonce, newSigner));
+    }
+
+    /// @notice Starts replacing the signing key with `newSigner`, approved by enough guardians.
+    function initiateRecovery(address newSigner, bytes[] calldata approvals) external {
+        RecoveryStorage storage $ = _storage();
+        uint256 required = $.guardianThreshold;
+        if (required == 0) revert NoGuardians();
+        if ($.pendingSigner != address(0)) revert RecoveryPending($.pendingSigner);
+        require(newSigner != address(0), "Invalid signer");
+        if (approvals.length < required) revert NotEnoughApprovals(approvals.length, required);
+
+        bytes32 digest = MessageHashUtils.toEthSignedMessageHash(hashRecovery(newSigner));
+        address previous = address(0);
+        for (uint256 i = 0; i < approvals.length; i++) {
+            address guardian = ECDSA.recover(digest, approvals[i]);
+            if (!$.isGuardian[guardian]) revert NotAGuardian(guardian);
+            if (guardian <= previous) revert GuardiansNotAscending(guardian, previous);
+            previous = guardian;
+        }
+
+        $.recoveryNonce++;
+        $.pendingSigner = newSigner;
+        $.recoveryExecuteAfter = uint64(block.timestamp) + $.recoveryDelay;
+        emit RecoveryInitiated(newSigner, $.recoveryExecuteAfter);
+    }
+
+    /// @notice Stops a pending recovery, from a batch of the current signing key.
+    function cancelRecovery() external onlySelf {
+        RecoveryStorage storage $ = _storage();
+        address newSigner = $.pendingSigner;
+        if (newSigner == address(0)) revert NoRecoveryPending();
+        $.recoveryNonce++;
+        delete $.pendingSigner;
+        delete $.recoveryExecuteAfter;
+        emit RecoveryCancelled(newSigner);
+    }
+
+    /// @notice Makes the pending signer the signing key once the timelock has passed.
+    function finalizeRecovery() external {
+        RecoveryStorage storage $ = _storage();
+        address newSigner = $.pendingSigner;
+        if (newSigner == address(0)) revert NoRecoveryPending();
+        if (block.timestamp < $.recoveryExecuteAfter) revert RecoveryTimelocked($.recoveryExecuteAfter);
+        address oldSigner = signer();
+        $.recoveryNonce++;
+        $.signingKey = newSigner;
+        delete $.pendingSigner;
+        delete $.recoveryExecuteAfter;
+        emit RecoveryFinalized(oldSigner, newSigner);
+    }
+
+    /// @notice Executes a batch signed by the signing key.
+    function execute(Call[] calldata calls, bytes calldata signature) external payable {
+        bytes32 digest = _hashBatch(BATCH, calls);
+        address key = signer();
+        require(ECDSA.recover(MessageHashUtils.toEthSignedMessageHash(digest), signature) == key, "Invalid signature");
+
+        emit BatchExecuted(_executeBatch(calls), key);
+    }
+}
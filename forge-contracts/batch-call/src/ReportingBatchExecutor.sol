@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+import "@openzeppelin/contracts/utils/cryptography/MessageHashUtils.sol";
+
+/**
+ * @title ReportingBatchExecutor
+ * @notice Batch executor for EIP-7702 accounts where calls can be marked optional and every
+ * call's outcome is emitted as a `CallResult` event. A failing optional call is reported and
+ * skipped; a failing required call reverts the batch with `CallFailed(index, reason)`.
+ *
+ * The account signs, as an EIP-191 message:
+ *    keccak256(abi.encode(block.chainid, address(this), nonce, calls))
+ */
+contract ReportingBatchExecutor {
+    /// @notice A nonce used for replay protection.
+    uint256 public nonce;
+
+    struct Call {
+        address to;
+        uint256 value;
+        bytes data;
+        bool allowFailure;
+    }
+
+    struct Result {
+        bool success;
+        bytes returnData;
+    }
+
+    /// @notice Emitted for every call, with its return data or revert data.
+    event CallResult(uint256 indexed index, address indexed to, bool success, bytes returnData);
+    /// @notice Emitted when a full batch is executed.
+    event BatchExecuted(uint256 indexed nonce, uint256 failed);
+
+    /// @notice A call without `allowFailure` reverted with `reason`.
+    error CallFailed(uint256 index, bytes reason);
+
+    /**
+     * @notice Executes a batch of calls using an off–chain signature.
+     * @param calls The calls, each with its own `allowFailure` flag.
+     * @param signature The account's ECDSA signature over the digest described above.
+     */
+    function execute(Call[] calldata calls, bytes calldata signature)
+        external
+        payable
+        returns (Result[] memory)
+    {
+        bytes32 digest = keccak256(abi.encode(block.chainid, address(this), nonce, calls));
+        address recovered = ECDSA.recover(MessageHashUtils.toEthSignedMessageHash(digest), signature);
+        require(recovered == address(this), "Invalid signature");
+
+        return _executeBatch(calls);
+    }
+
+    /**
+     * @notice Executes a batch of calls directly, when the account calls itself.
+     * @param calls The calls, each with its own `allowFailure` flag.
+     */
+    function execute(Call[] calldata calls) external payable returns (Result[] memory) {
+        require(msg.sender == address(this), "Invalid authority");
+        return _executeBatch(calls);
+    }
+
+    function _executeBatch(Call[] calldata calls) internal returns (Result[] memory results) {
+        uint256 currentNonce = nonce;
+        nonce++;
+
+        results = new Result[](calls.length);
+        uint256 failed;
+        for (uint256 i = 0; i < calls.length; i++) {
+            Call calldata c = calls[i];
+            (bool success, bytes memory returnData) = c.to.call{value: c.value}(c.data);
+            if (!success) {
+                if (!c.allowFailure) revert CallFailed(i, returnData);
+                failed++;
+            }
+            results[i] = Result(success, returnData);
+            emit CallResult(i, c.to, success, returnData);
+        }
+
+        emit BatchExecuted(currentNonce, failed);
+    }
+
+    fallback() external payable {}
+    receive() external payable {}
+}
@@ -0,0 +1,142 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+import "@openzeppelin/contracts/utils/cryptography/MessageHashUtils.sol";
+import "./SignedBatchExecutor.sol";
+
+/**
+ * @title SessionKeyDelegate
+ * @notice Batch executor for EIP-7702 accounts whose owner can hand out session keys limited to
+ * some targets and function selectors, a total value and an expiry.
+ *
+ * Both the owner and session keys sign, as an EIP-191 message:
+ *    keccak256(abi.encode(BATCH, block.chainid, address(this), nonce, calls))
+ * The owner's batches are unrestricted; only they can add and revoke session keys, from a
+ * transaction the account sends to itself or from a call of an owner-signed batch. A call of a
+ * session key batch must go to one of the key's targets and, when it carries at least four
+ * bytes of data, use one of its selectors. Calls with shorter data, such as plain transfers, need
+ * the zero selector. The values of all calls made with a key add up against its value limit.
+ * The batch nonce is shared by the owner and all session keys.
+ */
+contract SessionKeyDelegate is SignedBatchExecutor {
+    /// @notice Tags the digest, so that a signature for another delegate never passes here.
+    bytes32 public constant BATCH = keccak256("SessionKeyDelegate.batch");
+
+    struct SessionKey {
+        uint64 validUntil;
+        uint256 valueLimit;
+        uint256 valueSpent;
+        address[] targets;
+        bytes4[] selectors;
+    }
+
+    /// @custom:storage-location erc7201:eip7702.sessionkeydelegate
+    struct SessionKeyStorage {
+        mapping(address => SessionKey) sessions;
+        address[] keys;
+    }
+
+    // keccak256(abi.encode(uint256(keccak256("eip7702.sessionkeydelegate")) - 1)) & ~bytes32(uint256(0xff))
+    bytes32 private constant STORAGE_LOCATION = 0x15eeb5287b6506efb5a3c9bd5b1f032dd4536efb1d01896b10ee0f0d876e7600;
+
+    event SessionKeyAdded(address indexed key, uint64 validUntil, uint256 valueLimit, address[] targets, bytes4[] selectors);
+    event SessionKeyRevoked(address indexed key);
+    event BatchExecuted(uint256 indexed nonce, address indexed signer);
+
+    error UnknownSessionKey(address key);
+    error SessionKeyExpired(address key, uint64 validUntil);
+    error TargetNotAllowed(address key, address target);
+    error SelectorNotAllowed(address key, bytes4 selector);
+    error ValueLimitExceeded(address key, uint256 value, uint256 remaining);
+
+    function _storage() private pure returns (SessionKeyStorage storage $) {
+        assembly {
+            $.slot := STORAGE_LOCATION
+        }
+    }
+
+    /// @notice Adds or replaces `key`. Replacing resets the value it has spent.
+    function addSessionKey(
+        address key,
+        uint64 validUntil,
+        uint256 valueLimit,
+        address[] calldata targets,
+        bytes4[] calldata selectors
+    ) external onlySelf {
+        require(key != address(0) && key != address(this), "Invalid session key");
+        require(validUntil > block.timestamp, "Already expired");
+        SessionKeyStorage storage $ = _storage();
+        if ($.sessions[key].validUntil == 0) $.keys.push(key);
+        $.sessions[key] = SessionKey(validUntil, valueLimit, 0, targets, selectors);
+        emit SessionKeyAdded(key, validUntil, valueLimit, targets, selectors);
+    }
+
+    function revokeSessionKey(address key) external onlySelf {
+        SessionKeyStorage storage $ = _storage();
+        if ($.sessions[key].validUntil == 0) revert UnknownSessionKey(key);
+        delete $.sessions[key];
+        address[] storage keys = $.keys;
+        for (uint256 i = 0; i < keys.length; i++) {
+            if (keys[i] == key) {
+                keys[i] = keys[keys.length - 1];
+                keys.pop();
+                break;
+            }
+        }
+        emit SessionKeyRevoked(key);
+    }
+
+    /// @notice Every session key that has not been revoked, including expired ones.
+    function sessionKeys() external view returns (address[] memory) {
+        return _storage().keys;
+    }
+
+    function getSessionKey(address key) external view returns (SessionKey memory) {
+        return _storage().sessions[key];
+    }
+
+    /// @notice Executes a batch signed by the owner.
+    function execute(Call[] calldata calls, bytes calldata signature) external payable {
+        require(_recover(calls, signature) == address(this), "Invalid signature");
+        emit BatchExecuted(_executeBatch(calls), address(this));
+    }
+
+    /// @notice Executes a batch signed by a session key, within that key's permissions.
+    function executeWithSessionKey(Call[] calldata calls, bytes calldata signature) external payable {
+        address key = _recover(calls, signature);
+        SessionKey storage session = _storage().sessions[key];
+        if (session.validUntil == 0) revert UnknownSessionKey(key);
+        if (block.timestamp > session.validUntil) revert SessionKeyExpired(key, session.validUntil);
+
+        for (uint256 i = 0; i < calls.length; i++) {
+            Call calldata c = calls[i];
+            if (c.to == address(this) || !_contains(session.targets, c.to)) revert TargetNotAllowed(key, c.to);
+            bytes4 selector = c.data.length >= 4 ? bytes4(c.data[:4]) : bytes4(0);
+            if (!_contains(session.selectors, selector)) revert SelectorNotAllowed(key, selector);
+            uint256 remaining = session.valueLimit - session.valueSpent;
+            if (c.value > remaining) revert ValueLimitExceeded(key, c.value, remaining);
+            session.valueSpent += c.value;
+        }
+
+        emit BatchExecuted(_executeBatch(calls), key);
+    }
+
+    function _recover(Call[] calldata calls, bytes calldata signature) internal view returns (address) {
+        return ECDSA.recover(MessageHashUtils.toEthSignedMessageHash(_hashBatch(BATCH, calls)), signature);
+    }
+
+    function _contains(address[] storage list, address item) internal view returns (bool) {
+        for (uint256 i = 0; i < list.length; i++) {
+            if (list[i] == item) return true;
+        }
+        return false;
+    }
+
+    function _contains(bytes4[] storage list, bytes4 item) internal view returns (bool) {
+        for (uint256 i = 0; i < list.length; i++) {
+            if (list[i] == item) return true;
+        }
+        return false;
+    }
+}
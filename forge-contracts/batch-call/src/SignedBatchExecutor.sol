@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+/**
+ * @title SignedBatchExecutor
+ * @notice The part of a signed batch executor for EIP-7702 accounts that does not depend on who
+ * signs: the batch nonce, the digest and the call loop.
+ *
+ * A batch is signed over
+ *    keccak256(abi.encode(tag, block.chainid, address(this), nonce, calls))
+ * where `tag` is the inheriting contract's own `BATCH` constant, so that a signature for one
+ * delegate never passes in another.
+ *
+ * Delegates built on this contract keep their state in ERC-7201 namespaces, so that what another
+ * delegate left in the account's storage is never read as theirs. The nonce lives in this
+ * contract's namespace and the rest of their state in their own.
+ */
+abstract contract SignedBatchExecutor {
+    struct Call {
+        address to;
+        uint256 value;
+        bytes data;
+    }
+
+    /// @custom:storage-location erc7201:eip7702.signedbatchexecutor
+    struct SignedBatchStorage {
+        /// A nonce used for replay protection of batches.
+        uint256 nonce;
+    }
+
+    // keccak256(abi.encode(uint256(keccak256("eip7702.signedbatchexecutor")) - 1)) & ~bytes32(uint256(0xff))
+    bytes32 private constant SIGNED_BATCH_STORAGE_LOCATION =
+        0xcea40d800c4d11753714d87a70ec3af12236068ecffc812759bdb8dbd3d33b00;
+
+    function _signedBatchStorage() private pure returns (SignedBatchStorage storage $) {
+        assembly {
+            $.slot := SIGNED_BATCH_STORAGE_LOCATION
+        }
+    }
+
+    modifier onlySelf() {
+        require(msg.sender == address(this), "Invalid authority");
+        _;
+    }
+
+    /// @notice A nonce used for replay protection of batches.
+    function nonce() external view returns (uint256) {
+        return _signedBatchStorage().nonce;
+    }
+
+    /// @notice The digest signed for `calls` at the current nonce, before any signature scheme's
+    /// own prefix.
+    function _hashBatch(bytes32 tag, Call[] calldata calls) internal view returns (bytes32) {
+        return keccak256(abi.encode(tag, block.chainid, address(this), _signedBatchStorage().nonce, calls));
+    }
+
+    /// @notice Bumps the nonce and makes the calls, bubbling up the first revert.
+    /// @return usedNonce The nonce the batch was signed for.
+    function _executeBatch(Call[] calldata calls) internal returns (uint256 usedNonce) {
+        SignedBatchStorage storage $ = _signedBatchStorage();
+        usedNonce = $.nonce;
+        $.nonce++;
+        for (uint256 i = 0; i < calls.length; i++) {
+            (bool success, bytes memory result) = calls[i].to.call{value: calls[i].value}(calls[i].data);
+            if (!success) {
+                assembly {
+                    revert(add(result, 0x20), mload(result))
+                }
+            }
+        }
+    }
+
+    fallback() external payable {}
+    receive() external payable {}
+}
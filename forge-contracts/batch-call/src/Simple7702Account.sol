@@ -0,0 +1,73 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+import {PackedUserOperation, IAccount} from "./MinimalEntryPoint.sol";
+
+/**
+ * @title Simple7702Account
+ * @notice ERC-4337 account for EOAs delegated with EIP-7702. A user operation is valid when the
+ * EOA's own key signed the EntryPoint's user operation hash, which under v0.8 is already an
+ * EIP-712 digest and is signed as is.
+ */
+contract Simple7702Account is IAccount {
+    /// @notice The EntryPoint this account accepts user operations from.
+    address public immutable entryPoint;
+
+    struct Call {
+        address to;
+        uint256 value;
+        bytes data;
+    }
+
+    uint256 internal constant SIG_VALIDATION_FAILED = 1;
+
+    constructor(address entryPoint_) {
+        entryPoint = entryPoint_;
+    }
+
+    function validateUserOp(PackedUserOperation calldata userOp, bytes32 userOpHash, uint256 missingAccountFunds)
+        external
+        returns (uint256 validationData)
+    {
+        require(msg.sender == entryPoint, "Not from EntryPoint");
+        (address recovered, ECDSA.RecoverError err,) = ECDSA.tryRecover(userOpHash, userOp.signature);
+        if (err != ECDSA.RecoverError.NoError || recovered != address(this)) {
+            validationData = SIG_VALIDATION_FAILED;
+        }
+        if (missingAccountFunds != 0) {
+            (bool success,) = payable(msg.sender).call{value: missingAccountFunds}("");
+            (success);
+        }
+    }
+
+    /// @notice Executes one call, from the EntryPoint or from the account itself.
+    function execute(address to, uint256 value, bytes calldata data) external payable {
+        _requireFromEntryPointOrSelf();
+        _call(to, value, data);
+    }
+
+    /// @notice Executes calls in order, reverting all of them if one reverts.
+    function executeBatch(Call[] calldata calls) external payable {
+        _requireFromEntryPointOrSelf();
+        for (uint256 i = 0; i < calls.length; i++) {
+            _call(calls[i].to, calls[i].value, calls[i].data);
+        }
+    }
+
+    function _requireFromEntryPointOrSelf() internal view {
+        require(msg.sender == entryPoint || msg.sender == address(this), "Invalid authority");
+    }
+
+    function _call(address to, uint256 value, bytes calldata data) internal {
+        (bool success, bytes memory result) = to.call{value: value}(data);
+        if (!success) {
+            assembly {
+                revert(add(result, 0x20), mload(result))
+            }
+        }
+    }
+
+    fallback() external payable {}
+    receive() external payable {}
+}
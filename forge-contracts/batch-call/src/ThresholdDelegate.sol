@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+import "@openzeppelin/contracts/utils/cryptography/MessageHashUtils.sol";
+import "./SignedBatchExecutor.sol";
+
+/**
+ * @title ThresholdDelegate
+ * @notice Batch executor for EIP-7702 accounts controlled by M of N owners.
+ *
+ * Each owner signs, as an EIP-191 message:
+ *    keccak256(abi.encode(BATCH, block.chainid, address(this), nonce, calls))
+ * and `execute` takes at least `threshold` of these signatures, ordered by strictly ascending
+ * signer address so that no owner counts twice. Anyone can submit a fully signed batch.
+ *
+ * The owner set is changed with `setOwners`, either from a transaction the account sends to
+ * itself (for the initial setup) or from a call of a threshold-signed batch. The EOA's own key
+ * keeps full control of the account; this contract cannot take it away.
+ */
+contract ThresholdDelegate is SignedBatchExecutor {
+    /// @notice Tags the digest, so that owners' signatures for another delegate never pass here.
+    bytes32 public constant BATCH = keccak256("ThresholdDelegate.batch");
+
+    /// @custom:storage-location erc7201:eip7702.thresholddelegate
+    struct ThresholdStorage {
+        /// Number of owner signatures `execute` needs; zero until owners are set.
+        uint256 threshold;
+        mapping(address => bool) isOwner;
+        address[] owners;
+    }
+
+    // keccak256(abi.encode(uint256(keccak256("eip7702.thresholddelegate")) - 1)) & ~bytes32(uint256(0xff))
+    bytes32 private constant STORAGE_LOCATION = 0x42faf6a82032b8dd7f25d9f85881a9595a4b30ee5f9997f7696a600299602600;
+
+    event OwnersChanged(address[] owners, uint256 threshold);
+    event BatchExecuted(uint256 indexed nonce, address[] signers);
+
+    error NotConfigured();
+    error NotEnoughSignatures(uint256 count, uint256 threshold);
+    error NotAnOwner(address signer);
+    error SignersNotAscending(address signer, address previous);
+
+    function _storage() private pure returns (ThresholdStorage storage $) {
+        assembly {
+            $.slot := STORAGE_LOCATION
+        }
+    }
+
+    /// @notice Replaces the owner set and threshold.
+    function setOwners(address[] calldata owners_, uint256 threshold_) external onlySelf {
+        require(threshold_ > 0 && threshold_ <= owners_.length, "Invalid threshold");
+        ThresholdStorage storage $ = _storage();
+        for (uint256 i = 0; i < $.owners.length; i++) {
+            $.isOwner[$.owners[i]] = false;
+        }
+        delete $.owners;
+        for (uint256 i = 0; i < owners_.length; i++) {
+            address owner = owners_[i];
+            require(owner != address(0) && !$.isOwner[owner], "Invalid owner");
+            $.isOwner[owner] = true;
+            $.owners.push(owner);
+        }
+        $.threshold = threshold_;
+        emit OwnersChanged(owners_, threshold_);
+    }
+
+    /// @notice Number of owner signatures `execute` needs; zero until owners are set.
+    function threshold() external view returns (uint256) {
+        return _storage().threshold;
+    }
+
+    function isOwner(address owner) external view returns (bool) {
+        return _storage().isOwner[owner];
+    }
+
+    function owners() external view returns (address[] memory) {
+        return _storage().owners;
+    }
+
+    /// @notice The digest owners sign, before the EIP-191 prefix, for the current nonce.
+    function hashBatch(Call[] calldata calls) public view returns (bytes32) {
+        return _hashBatch(BATCH, calls);
+    }
+
+    /// @notice Executes a batch signed by at least `threshold` owners.
+    function execute(Call[] calldata calls, bytes[] calldata signatures) external payable {
+        ThresholdStorage storage $ = _storage();
+        uint256 required = $.threshold;
+        if (required == 0) revert NotConfigured();
+        if (signatures.length < required) revert NotEnoughSignatures(signatures.length, required);
+
+        bytes32 digest = MessageHashUtils.toEthSignedMessageHash(hashBatch(calls));
+        address[] memory signers = new address[](signatures.length);
+        address previous = address(0);
+        for (uint256 i = 0; i < signatures.length; i++) {
+            address signer = ECDSA.recover(digest, signatures[i]);
+            if (!$.isOwner[signer]) revert NotAnOwner(signer);
+            if (signer <= previous) revert SignersNotAscending(signer, previous);
+            signers[i] = signer;
+            previous = signer;
+        }
+
+        emit BatchExecuted(_executeBatch(calls), signers);
+    }
+}
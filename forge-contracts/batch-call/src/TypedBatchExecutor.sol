@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+import "@openzeppelin/contracts/utils/cryptography/EIP712.sol";
+
+/**
+ * @title TypedBatchExecutor
+ * @notice Batch executor for EIP-7702 accounts that takes EIP-712 signatures, so wallets can show
+ * the calls being signed instead of an opaque hash. The account signs the typed message
+ *    Batch(Call[] calls,uint256 nonce)
+ *    Call(address to,uint256 value,bytes data)
+ * in the domain {name: "TypedBatchExecutor", version: "1", chainId, verifyingContract: account}.
+ */
+contract TypedBatchExecutor is EIP712 {
+    struct Call {
+        address to;
+        uint256 value;
+        bytes data;
+    }
+
+    struct Batch {
+        Call[] calls;
+        uint256 nonce;
+    }
+
+    bytes32 public constant CALL_TYPEHASH = keccak256("Call(address to,uint256 value,bytes data)");
+    bytes32 public constant BATCH_TYPEHASH =
+        keccak256("Batch(Call[] calls,uint256 nonce)Call(address to,uint256 value,bytes data)");
+
+    /// @notice A nonce used for replay protection.
+    uint256 public nonce;
+
+    /// @notice Emitted for every individual call executed.
+    event CallExecuted(address indexed sender, address indexed to, uint256 value, bytes data);
+    /// @notice Emitted when a full batch is executed.
+    event BatchExecuted(uint256 indexed nonce, Call[] calls);
+
+    constructor() EIP712("TypedBatchExecutor", "1") {}
+
+    /**
+     * @notice Executes a batch signed by the account as EIP-712 typed data.
+     * @param batch The signed message; its nonce must be the account's current nonce.
+     * @param signature The account's ECDSA signature over the typed data hash.
+     */
+    function execute(Batch calldata batch, bytes calldata signature) external payable {
+        require(batch.nonce == nonce, "Invalid nonce");
+        address recovered = ECDSA.recover(hashBatch(batch), signature);
+        require(recovered == address(this), "Invalid signature");
+
+        _executeBatch(batch.calls);
+    }
+
+    /**
+     * @notice Executes a batch of calls directly, when the account calls itself.
+     * @param calls An array of Call structs containing destination, ETH value, and calldata.
+     */
+    function execute(Call[] calldata calls) external payable {
+        require(msg.sender == address(this), "Invalid authority");
+        _executeBatch(calls);
+    }
+
+    /// @notice The EIP-712 hash the account signs for `batch`.
+    function hashBatch(Batch calldata batch) public view returns (bytes32) {
+        bytes32[] memory callHashes = new bytes32[](batch.calls.length);
+        for (uint256 i = 0; i < batch.calls.length; i++) {
+            Call calldata c = batch.calls[i];
+            callHashes[i] = keccak256(abi.encode(CALL_TYPEHASH, c.to, c.value, keccak256(c.data)));
+        }
+        bytes32 structHash =
+            keccak256(abi.encode(BATCH_TYPEHASH, keccak256(abi.encodePacked(callHashes)), batch.nonce));
+        return _hashTypedDataV4(structHash);
+    }
+
+    function _executeBatch(Call[] calldata calls) internal {
+        uint256 currentNonce = nonce;
+        nonce++;
+
+        for (uint256 i = 0; i < calls.length; i++) {
+            _executeCall(calls[i]);
+        }
+
+        emit BatchExecuted(currentNonce, calls);
+    }
+
+    function _executeCall(Call calldata callItem) internal {
+        (bool success,) = callItem.to.call{value: callItem.value}(callItem.data);
+        require(success, "Call reverted");
+        emit CallExecuted(msg.sender, callItem.to, callItem.value, callItem.data);
+    }
+
+    fallback() external payable {}
+    receive() external payable {}
+}
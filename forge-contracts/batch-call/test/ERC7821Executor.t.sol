@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Test} from "forge-std/Test.sol";
+import {ERC7821Executor} from "../src/ERC7821Executor.sol";
+
+contract ERC7821ExecutorTest is Test {
+    // Alice's address and private key (EOA with no initial contract code).
+    address payable ALICE_ADDRESS = payable(0x70997970C51812dc3A010C7d01b50e0d17dc79C8);
+    uint256 constant ALICE_PK = 0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d;
+
+    bytes32 constant BATCH_MODE = bytes32(bytes10(0x01000000000000000000));
+
+    ERC7821Executor public implementation;
+
+    function setUp() public {
+        implementation = new ERC7821Executor();
+        vm.deal(ALICE_ADDRESS, 10 ether);
+    }
+
+    function testZeroAddressCallsTheAccount() public {
+        address recipient = makeAddr("recipient");
+        ERC7821Executor.Call[] memory inner = new ERC7821Executor.Call[](1);
+        inner[0] = ERC7821Executor.Call({to: recipient, value: 1 ether, data: ""});
+
+        // The outer call targets address(0), so it must reach Alice's own `execute`, which
+        // only she may call, and pay the recipient from there.
+        ERC7821Executor.Call[] memory calls = new ERC7821Executor.Call[](1);
+        calls[0] = ERC7821Executor.Call({
+            to: address(0),
+            value: 0,
+            data: abi.encodeCall(ERC7821Executor.execute, (BATCH_MODE, abi.encode(inner)))
+        });
+
+        vm.signAndAttachDelegation(address(implementation), ALICE_PK);
+
+        vm.prank(ALICE_ADDRESS);
+        ERC7821Executor(ALICE_ADDRESS).execute(BATCH_MODE, abi.encode(calls));
+
+        assertEq(recipient.balance, 1 ether);
+    }
+}
@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.29;
+
+import {ECDSA} from "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+import "@openzeppelin/contracts/utils/cryptography/MessageHashUtils.sol";
+
+// `SimpleTransfer` with the nonce of `transferWithSig` consumed, so a signed transfer executes
+// at most once. The signed digest is unchanged.
+contract SimpleTransferHardened {
+    event Transfer(address indexed from, address indexed to, uint256 amount);
+
+    // Nonces already used, per signer.
+    mapping(address => mapping(uint256 => bool)) public nonceUsed;
+
+    function transfer(address from_alice, address to_bob, uint256 amount) public payable {
+        require(msg.sender == from_alice, "Only Alice can initiate the transfer");
+        require(msg.value >= amount, "Insufficient ETH sent");
+
+        (bool success,) = to_bob.call{value: amount}("");
+        require(success, "Transfer failed");
+
+        if (msg.value > amount) {
+            (bool refundSuccess,) = from_alice.call{value: msg.value - amount}("");
+            require(refundSuccess, "Refund failed");
+        }
+
+        emit Transfer(from_alice, to_bob, amount);
+    }
+
+    function transferWithSig(
+        address from_alice,
+        address to_bob,
+        uint256 amount,
+        uint256 nonce,
+        bytes calldata signature
+    ) external payable {
+        bytes32 hash = keccak256(abi.encodePacked(from_alice, to_bob, amount, nonce, address(this)));
+        address signer = ECDSA.recover(MessageHashUtils.toEthSignedMessageHash(hash), signature);
+        require(signer == from_alice, "Invalid signature");
+        require(!nonceUsed[from_alice][nonce], "Nonce already used");
+        nonceUsed[from_alice][nonce] = true;
+        require(msg.value >= amount, "Insufficient ETH sent");
+
+        (bool success,) = to_bob.call{value: amount}("");
+        require(success, "Transfer failed");
+        if (msg.value > amount) {
+            (bool refundSuccess,) = msg.sender.call{value: msg.value - amount}("");
+            require(refundSuccess, "Refund failed");
+        }
+        emit Transfer(from_alice, to_bob, amount);
+    }
+}
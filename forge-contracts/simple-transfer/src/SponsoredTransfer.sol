@@ -0,0 +1,24 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.29;
+
+// Variant of `SimpleTransfer` used by the sponsored EIP-7702 flow. The sponsor is `msg.sender`
+// and pays no value, so the sender and value checks of `SimpleTransfer.transfer` are left out.
+contract SponsoredTransfer {
+    // Event to emit when a transfer occurs
+    event Transfer(address indexed from, address indexed to, uint256 amount);
+
+    // Function to transfer ETH from Alice to Bob
+    function transfer(address from_alice, address to_bob, uint256 amount) public payable {
+        // Transfer ETH to Bob
+        (bool success,) = to_bob.call{value: amount}("");
+        require(success, "Transfer failed");
+
+        // Refund excess ETH if any
+        if (msg.value > amount) {
+            (bool refundSuccess,) = from_alice.call{value: msg.value - amount}("");
+            require(refundSuccess, "Refund failed");
+        }
+
+        emit Transfer(from_alice, to_bob, amount);
+    }
+}
@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.29;
+
+import {ECDSA} from "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+import "@openzeppelin/contracts/utils/cryptography/MessageHashUtils.sol";
+
+// `SponsoredTransfer` for delegated accounts that only moves funds the account signed for.
+// The sponsor submits the account's signature over
+// keccak256(abi.encode(block.chainid, address(this), nonce, to, amount)), so the signature is
+// bound to one chain, one account and one use.
+contract SponsoredTransferHardened {
+    event Transfer(address indexed from, address indexed to, uint256 amount);
+
+    uint256 public nonce;
+
+    function transfer(address to, uint256 amount, bytes calldata signature) external {
+        bytes32 digest = keccak256(abi.encode(block.chainid, address(this), nonce, to, amount));
+        address signer = ECDSA.recover(MessageHashUtils.toEthSignedMessageHash(digest), signature);
+        require(signer == address(this), "Invalid signature");
+        nonce++;
+
+        (bool success,) = to.call{value: amount}("");
+        require(success, "Transfer failed");
+
+        emit Transfer(address(this), to, amount);
+    }
+}
@@ -0,0 +1,579 @@
+//! Static risk analysis of delegate runtime code.
+//!
+//! Delegating an EOA hands the implementation full control over the account, so anything an
+//! outsider can reach through the dispatcher is reachable against the account's funds. The
+//! analysis is a heuristic over solc output: code reachable from each dispatcher entry is
+//! collected by following static jumps (and any pushed jump destination, which covers internal
+//! function returns), then checked for calls, storage writes and access control.
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt;
+
+use alloy::{json_abi::JsonAbi, primitives::Selector};
+
+use crate::artifacts::strip_metadata;
+
+pub mod opcode {
+    pub const STOP: u8 = 0x00;
+    pub const LT: u8 = 0x10;
+    pub const EQ: u8 = 0x14;
+    pub const ADDRESS: u8 = 0x30;
+    pub const CALLER: u8 = 0x33;
+    pub const CALLDATALOAD: u8 = 0x35;
+    pub const CALLDATASIZE: u8 = 0x36;
+    pub const CALLDATACOPY: u8 = 0x37;
+    pub const SSTORE: u8 = 0x55;
+    pub const JUMP: u8 = 0x56;
+    pub const JUMPI: u8 = 0x57;
+    pub const JUMPDEST: u8 = 0x5b;
+    pub const PUSH0: u8 = 0x5f;
+    pub const PUSH1: u8 = 0x60;
+    pub const PUSH4: u8 = 0x63;
+    pub const PUSH32: u8 = 0x7f;
+    pub const DUP1: u8 = 0x80;
+    pub const CALL: u8 = 0xf1;
+    pub const CALLCODE: u8 = 0xf2;
+    pub const RETURN: u8 = 0xf3;
+    pub const DELEGATECALL: u8 = 0xf4;
+    pub const STATICCALL: u8 = 0xfa;
+    pub const REVERT: u8 = 0xfd;
+    pub const INVALID: u8 = 0xfe;
+    pub const SELFDESTRUCT: u8 = 0xff;
+}
+
+use opcode::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction<'a> {
+    pub pc: usize,
+    pub opcode: u8,
+    pub immediate: &'a [u8],
+}
+
+impl Instruction<'_> {
+    pub fn is_push(&self) -> bool {
+        (PUSH0..=PUSH32).contains(&self.opcode)
+    }
+
+    /// The pushed value, if it fits in a `usize`.
+    pub fn push_value(&self) -> Option<usize> {
+        if !self.is_push() || self.immediate.len() > 8 {
+            return None;
+        }
+        Some(
+            self.immediate
+                .iter()
+                .fold(0usize, |acc, b| (acc << 8) | *b as usize),
+        )
+    }
+
+    fn is_terminator(&self) -> bool {
+        matches!(
+            self.opcode,
+            STOP | JUMP | JUMPI | RETURN | REVERT | INVALID | SELFDESTRUCT
+        )
+    }
+}
+
+impl fmt::Display for Instruction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04x}: {}", self.pc, opcode_name(self.opcode))?;
+        if !self.immediate.is_empty() {
+            write!(f, " 0x{}", hex::encode(self.immediate))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn opcode_name(op: u8) -> String {
+    let name = match op {
+        0x00 => "STOP",
+        0x01 => "ADD",
+        0x02 => "MUL",
+        0x03 => "SUB",
+        0x04 => "DIV",
+        0x05 => "SDIV",
+        0x06 => "MOD",
+        0x07 => "SMOD",
+        0x08 => "ADDMOD",
+        0x09 => "MULMOD",
+        0x0a => "EXP",
+        0x0b => "SIGNEXTEND",
+        0x10 => "LT",
+        0x11 => "GT",
+        0x12 => "SLT",
+        0x13 => "SGT",
+        0x14 => "EQ",
+        0x15 => "ISZERO",
+        0x16 => "AND",
+        0x17 => "OR",
+        0x18 => "XOR",
+        0x19 => "NOT",
+        0x1a => "BYTE",
+        0x1b => "SHL",
+        0x1c => "SHR",
+        0x1d => "SAR",
+        0x20 => "KECCAK256",
+        0x30 => "ADDRESS",
+        0x31 => "BALANCE",
+        0x32 => "ORIGIN",
+        0x33 => "CALLER",
+        0x34 => "CALLVALUE",
+        0x35 => "CALLDATALOAD",
+        0x36 => "CALLDATASIZE",
+        0x37 => "CALLDATACOPY",
+        0x38 => "CODESIZE",
+        0x39 => "CODECOPY",
+        0x3a => "GASPRICE",
+        0x3b => "EXTCODESIZE",
+        0x3c => "EXTCODECOPY",
+        0x3d => "RETURNDATASIZE",
+        0x3e => "RETURNDATACOPY",
+        0x3f => "EXTCODEHASH",
+        0x40 => "BLOCKHASH",
+        0x41 => "COINBASE",
+        0x42 => "TIMESTAMP",
+        0x43 => "NUMBER",
+        0x44 => "PREVRANDAO",
+        0x45 => "GASLIMIT",
+        0x46 => "CHAINID",
+        0x47 => "SELFBALANCE",
+        0x48 => "BASEFEE",
+        0x49 => "BLOBHASH",
+        0x4a => "BLOBBASEFEE",
+        0x50 => "POP",
+        0x51 => "MLOAD",
+        0x52 => "MSTORE",
+        0x53 => "MSTORE8",
+        0x54 => "SLOAD",
+        0x55 => "SSTORE",
+        0x56 => "JUMP",
+        0x57 => "JUMPI",
+        0x58 => "PC",
+        0x59 => "MSIZE",
+        0x5a => "GAS",
+        0x5b => "JUMPDEST",
+        0x5c => "TLOAD",
+        0x5d => "TSTORE",
+        0x5e => "MCOPY",
+        0x5f => "PUSH0",
+        0x60..=0x7f => return format!("PUSH{}", op - 0x5f),
+        0x80..=0x8f => return format!("DUP{}", op - 0x7f),
+        0x90..=0x9f => return format!("SWAP{}", op - 0x8f),
+        0xa0..=0xa4 => return format!("LOG{}", op - 0xa0),
+        0xf0 => "CREATE",
+        0xf1 => "CALL",
+        0xf2 => "CALLCODE",
+        0xf3 => "RETURN",
+        0xf4 => "DELEGATECALL",
+        0xf5 => "CREATE2",
+        0xfa => "STATICCALL",
+        0xfd => "REVERT",
+        0xfe => "INVALID",
+        0xff => "SELFDESTRUCT",
+        _ => return format!("UNKNOWN(0x{op:02x})"),
+    };
+    name.to_string()
+}
+
+pub fn disassemble(code: &[u8]) -> Vec<Instruction<'_>> {
+    let mut out = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let op = code[pc];
+        let size = if (PUSH1..=PUSH32).contains(&op) {
+            (op - PUSH0) as usize
+        } else {
+            0
+        };
+        let end = (pc + 1 + size).min(code.len());
+        out.push(Instruction {
+            pc,
+            opcode: op,
+            immediate: &code[pc + 1..end],
+        });
+        pc = end;
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub severity: Severity,
+    /// Function signature (or selector) the finding applies to, `None` for the whole contract.
+    pub function: Option<String>,
+    pub pc: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:?}]", self.severity)?;
+        if let Some(function) = &self.function {
+            write!(f, " {function}")?;
+        }
+        if let Some(pc) = self.pc {
+            write!(f, " @0x{pc:04x}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionSummary {
+    pub selector: Selector,
+    pub name: String,
+    pub checks_caller: bool,
+    /// Reaches a `STATICCALL`, which in practice is `ecrecover` or an ERC-1271 check.
+    pub verifies_signature: bool,
+    pub calls: bool,
+    pub delegatecalls: bool,
+    pub writes_storage: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub functions: Vec<FunctionSummary>,
+    pub accepts_plain_eth: bool,
+    pub has_fallback: bool,
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.findings.iter().map(|f| f.severity).max()
+    }
+}
+
+struct Block {
+    /// Instruction index range.
+    start: usize,
+    end: usize,
+    successors: Vec<usize>,
+}
+
+struct Cfg<'a> {
+    instructions: Vec<Instruction<'a>>,
+    blocks: Vec<Block>,
+    block_at_pc: HashMap<usize, usize>,
+}
+
+impl<'a> Cfg<'a> {
+    fn new(code: &'a [u8]) -> Self {
+        let instructions = disassemble(code);
+        let jumpdests: BTreeSet<usize> = instructions
+            .iter()
+            .filter(|i| i.opcode == JUMPDEST)
+            .map(|i| i.pc)
+            .collect();
+
+        let mut blocks = Vec::new();
+        let mut start = 0;
+        for (idx, ins) in instructions.iter().enumerate() {
+            let next_is_dest = instructions
+                .get(idx + 1)
+                .is_some_and(|n| n.opcode == JUMPDEST);
+            if ins.is_terminator() || next_is_dest || idx + 1 == instructions.len() {
+                blocks.push(Block {
+                    start,
+                    end: idx + 1,
+                    successors: Vec::new(),
+                });
+                start = idx + 1;
+            }
+        }
+
+        let block_at_pc: HashMap<usize, usize> = blocks
+            .iter()
+            .enumerate()
+            .map(|(b, block)| (instructions[block.start].pc, b))
+            .collect();
+
+        for b in 0..blocks.len() {
+            let body = &instructions[blocks[b].start..blocks[b].end];
+            let last = body[body.len() - 1];
+            let mut successors = Vec::new();
+            if (!last.is_terminator() || last.opcode == JUMPI) && b + 1 < blocks.len() {
+                successors.push(b + 1);
+            }
+            // Static jump targets and pushed return addresses of internal calls.
+            for ins in body {
+                if let Some(target) = ins.push_value().filter(|t| jumpdests.contains(t)) {
+                    successors.push(block_at_pc[&target]);
+                }
+            }
+            blocks[b].successors = successors;
+        }
+
+        Self {
+            instructions,
+            blocks,
+            block_at_pc,
+        }
+    }
+
+    fn reachable(&self, entry_pc: usize) -> BTreeSet<usize> {
+        let mut seen = BTreeSet::new();
+        let Some(&entry) = self.block_at_pc.get(&entry_pc) else {
+            return seen;
+        };
+        let mut queue = VecDeque::from([entry]);
+        while let Some(b) = queue.pop_front() {
+            if seen.insert(b) {
+                queue.extend(self.blocks[b].successors.iter().copied());
+            }
+        }
+        seen
+    }
+
+    fn instructions_in<'s>(
+        &'s self,
+        blocks: &'s BTreeSet<usize>,
+    ) -> impl Iterator<Item = &'s Instruction<'a>> + 's {
+        blocks
+            .iter()
+            .flat_map(|&b| &self.instructions[self.blocks[b].start..self.blocks[b].end])
+    }
+
+    /// `PUSH4 selector, EQ, PUSHn dest, JUMPI` as emitted by solc's dispatcher.
+    fn dispatch_entries(&self) -> Vec<(Selector, usize, usize)> {
+        self.instructions
+            .windows(4)
+            .enumerate()
+            .filter_map(|(idx, w)| {
+                if w[0].opcode != PUSH4 || w[1].opcode != EQ || w[3].opcode != JUMPI {
+                    return None;
+                }
+                let selector = Selector::from_slice(w[0].immediate);
+                Some((selector, w[2].push_value()?, idx + 3))
+            })
+            .collect()
+    }
+
+    /// Where execution continues for calldata shorter than a selector:
+    /// `PUSH1 4, CALLDATASIZE, LT, PUSHn dest, JUMPI`.
+    fn short_calldata_entry(&self) -> Option<usize> {
+        self.instructions.windows(5).find_map(|w| {
+            (w[0].push_value() == Some(4)
+                && w[1].opcode == CALLDATASIZE
+                && w[2].opcode == LT
+                && w[4].opcode == JUMPI)
+                .then(|| w[3].push_value())
+                .flatten()
+        })
+    }
+
+    /// Whether execution starting at instruction `idx` reverts before doing anything else.
+    fn reverts_immediately(&self, mut idx: usize) -> bool {
+        for _ in 0..32 {
+            let Some(ins) = self.instructions.get(idx) else {
+                return true;
+            };
+            match ins.opcode {
+                REVERT | INVALID => return true,
+                JUMPDEST => idx += 1,
+                op if op == DUP1 || ins.is_push() => idx += 1,
+                JUMP => {
+                    let target = idx
+                        .checked_sub(1)
+                        .and_then(|p| self.instructions[p].push_value());
+                    match target.and_then(|t| self.block_at_pc.get(&t)) {
+                        Some(&b) => idx = self.blocks[b].start,
+                        None => return false,
+                    }
+                }
+                _ => return false,
+            }
+        }
+        false
+    }
+
+    fn index_of_pc(&self, pc: usize) -> Option<usize> {
+        self.block_at_pc.get(&pc).map(|&b| self.blocks[b].start)
+    }
+}
+
+/// Analyzes runtime code. `abi` is only used to name functions.
+pub fn analyze(code: &[u8], abi: Option<&JsonAbi>) -> Report {
+    let code = strip_metadata(code);
+    let cfg = Cfg::new(code);
+    let mut report = Report::default();
+
+    let name_of = |selector: Selector| {
+        abi.and_then(|abi| abi.functions().find(|f| f.selector() == selector))
+            .map(|f| f.signature())
+            .unwrap_or_else(|| selector.to_string())
+    };
+
+    let entries = cfg.dispatch_entries();
+    for &(selector, entry_pc, _) in &entries {
+        let blocks = cfg.reachable(entry_pc);
+        let summary = summarize(&cfg, &blocks, selector, name_of(selector));
+        flag_function(&cfg, &blocks, &summary, &mut report.findings);
+        report.functions.push(summary);
+    }
+
+    // Code after the last selector comparison of each dispatcher chain runs for unknown
+    // selectors; code behind the short-calldata jump runs for plain transfers.
+    let chain_ends: Vec<usize> = entries
+        .iter()
+        .map(|&(_, _, jumpi)| jumpi + 1)
+        .filter(|&next| {
+            cfg.instructions
+                .get(next + 1)
+                .is_none_or(|i| i.opcode != PUSH4)
+        })
+        .collect();
+    report.has_fallback = chain_ends.iter().any(|&idx| !cfg.reverts_immediately(idx));
+    report.accepts_plain_eth = match cfg.short_calldata_entry() {
+        Some(pc) => cfg
+            .index_of_pc(pc)
+            .is_some_and(|idx| !cfg.reverts_immediately(idx)),
+        None => !code.is_empty() && !cfg.reverts_immediately(0),
+    };
+
+    if !report.accepts_plain_eth {
+        report.findings.push(Finding {
+            severity: Severity::Low,
+            function: None,
+            pc: None,
+            message: "no receive or fallback: plain ETH transfers to the delegated account revert"
+                .into(),
+        });
+    }
+
+    for ins in &cfg.instructions {
+        if ins.opcode == SELFDESTRUCT {
+            report.findings.push(Finding {
+                severity: Severity::High,
+                function: None,
+                pc: Some(ins.pc),
+                message: "SELFDESTRUCT present".into(),
+            });
+        }
+    }
+
+    report.findings.sort_by(|a, b| b.severity.cmp(&a.severity));
+    report
+}
+
+fn summarize(
+    cfg: &Cfg<'_>,
+    blocks: &BTreeSet<usize>,
+    selector: Selector,
+    name: String,
+) -> FunctionSummary {
+    let has = |op: u8| cfg.instructions_in(blocks).any(|i| i.opcode == op);
+    FunctionSummary {
+        selector,
+        name,
+        checks_caller: has(CALLER),
+        verifies_signature: has(STATICCALL),
+        calls: has(CALL),
+        delegatecalls: has(DELEGATECALL) || has(CALLCODE),
+        writes_storage: has(SSTORE),
+    }
+}
+
+fn flag_function(
+    cfg: &Cfg<'_>,
+    blocks: &BTreeSet<usize>,
+    summary: &FunctionSummary,
+    findings: &mut Vec<Finding>,
+) {
+    let guarded = summary.checks_caller || summary.verifies_signature;
+    let first = |op: u8| {
+        cfg.instructions_in(blocks)
+            .find(|i| i.opcode == op)
+            .map(|i| i.pc)
+    };
+    let mut flag = |severity, pc, message: &str| {
+        findings.push(Finding {
+            severity,
+            function: Some(summary.name.clone()),
+            pc,
+            message: message.into(),
+        })
+    };
+
+    if summary.calls && !guarded {
+        flag(
+            Severity::High,
+            first(CALL),
+            "externally callable CALL (can move the account's ETH) without a CALLER check or signature verification",
+        );
+    }
+    if summary.delegatecalls {
+        let reads_calldata = cfg
+            .instructions_in(blocks)
+            .any(|i| matches!(i.opcode, CALLDATALOAD | CALLDATACOPY));
+        if reads_calldata {
+            flag(
+                if guarded {
+                    Severity::Medium
+                } else {
+                    Severity::High
+                },
+                first(DELEGATECALL).or(first(CALLCODE)),
+                "DELEGATECALL to a target that may come from calldata",
+            );
+        }
+    }
+    if summary.writes_storage && !guarded {
+        flag(
+            Severity::Medium,
+            first(SSTORE),
+            "storage write without a CALLER check or signature verification",
+        );
+    }
+    if summary.checks_caller && !summary.verifies_signature {
+        let compares_self = cfg.instructions_in(blocks).any(|i| i.opcode == ADDRESS);
+        if !compares_self {
+            flag(
+                Severity::Info,
+                first(CALLER),
+                "msg.sender is checked but not against address(this); verify who may call",
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifacts::Artifact;
+
+    fn analyze_artifact(name: &str) -> Report {
+        let artifact = Artifact::load(name).unwrap();
+        analyze(&artifact.deployed_bytecode.object, Some(&artifact.abi))
+    }
+
+    #[test]
+    fn flags_unprotected_sponsored_transfer() {
+        let report = analyze_artifact("SponsoredTransfer");
+        let transfer = &report.functions[0];
+        assert_eq!(transfer.name, "transfer(address,address,uint256)");
+        assert!(transfer.calls && !transfer.checks_caller);
+        assert_eq!(report.max_severity(), Some(Severity::High));
+        assert!(!report.accepts_plain_eth);
+    }
+
+    #[test]
+    fn batch_executor_is_guarded() {
+        let report = analyze_artifact("BatchCallAndSponsor");
+        assert_eq!(report.functions.len(), 3);
+        assert!(report.accepts_plain_eth && report.has_fallback);
+        assert!(
+            report.max_severity() < Some(Severity::Medium),
+            "{report:#?}"
+        );
+    }
+}
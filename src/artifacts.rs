@@ -0,0 +1,342 @@
+//! Forge build artifacts.
+//!
+//! Artifacts are looked up in the committed `artifacts/` directory first and then in the
+//! `out/` directory of the Forge project the contract lives in, so a plain `forge build` is
+//! enough to use a contract that has not been committed yet.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use alloy::{
+    json_abi::JsonAbi,
+    network::TransactionBuilder,
+    primitives::{Address, Bytes},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+};
+use serde::Deserialize;
+
+use crate::{
+    decode::AbiRegistry,
+    error::{Error, Result},
+    layout::StorageLayout,
+    revert::DecodeRevert,
+};
+
+pub const ARTIFACTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/artifacts");
+pub const FORGE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/forge-contracts");
+
+/// Where a contract's Solidity source lives.
+#[derive(Debug, Clone, Copy)]
+pub struct Source {
+    pub contract: &'static str,
+    /// Forge project directory under `forge-contracts`.
+    pub project: &'static str,
+    /// Source file name under the project's `src` directory.
+    pub file: &'static str,
+}
+
+impl Source {
+    pub fn committed_path(&self) -> PathBuf {
+        PathBuf::from(ARTIFACTS_DIR).join(format!("{}.json", self.contract))
+    }
+
+    pub fn forge_out_path(&self) -> PathBuf {
+        PathBuf::from(FORGE_DIR)
+            .join(self.project)
+            .join("out")
+            .join(self.file)
+            .join(format!("{}.json", self.contract))
+    }
+}
+
+pub const SOURCES: &[Source] = &[
+    Source {
+        contract: "EventLogger",
+        project: "log-contract",
+        file: "Counter.sol",
+    },
+    Source {
+        contract: "SimpleTransfer",
+        project: "simple-transfer",
+        file: "Counter.sol",
+    },
+    Source {
+        contract: "SponsoredTransfer",
+        project: "simple-transfer",
+        file: "SponsoredTransfer.sol",
+    },
+    Source {
+        contract: "BatchCallAndSponsor",
+        project: "batch-call",
+        file: "BatchCallAndSponsor.sol",
+    },
+    Source {
+        contract: "SimpleTransferHardened",
+        project: "simple-transfer",
+        file: "SimpleTransferHardened.sol",
+    },
+    Source {
+        contract: "SponsoredTransferHardened",
+        project: "simple-transfer",
+        file: "SponsoredTransferHardened.sol",
+    },
+    Source {
+        contract: "BatchCallAndSponsorHardened",
+        project: "batch-call",
+        file: "BatchCallAndSponsorHardened.sol",
+    },
+    Source {
+        contract: "DomainBatchExecutor",
+        project: "batch-call",
+        file: "DomainBatchExecutor.sol",
+    },
+    Source {
+        contract: "TypedBatchExecutor",
+        project: "batch-call",
+        file: "TypedBatchExecutor.sol",
+    },
+    Source {
+        contract: "ReportingBatchExecutor",
+        project: "batch-call",
+        file: "ReportingBatchExecutor.sol",
+    },
+    Source {
+        contract: "ERC7821Executor",
+        project: "batch-call",
+        file: "ERC7821Executor.sol",
+    },
+    Source {
+        contract: "MinimalEntryPoint",
+        project: "batch-call",
+        file: "MinimalEntryPoint.sol",
+    },
+    Source {
+        contract: "Simple7702Account",
+        project: "batch-call",
+        file: "Simple7702Account.sol",
+    },
+    Source {
+        contract: "SessionKeyDelegate",
+        project: "batch-call",
+        file: "SessionKeyDelegate.sol",
+    },
+    Source {
+        contract: "ThresholdDelegate",
+        project: "batch-call",
+        file: "ThresholdDelegate.sol",
+    },
+    Source {
+        contract: "RecoveryDelegate",
+        project: "batch-call",
+        file: "RecoveryDelegate.sol",
+    },
+    Source {
+        contract: "P256Delegate",
+        project: "batch-call",
+        file: "P256Delegate.sol",
+    },
+];
+
+pub fn source(contract: &str) -> Option<&'static Source> {
+    SOURCES.iter().find(|s| s.contract == contract)
+}
+
+/// Every artifact available locally: the committed ones plus Forge builds of known sources.
+pub fn all() -> Result<Vec<(String, Artifact)>> {
+    let io = |source| Error::Io {
+        path: ARTIFACTS_DIR.into(),
+        source,
+    };
+    let mut found = Vec::new();
+    for entry in std::fs::read_dir(ARTIFACTS_DIR).map_err(io)? {
+        let path = entry.map_err(io)?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            found.push((name, Artifact::from_path(&path)?));
+        }
+    }
+    for source in SOURCES {
+        let out = source.forge_out_path();
+        if out.exists() && !found.iter().any(|(name, _)| name == source.contract) {
+            found.push((source.contract.to_string(), Artifact::from_path(out)?));
+        }
+    }
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(found)
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ImmutableReference {
+    pub start: usize,
+    pub length: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BytecodeObject {
+    pub object: Bytes,
+    /// Byte ranges of the runtime code filled in by the constructor, keyed by AST id.
+    #[serde(default)]
+    pub immutable_references: BTreeMap<String, Vec<ImmutableReference>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    pub abi: JsonAbi,
+    pub bytecode: BytecodeObject,
+    pub deployed_bytecode: BytecodeObject,
+    /// Only present when built with `extra_output = ["storageLayout"]`.
+    #[serde(default)]
+    pub storage_layout: Option<StorageLayout>,
+}
+
+impl Artifact {
+    pub fn from_path(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let raw = match std::fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(source) => return Err(Error::Io { path, source }),
+        };
+        serde_json::from_str(&raw).map_err(|source| Error::Json { path, source })
+    }
+
+    /// Loads the artifact of `contract`, preferring the committed copy over the Forge output.
+    pub fn load(contract: &str) -> Result<Self> {
+        let committed = PathBuf::from(ARTIFACTS_DIR).join(format!("{contract}.json"));
+        if committed.exists() {
+            return Self::from_path(committed);
+        }
+        let source = source(contract)
+            .ok_or_else(|| Error::Config(format!("no artifact or known source for {contract}")))?;
+        let out = source.forge_out_path();
+        if !out.exists() {
+            return Err(Error::Config(format!(
+                "artifact for {contract} not found, run `forge build` in forge-contracts/{}",
+                source.project
+            )));
+        }
+        Self::from_path(out)
+    }
+}
+
+/// Functions, events and errors of `binding` that `artifact` does not declare the same way.
+///
+/// Hand-written bindings may leave items out, so only what the binding declares is checked.
+/// Parameter names are ignored; types, return types and `indexed` flags are not.
+pub fn missing_from(binding: &JsonAbi, artifact: &JsonAbi) -> Vec<String> {
+    let event = |event: &alloy::json_abi::Event| {
+        let indexed: Vec<_> = event.inputs.iter().map(|input| input.indexed).collect();
+        format!("event {} indexed {indexed:?}", event.signature())
+    };
+    let functions: Vec<_> = artifact
+        .functions()
+        .map(|function| function.signature_with_outputs())
+        .collect();
+    let events: Vec<_> = artifact.events().map(event).collect();
+    let errors: Vec<_> = artifact.errors().map(|error| error.signature()).collect();
+
+    let mut missing = Vec::new();
+    for function in binding.functions() {
+        if !functions.contains(&function.signature_with_outputs()) {
+            missing.push(format!("function {}", function.signature_with_outputs()));
+        }
+    }
+    for declared in binding.events() {
+        if !events.contains(&event(declared)) {
+            missing.push(format!("event {}", declared.signature()));
+        }
+    }
+    for error in binding.errors() {
+        if !errors.contains(&error.signature()) {
+            missing.push(format!("error {}", error.signature()));
+        }
+    }
+    missing
+}
+
+/// Strips the CBOR-encoded metadata solc appends to runtime and creation code.
+///
+/// The last two bytes hold the length of the CBOR section; code that does not end in a
+/// plausible section is returned unchanged.
+pub fn strip_metadata(code: &[u8]) -> &[u8] {
+    let Some(len_bytes) = code.len().checked_sub(2).map(|i| &code[i..]) else {
+        return code;
+    };
+    let cbor_len = u16::from_be_bytes([len_bytes[0], len_bytes[1]]) as usize;
+    match code.len().checked_sub(cbor_len + 2) {
+        // CBOR maps of up to 23 entries start with 0xa0 + n.
+        Some(start) if (0xa1..=0xb7).contains(&code[start]) => &code[..start],
+        _ => code,
+    }
+}
+
+/// Deploys `contract` from its artifact and returns the new address.
+pub async fn deploy<P: Provider>(provider: &P, contract: &str) -> Result<Address> {
+    deploy_with_args(provider, contract, &[]).await
+}
+
+/// Deploys `contract` with ABI-encoded constructor arguments.
+pub async fn deploy_with_args<P: Provider>(
+    provider: &P,
+    contract: &str,
+    args: &[u8],
+) -> Result<Address> {
+    let artifact = Artifact::load(contract)?;
+    let code = [&artifact.bytecode.object[..], args].concat();
+    let tx = TransactionRequest::default().with_deploy_code(code);
+    let mut abis = AbiRegistry::default();
+    abis.add(&artifact.abi);
+    let receipt = provider
+        .send_transaction(tx)
+        .await
+        .decode_revert(&abis, &format!("deploying {contract}"))?
+        .get_receipt()
+        .await?;
+    receipt.contract_address.ok_or_else(|| {
+        Error::UnexpectedResponse(format!("deploying {contract} did not create a contract"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_solc_metadata() {
+        let artifact = Artifact::load("EventLogger").unwrap();
+        let code = &artifact.deployed_bytecode.object;
+        let stripped = strip_metadata(code);
+
+        // ipfs hash + solc version: a2 64 'ipfs' 58 22 <34 bytes> 64 'solc' 43 <3 bytes> 00 33
+        assert_eq!(code.len() - stripped.len(), 0x33 + 2);
+        assert_eq!(stripped.last(), Some(&0xfe));
+    }
+
+    #[test]
+    fn finds_binding_items_missing_from_the_artifact() {
+        let full = Artifact::load("EventLogger").unwrap().abi;
+        let mut partial = full.clone();
+        partial.functions.remove("emitHello");
+        assert!(missing_from(&partial, &full).is_empty());
+        assert_eq!(missing_from(&full, &partial), ["function emitHello()()"]);
+
+        let mut renamed = full.clone();
+        fn input(abi: &mut JsonAbi) -> &mut alloy::json_abi::Param {
+            &mut abi.functions.get_mut("emitWorld").unwrap()[0].inputs[0]
+        }
+        input(&mut renamed).name = "name".into();
+        assert!(missing_from(&renamed, &full).is_empty());
+        input(&mut renamed).ty = "bytes".into();
+        assert_eq!(
+            missing_from(&renamed, &full),
+            ["function emitWorld(bytes)()"]
+        );
+    }
+
+    #[test]
+    fn leaves_code_without_metadata_alone() {
+        assert_eq!(strip_metadata(&[0x60, 0x00]), &[0x60, 0x00]);
+        assert_eq!(strip_metadata(&[]), &[] as &[u8]);
+    }
+}
@@ -0,0 +1,51 @@
+//! Signing and inspecting EIP-7702 authorizations.
+
+use alloy::{
+    eips::eip7702::{Authorization, SignedAuthorization},
+    primitives::{Address, U256},
+    signers::{SignerSync, local::PrivateKeySigner},
+};
+
+use crate::error::Result;
+
+/// Code prefix of an account delegated through EIP-7702 (`0xef0100 ‖ address`).
+pub const DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+/// Signs an authorization without consulting the delegate registry.
+///
+/// Delegate accounts with [`DelegateRegistry::sign_authorization`] and clear them with
+/// [`sign_revocation`]. This is only for code that points accounts at unapproved delegates on
+/// purpose, such as the drain probe.
+///
+/// [`DelegateRegistry::sign_authorization`]: crate::registry::DelegateRegistry::sign_authorization
+pub fn sign_unchecked(
+    signer: &PrivateKeySigner,
+    chain_id: u64,
+    address: Address,
+    nonce: u64,
+) -> Result<SignedAuthorization> {
+    let auth = Authorization {
+        chain_id: U256::from(chain_id),
+        address,
+        nonce,
+    };
+    let sig = signer.sign_hash_sync(&auth.signature_hash())?;
+    Ok(auth.into_signed(sig))
+}
+
+/// Signs an authorization to the zero address, which clears the account's code.
+pub fn sign_revocation(
+    signer: &PrivateKeySigner,
+    chain_id: u64,
+    nonce: u64,
+) -> Result<SignedAuthorization> {
+    sign_unchecked(signer, chain_id, Address::ZERO, nonce)
+}
+
+/// Returns the delegate address if `code` is an EIP-7702 delegation designator.
+pub fn delegated_to(code: &[u8]) -> Option<Address> {
+    match code.strip_prefix(&DELEGATION_PREFIX) {
+        Some(rest) if rest.len() == 20 => Some(Address::from_slice(rest)),
+        _ => None,
+    }
+}
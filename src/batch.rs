@@ -0,0 +1,406 @@
+//! Signatures for the batch executors.
+//!
+//! `BatchCallAndSponsor` recovers the account from an EIP-191 signature over
+//! `keccak256(abi.encodePacked(nonce, calls))`, where each call is packed as
+//! `to ‖ value ‖ data`. That packing is ambiguous: the bytes of one call's data can be read as
+//! further calls. `BatchCallAndSponsorHardened` instead signs the chain id, the account, the
+//! nonce and the hash of the ABI-encoded calls.
+//! `DomainBatchExecutor` hashes the calls the same way and also adds a deadline, see
+//! [`DomainBatch`].
+//!
+//! [`Batch`] builds the calldata of a batch for `BatchCallAndSponsor`, its hardened variant or an
+//! ERC-7821 executor, so callers do not depend on the interface the account is delegated to.
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{Address, B256, Bytes, Signature, U256, keccak256},
+    providers::Provider,
+    signers::{SignerSync, local::PrivateKeySigner},
+    sol_types::{SolCall, SolValue},
+};
+
+use crate::{
+    contracts::{
+        BatchCallAndSponsor::{self, Call},
+        BatchCallAndSponsorHardened, DomainBatchExecutor,
+    },
+    erc7821::{self, Execution},
+    error::{Error, Result},
+};
+
+/// `abi.encode(calls)`, hashed by `BatchCallAndSponsorHardened` and `DomainBatchExecutor`.
+pub fn encode_calls(calls: &[Call]) -> Vec<u8> {
+    calls.abi_encode()
+}
+
+/// The calls packed as `to ‖ value ‖ data`, the way `BatchCallAndSponsor` hashes them.
+fn pack_calls(calls: &[Call]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for call in calls {
+        out.extend_from_slice(call.to.as_slice());
+        out.extend_from_slice(&call.value.to_be_bytes::<32>());
+        out.extend_from_slice(&call.data);
+    }
+    out
+}
+
+/// Digest signed for `BatchCallAndSponsor`. It is valid on every chain and for every executor
+/// that uses the same scheme, as long as the account's nonce matches.
+pub fn digest(nonce: U256, calls: &[Call]) -> B256 {
+    let mut packed = nonce.to_be_bytes::<32>().to_vec();
+    packed.extend(pack_calls(calls));
+    keccak256(packed)
+}
+
+/// Digest signed for `BatchCallAndSponsorHardened`:
+/// `keccak256(abi.encode(chainid, account, nonce, keccak256(abi.encode(calls))))`.
+pub fn bound_digest(chain_id: u64, account: Address, nonce: U256, calls: &[Call]) -> B256 {
+    keccak256(
+        (
+            U256::from(chain_id),
+            account,
+            nonce,
+            keccak256(encode_calls(calls)),
+        )
+            .abi_encode(),
+    )
+}
+
+/// Signs `digest` as a personal message, as the executors expect.
+pub fn sign(signer: &PrivateKeySigner, digest: B256) -> Result<Bytes> {
+    Ok(signer
+        .sign_message_sync(digest.as_slice())?
+        .as_bytes()
+        .into())
+}
+
+/// A batch for `DomainBatchExecutor`, valid on one chain, for one account, until a deadline.
+#[derive(Clone)]
+pub struct DomainBatch {
+    pub chain_id: u64,
+    pub account: Address,
+    /// The account's current `DomainBatchExecutor.nonce()`.
+    pub nonce: U256,
+    /// Last block timestamp at which the signature is accepted.
+    pub valid_until: u64,
+    pub calls: Vec<Call>,
+}
+
+impl DomainBatch {
+    /// `keccak256(abi.encode(chainid, account, nonce, validUntil, keccak256(abi.encode(calls))))`.
+    pub fn digest(&self) -> B256 {
+        self.digest_on(self.chain_id)
+    }
+
+    fn digest_on(&self, chain_id: u64) -> B256 {
+        keccak256(
+            (
+                U256::from(chain_id),
+                self.account,
+                self.nonce,
+                U256::from(self.valid_until),
+                keccak256(encode_calls(&self.calls)),
+            )
+                .abi_encode(),
+        )
+    }
+
+    pub fn sign(&self, signer: &PrivateKeySigner) -> Result<Bytes> {
+        sign(signer, self.digest())
+    }
+
+    /// Checks `signature` the way the executor on `chain_id` would in a block at `now`.
+    pub fn verify(&self, signature: &[u8], chain_id: u64, now: u64) -> Result<()> {
+        if now > self.valid_until {
+            return Err(Error::Expired {
+                valid_until: self.valid_until,
+                now,
+            });
+        }
+        let signer = Signature::from_raw(signature)
+            .and_then(|sig| sig.recover_address_from_msg(self.digest_on(chain_id)))
+            .map_err(|err| Error::InvalidSignature(err.to_string()))?;
+        if signer != self.account {
+            return Err(Error::InvalidSignature(format!(
+                "recovered {signer} on chain {chain_id}, expected {}",
+                self.account
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn call(&self, signature: Bytes) -> DomainBatchExecutor::execute_1Call {
+        DomainBatchExecutor::execute_1Call {
+            calls: self.calls.iter().cloned().map(Into::into).collect(),
+            validUntil: U256::from(self.valid_until),
+            signature,
+        }
+    }
+}
+
+/// The timestamp of the latest block, the `now` that deadlines such as
+/// [`DomainBatch::valid_until`] are checked against.
+pub async fn latest_timestamp<P: Provider>(provider: &P) -> Result<u64> {
+    let block = provider
+        .get_block_by_number(BlockNumberOrTag::Latest)
+        .await
+        .map_err(Error::rpc)?
+        .ok_or_else(|| Error::UnexpectedResponse("no latest block".into()))?;
+    Ok(block.header.timestamp)
+}
+
+/// The interface of the executor an account is delegated to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Executor {
+    /// `execute(Call[])`, or `execute(Call[], bytes)` signed over [`digest`].
+    BatchCallAndSponsor,
+    /// The same interface, signed over [`bound_digest`].
+    BatchCallAndSponsorHardened,
+    /// ERC-7821 `execute(bytes32, bytes)`, signed over [`erc7821::digest`] as `opData`.
+    Erc7821 { try_mode: bool },
+}
+
+impl Executor {
+    /// Whether a signed batch is valid only on the chain and for the account it was signed
+    /// for. `BatchCallAndSponsor` signatures replay on other chains and other accounts.
+    pub fn binds_account(&self) -> bool {
+        !matches!(self, Self::BatchCallAndSponsor)
+    }
+}
+
+/// A batch for whichever executor the account uses. Its calldata goes to the account itself.
+#[derive(Clone)]
+pub struct Batch {
+    pub executor: Executor,
+    pub calls: Vec<Call>,
+}
+
+impl Batch {
+    /// Calldata for the account executing the batch from itself.
+    pub fn direct(&self) -> Bytes {
+        match self.executor {
+            Executor::BatchCallAndSponsor => BatchCallAndSponsor::execute_0Call {
+                calls: self.calls.clone(),
+            }
+            .abi_encode(),
+            Executor::BatchCallAndSponsorHardened => BatchCallAndSponsorHardened::execute_0Call {
+                calls: self.calls.iter().cloned().map(Into::into).collect(),
+            }
+            .abi_encode(),
+            Executor::Erc7821 { try_mode } => Execution {
+                try_mode,
+                calls: self.calls.clone(),
+                op_data: None,
+            }
+            .call()
+            .abi_encode(),
+        }
+        .into()
+    }
+
+    /// Calldata any sponsor can submit, signed by the account at the executor's `nonce()`.
+    pub fn sponsored(
+        &self,
+        signer: &PrivateKeySigner,
+        chain_id: u64,
+        nonce: U256,
+    ) -> Result<Bytes> {
+        Ok(match self.executor {
+            Executor::BatchCallAndSponsor => BatchCallAndSponsor::execute_1Call {
+                calls: self.calls.clone(),
+                signature: sign(signer, digest(nonce, &self.calls))?,
+            }
+            .abi_encode(),
+            Executor::BatchCallAndSponsorHardened => BatchCallAndSponsorHardened::execute_1Call {
+                calls: self.calls.iter().cloned().map(Into::into).collect(),
+                signature: sign(
+                    signer,
+                    bound_digest(chain_id, signer.address(), nonce, &self.calls),
+                )?,
+            }
+            .abi_encode(),
+            Executor::Erc7821 { try_mode } => {
+                erc7821::sign(signer, chain_id, nonce, try_mode, self.calls.clone())?
+                    .call()
+                    .abi_encode()
+            }
+        }
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, bytes};
+
+    #[test]
+    fn packs_like_solidity() {
+        let calls = vec![
+            Call {
+                to: address!("0x00000000000000000000000000000000000000d1"),
+                value: U256::from(1),
+                data: Bytes::new(),
+            },
+            Call {
+                to: address!("0x00000000000000000000000000000000000000d2"),
+                value: U256::ZERO,
+                data: bytes!("0xdeadbeef"),
+            },
+        ];
+        let account = address!("0x00000000000000000000000000000000000a11ce");
+        let nonce = U256::from(7);
+
+        let packed = (
+            nonce,
+            calls[0].to,
+            calls[0].value,
+            calls[0].data.clone(),
+            calls[1].to,
+            calls[1].value,
+            calls[1].data.clone(),
+        )
+            .abi_encode_packed();
+        assert_eq!(digest(nonce, &calls), keccak256(&packed));
+
+        // `abi.encode(calls)` of a single dynamic array starts with its offset.
+        let encoded = encode_calls(&calls);
+        assert_eq!(U256::from_be_slice(&encoded[..32]), U256::from(32));
+        let bound = (U256::from(31337), account, nonce, keccak256(&encoded)).abi_encode();
+        assert_eq!(
+            bound_digest(31337, account, nonce, &calls),
+            keccak256(bound)
+        );
+        // Packed, the second call can hide in the first one's data; encoded, it cannot.
+        let merged = vec![Call {
+            data: (calls[1].to, calls[1].value, calls[1].data.clone())
+                .abi_encode_packed()
+                .into(),
+            ..calls[0].clone()
+        }];
+        assert_eq!(digest(nonce, &calls), digest(nonce, &merged));
+        assert_ne!(
+            bound_digest(31337, account, nonce, &calls),
+            bound_digest(31337, account, nonce, &merged)
+        );
+        let domain = DomainBatch {
+            chain_id: 31337,
+            account,
+            nonce,
+            valid_until: 1_000,
+            calls: calls.clone(),
+        };
+        assert_eq!(
+            domain.digest(),
+            keccak256(
+                (
+                    U256::from(31337),
+                    account,
+                    nonce,
+                    U256::from(1_000),
+                    keccak256(&encoded)
+                )
+                    .abi_encode()
+            )
+        );
+        assert_ne!(
+            bound_digest(31337, account, nonce, &calls),
+            bound_digest(1, account, nonce, &calls)
+        );
+    }
+
+    #[test]
+    fn rejects_cross_chain_and_expired_batches() {
+        let alice = PrivateKeySigner::random();
+        let batch = DomainBatch {
+            chain_id: 31337,
+            account: alice.address(),
+            nonce: U256::ZERO,
+            valid_until: 1_000,
+            calls: vec![Call {
+                to: address!("0x00000000000000000000000000000000000000d1"),
+                value: U256::from(1),
+                data: Bytes::new(),
+            }],
+        };
+        let signature = batch.sign(&alice).unwrap();
+
+        batch.verify(&signature, 31337, 1_000).unwrap();
+        assert!(matches!(
+            batch.verify(&signature, 1, 999),
+            Err(Error::InvalidSignature(_))
+        ));
+        assert!(matches!(
+            batch.verify(&signature, 31337, 1_001),
+            Err(Error::Expired {
+                valid_until: 1_000,
+                now: 1_001
+            })
+        ));
+
+        let other = PrivateKeySigner::random();
+        let for_other = DomainBatch {
+            account: other.address(),
+            ..batch.clone()
+        };
+        assert!(for_other.verify(&signature, 31337, 0).is_err());
+    }
+
+    #[test]
+    fn targets_either_executor() {
+        let alice = PrivateKeySigner::random();
+        let calls = vec![Call {
+            to: address!("0x00000000000000000000000000000000000000d1"),
+            value: U256::from(1),
+            data: bytes!("0xdeadbeef"),
+        }];
+        let nonce = U256::from(2);
+
+        let legacy = Batch {
+            executor: Executor::BatchCallAndSponsor,
+            calls: calls.clone(),
+        };
+        let call = BatchCallAndSponsor::execute_1Call::abi_decode(
+            &legacy.sponsored(&alice, 1, nonce).unwrap(),
+        )
+        .unwrap();
+        let signer = Signature::from_raw(&call.signature)
+            .unwrap()
+            .recover_address_from_msg(digest(nonce, &calls))
+            .unwrap();
+        assert_eq!(signer, alice.address());
+        assert!(!legacy.executor.binds_account());
+
+        let hardened = Batch {
+            executor: Executor::BatchCallAndSponsorHardened,
+            calls: calls.clone(),
+        };
+        let call = BatchCallAndSponsorHardened::execute_1Call::abi_decode(
+            &hardened.sponsored(&alice, 1, nonce).unwrap(),
+        )
+        .unwrap();
+        let signer = Signature::from_raw(&call.signature)
+            .unwrap()
+            .recover_address_from_msg(bound_digest(1, alice.address(), nonce, &calls))
+            .unwrap();
+        assert_eq!(signer, alice.address());
+        assert_eq!(hardened.direct(), legacy.direct());
+
+        let batch = Batch {
+            executor: Executor::Erc7821 { try_mode: true },
+            calls: calls.clone(),
+        };
+        let direct = Execution::decode_calldata(&batch.direct()).unwrap();
+        assert_eq!(direct.mode(), erc7821::Mode::TRY_BATCH);
+
+        let signed =
+            Execution::decode_calldata(&batch.sponsored(&alice, 1, nonce).unwrap()).unwrap();
+        assert_eq!(signed.mode(), erc7821::Mode::TRY_BATCH_WITH_OP_DATA);
+        let digest = erc7821::digest(1, alice.address(), nonce, signed.mode(), &calls);
+        let signer = Signature::from_raw(signed.op_data.as_deref().unwrap())
+            .unwrap()
+            .recover_address_from_msg(digest)
+            .unwrap();
+        assert_eq!(signer, alice.address());
+    }
+}
@@ -0,0 +1,213 @@
+//! Batches with optional calls and a per-call report, for `ReportingBatchExecutor`.
+//!
+//! Every call of a batch emits `CallResult(index, to, success, returnData)`. After the receipt
+//! arrives, [`report`] turns those events into one [`CallReport`] per call, with revert data
+//! decoded against the known ABIs. When a required call fails the whole batch reverts with
+//! `CallFailed(index, reason)`, which [`failed_call`] unpacks the same way.
+
+use std::fmt;
+
+use alloy::{
+    primitives::{Address, B256, Bytes, Log, U256, keccak256},
+    rpc::types::TransactionReceipt,
+    signers::local::PrivateKeySigner,
+    sol,
+    sol_types::{SolError, SolEvent, SolValue},
+};
+
+use crate::{batch, decode::AbiRegistry, error::Result, revert::Revert};
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc, abi)]
+    interface ReportingBatchExecutor {
+        struct Call {
+            address to;
+            uint256 value;
+            bytes data;
+            bool allowFailure;
+        }
+
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
+
+        event CallResult(uint256 indexed index, address indexed to, bool success, bytes returnData);
+        event BatchExecuted(uint256 indexed nonce, uint256 failed);
+
+        error CallFailed(uint256 index, bytes reason);
+
+        function nonce() external view returns (uint256);
+
+        function execute(Call[] calldata calls) external payable returns (Result[] memory);
+
+        function execute(Call[] calldata calls, bytes calldata signature)
+            external
+            payable
+            returns (Result[] memory);
+    }
+);
+
+pub use ReportingBatchExecutor::Call;
+
+/// A signed batch for an account delegated to `ReportingBatchExecutor`.
+#[derive(Clone)]
+pub struct ReportedBatch {
+    pub chain_id: u64,
+    pub account: Address,
+    /// The account's current `ReportingBatchExecutor.nonce()`.
+    pub nonce: U256,
+    pub calls: Vec<Call>,
+}
+
+impl ReportedBatch {
+    /// `keccak256(abi.encode(chainid, account, nonce, calls))`.
+    pub fn digest(&self) -> B256 {
+        keccak256(
+            (
+                U256::from(self.chain_id),
+                self.account,
+                self.nonce,
+                self.calls.clone(),
+            )
+                .abi_encode_params(),
+        )
+    }
+
+    pub fn sign(&self, signer: &PrivateKeySigner) -> Result<Bytes> {
+        batch::sign(signer, self.digest())
+    }
+
+    pub fn call(&self, signature: Bytes) -> ReportingBatchExecutor::execute_1Call {
+        ReportingBatchExecutor::execute_1Call {
+            calls: self.calls.clone(),
+            signature,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallReport {
+    pub index: usize,
+    /// `None` when taken from a `CallFailed` revert, which does not carry the target.
+    pub to: Option<Address>,
+    pub success: bool,
+    /// Return data, or the revert data of a failed call.
+    pub output: Bytes,
+    /// The decoded revert of a failed call.
+    pub revert: Option<Revert>,
+}
+
+impl fmt::Display for CallReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.index)?;
+        if let Some(to) = self.to {
+            write!(f, " {to}")?;
+        }
+        match &self.revert {
+            None => write!(f, " ok {}", self.output),
+            Some(revert) => write!(f, " failed: {revert}"),
+        }
+    }
+}
+
+/// The `ReportingBatchExecutor` ABI, so that `CallFailed` decodes without a Forge build.
+pub fn abi() -> alloy::json_abi::JsonAbi {
+    ReportingBatchExecutor::abi::contract()
+}
+
+/// Per-call results of the batches `account` executed in `receipt`, in call order.
+pub fn report(
+    receipt: &TransactionReceipt,
+    account: Address,
+    abis: &AbiRegistry,
+) -> Vec<CallReport> {
+    let logs: Vec<Log> = receipt
+        .inner
+        .logs()
+        .iter()
+        .map(|log| log.inner.clone())
+        .collect();
+    report_logs(&logs, account, abis)
+}
+
+pub fn report_logs(logs: &[Log], account: Address, abis: &AbiRegistry) -> Vec<CallReport> {
+    logs.iter()
+        .filter(|log| log.address == account)
+        .filter_map(|log| ReportingBatchExecutor::CallResult::decode_log_data(&log.data).ok())
+        .map(|event| CallReport {
+            index: event.index.saturating_to(),
+            to: Some(event.to),
+            success: event.success,
+            revert: (!event.success).then(|| abis.decode_revert(&event.returnData)),
+            output: event.returnData,
+        })
+        .collect()
+}
+
+/// Unpacks the revert data of a batch that reverted because a required call failed.
+pub fn failed_call(data: &[u8], abis: &AbiRegistry) -> Option<CallReport> {
+    let failed = ReportingBatchExecutor::CallFailed::abi_decode(data).ok()?;
+    Some(CallReport {
+        index: failed.index.saturating_to(),
+        to: None,
+        success: false,
+        revert: Some(abis.decode_revert(&failed.reason)),
+        output: failed.reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{primitives::address, sol_types::Revert as RevertReason};
+
+    #[test]
+    fn reports_each_call() {
+        let account = address!("0x00000000000000000000000000000000000a11ce");
+        let target = address!("0x00000000000000000000000000000000000000d1");
+        let reason = RevertReason::from("Invalid authority").abi_encode();
+        let event = |index: u64, success: bool, data: Vec<u8>| Log {
+            address: account,
+            data: ReportingBatchExecutor::CallResult {
+                index: U256::from(index),
+                to: target,
+                success,
+                returnData: data.into(),
+            }
+            .encode_log_data(),
+        };
+        let mut logs = vec![event(0, true, vec![1]), event(1, false, reason.clone())];
+        // Events of other accounts are not part of this batch.
+        logs.push(Log {
+            address: target,
+            ..event(2, true, vec![])
+        });
+
+        let abis = AbiRegistry::default();
+        let report = report_logs(&logs, account, &abis);
+        assert_eq!(report.len(), 2);
+        assert!(report[0].success);
+        assert_eq!(
+            report[1].revert,
+            Some(Revert::Reason("Invalid authority".into()))
+        );
+        assert_eq!(
+            report[1].to_string(),
+            format!("#1 {target} failed: Error(\"Invalid authority\")")
+        );
+
+        let reverted = ReportingBatchExecutor::CallFailed {
+            index: U256::from(2),
+            reason: reason.into(),
+        }
+        .abi_encode();
+        let failed = failed_call(&reverted, &abis).unwrap();
+        assert_eq!(failed.index, 2);
+        assert_eq!(
+            failed.revert,
+            Some(Revert::Reason("Invalid authority".into()))
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! Flags risky patterns in a delegate's runtime code before an EOA is pointed at it.
+//!
+//! Usage: `analyze_delegate <address | contract> [--disasm]`
+//!
+//! An address is fetched from `RPC_URL` (default `http://localhost:8545`), following an
+//! EIP-7702 delegation; anything else is loaded as a local artifact. Exits with an error if a
+//! high-severity finding is reported.
+
+use alloy::{primitives::Address, providers::ProviderBuilder};
+use interact_eth::{
+    analyzer::{Severity, analyze, disassemble},
+    artifacts::{self, Artifact},
+    verify::verify_code,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let disasm = args.iter().any(|a| a == "--disasm");
+    let Some(target) = args.iter().find(|a| !a.starts_with("--")) else {
+        anyhow::bail!("usage: analyze_delegate <address | contract> [--disasm]");
+    };
+
+    // Name functions from a matching artifact's ABI when there is one.
+    let (code, abi) = match target.parse::<Address>() {
+        Ok(address) => {
+            let rpc_url =
+                std::env::var("RPC_URL").unwrap_or_else(|_| "http://localhost:8545".into());
+            let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+            let candidates = artifacts::all()?;
+            let verification = verify_code(&provider, address, &candidates).await?;
+            if let Some(delegate) = verification.delegate {
+                println!("{address} delegates to {delegate}");
+            }
+            let abi = verification.matched.and_then(|(name, _)| {
+                println!("code matches {name}");
+                candidates
+                    .into_iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, a)| a.abi)
+            });
+            (verification.code, abi)
+        }
+        Err(_) => {
+            let artifact = Artifact::load(target)?;
+            (artifact.deployed_bytecode.object, Some(artifact.abi))
+        }
+    };
+
+    if disasm {
+        for ins in disassemble(&code) {
+            println!("{ins}");
+        }
+        println!();
+    }
+
+    let report = analyze(&code, abi.as_ref());
+
+    println!("functions:");
+    for f in &report.functions {
+        let mut traits = Vec::new();
+        if f.checks_caller {
+            traits.push("checks caller");
+        }
+        if f.verifies_signature {
+            traits.push("verifies signature");
+        }
+        if f.calls {
+            traits.push("calls");
+        }
+        if f.delegatecalls {
+            traits.push("delegatecalls");
+        }
+        if f.writes_storage {
+            traits.push("writes storage");
+        }
+        println!("  {} {} [{}]", f.selector, f.name, traits.join(", "));
+    }
+    println!("receives plain ETH: {}", report.accepts_plain_eth);
+    println!("fallback:           {}", report.has_fallback);
+
+    println!("findings:");
+    if report.findings.is_empty() {
+        println!("  none");
+    }
+    for finding in &report.findings {
+        println!("  {finding}");
+    }
+
+    anyhow::ensure!(
+        report.max_severity() < Some(Severity::High),
+        "high-severity findings"
+    );
+    Ok(())
+}
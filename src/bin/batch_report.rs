@@ -0,0 +1,121 @@
+//! Example: a sponsored batch with an optional call that fails, and the per-call report.
+//!
+//! Spawns Anvil, delegates Alice to `ReportingBatchExecutor` (run `make contracts` first) and
+//! has Bob submit a batch that pays Dave, calls `EventLogger` and makes a call that reverts
+//! but is marked `allowFailure`. The same batch with that call required is then rejected with
+//! the index and reason of the failing call.
+//!
+//! Usage: cargo run --bin batch_report
+
+use alloy::{
+    node_bindings::Anvil,
+    primitives::{Bytes, U256},
+    providers::{Provider, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+    sol_types::SolCall,
+};
+use interact_eth::{
+    artifacts,
+    batch_report::{self, Call, ReportedBatch, ReportingBatchExecutor},
+    contracts::{BatchCallAndSponsor, EventLogger},
+    decode::AbiRegistry,
+    error::Error,
+    registry::DelegateRegistry,
+    revert::DecodeRevert,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Ensure `anvil` is available in $PATH.
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+    let alice: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+    let dave: PrivateKeySigner = anvil.keys()[2].clone().into();
+
+    let provider = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let chain_id = anvil.chain_id();
+    let executor = artifacts::deploy(&provider, "ReportingBatchExecutor").await?;
+    let logger = EventLogger::deploy(&provider).await?;
+    // Calling `execute(calls)` on a plain deployment reverts with "Invalid authority".
+    let guarded = BatchCallAndSponsor::deploy(&provider).await?;
+
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&batch_report::abi());
+
+    let nonce = provider.get_transaction_count(alice.address()).await?;
+    let mut registry = DelegateRegistry::from_env()?;
+    registry.register("ReportingBatchExecutor", chain_id, executor)?;
+    let auth = registry
+        .sign_authorization(&provider, &alice, chain_id, executor, nonce)
+        .await?;
+    let account = ReportingBatchExecutor::new(alice.address(), &provider);
+
+    let mut batch = ReportedBatch {
+        chain_id,
+        account: alice.address(),
+        nonce: U256::ZERO,
+        calls: vec![
+            Call {
+                to: dave.address(),
+                value: U256::from(1_000_000_000_000_000_000u128),
+                data: Bytes::new(),
+                allowFailure: false,
+            },
+            Call {
+                to: *logger.address(),
+                value: U256::ZERO,
+                data: EventLogger::emitHelloCall {}.abi_encode().into(),
+                allowFailure: false,
+            },
+            Call {
+                to: *guarded.address(),
+                value: U256::ZERO,
+                data: BatchCallAndSponsor::execute_0Call { calls: vec![] }
+                    .abi_encode()
+                    .into(),
+                allowFailure: true,
+            },
+        ],
+    };
+
+    let receipt = account
+        .call_builder(&batch.call(batch.sign(&alice)?))
+        .authorization_list(vec![auth])
+        .send()
+        .await
+        .decode_revert(&abis, "batch")?
+        .get_receipt()
+        .await?;
+    println!("Transaction hash: {}", receipt.transaction_hash);
+    assert!(receipt.status());
+
+    let report = batch_report::report(&receipt, alice.address(), &abis);
+    for call in &report {
+        println!("{call}");
+    }
+    assert_eq!(report.len(), 3);
+    assert!(report[0].success && report[1].success && !report[2].success);
+
+    // Required this time: the batch reverts and names the failing call.
+    batch.nonce = account.nonce().call().await?;
+    batch.calls[2].allowFailure = false;
+    let result = account
+        .call_builder(&batch.call(batch.sign(&alice)?))
+        .call()
+        .await
+        .decode_revert(&abis, "batch");
+    match result {
+        Err(Error::Revert(err)) => {
+            let failed = batch_report::failed_call(&err.data, &abis)
+                .ok_or_else(|| anyhow::anyhow!("unexpected revert: {err}"))?;
+            println!("Batch rejected: {failed}");
+            assert_eq!(failed.index, 2);
+        }
+        Err(err) => return Err(err.into()),
+        Ok(_) => anyhow::bail!("batch with a failing required call succeeded"),
+    }
+
+    Ok(())
+}
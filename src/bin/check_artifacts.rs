@@ -0,0 +1,83 @@
+//! Checks that the committed artifacts in `artifacts/` match a fresh Forge build.
+//!
+//! Usage: `check_artifacts [--update]`
+//!
+//! Build the Forge projects first (`make contracts`). Every contract in `artifacts::SOURCES` is
+//! expected to have a committed artifact. Bytecode is compared without the trailing solc
+//! metadata, which changes with unrelated edits such as comments. Contracts still bound through
+//! a hand-written interface also have that interface checked against the fresh ABI.
+//! `--update` copies the fresh artifacts over the committed ones.
+
+use interact_eth::{
+    artifacts::{Artifact, SOURCES, missing_from, strip_metadata},
+    contracts,
+};
+
+fn main() -> anyhow::Result<()> {
+    let update = std::env::args().any(|arg| arg == "--update");
+    let interfaces = contracts::interfaces();
+    let mut stale = 0;
+
+    for source in SOURCES {
+        let committed_path = source.committed_path();
+        let fresh_path = source.forge_out_path();
+        if !fresh_path.exists() {
+            let committed = if committed_path.exists() {
+                "committed"
+            } else {
+                "not committed"
+            };
+            println!(
+                "{:<28} {committed}, not built (forge-contracts/{})",
+                source.contract, source.project
+            );
+            stale += 1;
+            continue;
+        }
+        let fresh = Artifact::from_path(&fresh_path)?;
+
+        if let Some((_, interface)) = interfaces.iter().find(|(name, _)| *name == source.contract) {
+            for item in missing_from(interface, &fresh.abi) {
+                println!("{:<28} interface declares {item}", source.contract);
+                stale += 1;
+            }
+        }
+
+        let status = if !committed_path.exists() {
+            "not committed"
+        } else {
+            let committed = Artifact::from_path(&committed_path)?;
+            if committed.abi != fresh.abi {
+                "abi differs"
+            } else if strip_metadata(&committed.bytecode.object)
+                != strip_metadata(&fresh.bytecode.object)
+                || strip_metadata(&committed.deployed_bytecode.object)
+                    != strip_metadata(&fresh.deployed_bytecode.object)
+            {
+                "bytecode differs"
+            } else {
+                "ok"
+            }
+        };
+
+        if status == "ok" {
+            println!("{:<28} ok", source.contract);
+            continue;
+        }
+
+        if update {
+            std::fs::copy(&fresh_path, &committed_path)?;
+            println!("{:<28} {status}, updated", source.contract);
+        } else {
+            println!("{:<28} {status}", source.contract);
+            stale += 1;
+        }
+    }
+
+    anyhow::ensure!(
+        stale == 0,
+        "{stale} artifact(s) or interface(s) out of date, run `make artifacts` and fix the \
+         interfaces it reports"
+    );
+    Ok(())
+}
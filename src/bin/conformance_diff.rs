@@ -0,0 +1,56 @@
+//! Runs the same EIP-7702 scenario on Anvil and Reth and reports any difference.
+//!
+//! Usage: `conformance_diff [<left-rpc-url> <right-rpc-url>]`
+//!
+//! Without arguments a local Anvil (Prague) and a Reth dev node are spawned.
+//! Ensure `anvil` and `reth` are available in $PATH.
+
+use alloy::node_bindings::{Anvil, Reth};
+use interact_eth::{
+    conformance::{diff, run_scenario},
+    registry::DelegateRegistry,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let registry = DelegateRegistry::from_env()?;
+
+    let (left, right) = match args.as_slice() {
+        [left, right] => (
+            run_scenario(left, left.parse()?, &registry).await?,
+            run_scenario(right, right.parse()?, &registry).await?,
+        ),
+        [] => {
+            let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+            let reth = Reth::new().dev().disable_discovery().instance(1).spawn();
+            (
+                run_scenario("anvil", anvil.endpoint_url(), &registry).await?,
+                run_scenario("reth", reth.endpoint_url(), &registry).await?,
+            )
+        }
+        _ => anyhow::bail!("usage: conformance_diff [<left-rpc-url> <right-rpc-url>]"),
+    };
+
+    for step in &left.steps {
+        println!(
+            "{:<20} status={} gas_used={} logs={}",
+            step.label,
+            step.status,
+            step.gas_used,
+            step.logs.len()
+        );
+    }
+
+    let mismatches = diff(&left, &right);
+    if mismatches.is_empty() {
+        println!("{} and {} agree on every step", left.client, right.client);
+        return Ok(());
+    }
+
+    println!("{} vs {}:", left.client, right.client);
+    for mismatch in &mismatches {
+        println!("  {mismatch}");
+    }
+    anyhow::bail!("{} mismatch(es) found", mismatches.len())
+}
@@ -0,0 +1,106 @@
+//! Checks whether a stranger can move funds out of a delegated account.
+//!
+//! Usage: `drain_probe [<account> [token...]]`
+//!
+//! With an account, probes it on the Anvil node (or fork) at `RPC_URL` (default
+//! `http://localhost:8545`), naming functions from the matching local artifact. Without
+//! arguments, spawns Anvil, delegates Alice to `SponsoredTransfer` and expects its `transfer` to
+//! be flagged.
+
+use alloy::{
+    network::{TransactionBuilder, TransactionBuilder7702},
+    node_bindings::Anvil,
+    primitives::Address,
+    providers::{Provider, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+};
+use interact_eth::{
+    artifacts::{self, Artifact},
+    authorization::sign_unchecked,
+    drain::{Probe, probe},
+    verify::verify_code,
+};
+
+fn print_probes(probes: &[Probe]) {
+    for p in probes {
+        let verdict = if p.drains() { "DRAINS" } else { "ok" };
+        let status = if p.success { "success" } else { "reverted" };
+        println!("{verdict:6} {} ({status}) eth {}", p.function, p.eth_delta);
+        for (token, delta) in &p.token_deltas {
+            println!("         token {token} {delta}");
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // The stranger is a fresh key nobody has seen before.
+    let stranger = PrivateKeySigner::random().address();
+
+    if let Some(account) = args.first() {
+        let account: Address = account.parse()?;
+        let tokens = args[1..]
+            .iter()
+            .map(|t| t.parse())
+            .collect::<Result<Vec<Address>, _>>()?;
+
+        let rpc_url = std::env::var("RPC_URL").unwrap_or_else(|_| "http://localhost:8545".into());
+        let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+
+        let candidates = artifacts::all()?;
+        let verification = verify_code(&provider, account, &candidates).await?;
+        anyhow::ensure!(
+            verification.delegate.is_some(),
+            "{account} is not delegated"
+        );
+        let Some((name, _)) = verification.matched else {
+            anyhow::bail!("delegate code matches no local artifact, cannot enumerate its ABI");
+        };
+        println!("{account} delegates to {name}");
+
+        let abi = &Artifact::load(&name)?.abi;
+        let probes = probe(&provider, account, abi, stranger, &tokens).await?;
+        print_probes(&probes);
+        anyhow::ensure!(
+            !probes.iter().any(Probe::drains),
+            "{account} can be drained by anyone"
+        );
+        return Ok(());
+    }
+
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+    let alice: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+
+    let provider = ProviderBuilder::new()
+        .wallet(bob.clone())
+        .connect_http(anvil.endpoint_url());
+    let delegate = artifacts::deploy(&provider, "SponsoredTransfer").await?;
+
+    // Bob sponsors Alice's delegation without calling anything. The registry would refuse this
+    // delegate, which is the point of the probe.
+    let nonce = provider.get_transaction_count(alice.address()).await?;
+    let auth = sign_unchecked(&alice, anvil.chain_id(), delegate, nonce)?;
+    let tx = TransactionRequest::default()
+        .with_to(bob.address())
+        .with_authorization_list(vec![auth]);
+    provider.send_transaction(tx).await?.get_receipt().await?;
+
+    let probing = ProviderBuilder::new().connect_http(anvil.endpoint_url());
+    let abi = Artifact::load("SponsoredTransfer")?.abi;
+    let probes = probe(&probing, alice.address(), &abi, stranger, &[]).await?;
+    print_probes(&probes);
+
+    assert!(
+        probes
+            .iter()
+            .any(|p| p.drains() && p.function == "transfer(address,address,uint256)"),
+        "SponsoredTransfer.transfer should be drainable"
+    );
+    println!("Alice's account can be drained through SponsoredTransfer.transfer");
+
+    Ok(())
+}
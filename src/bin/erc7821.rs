@@ -0,0 +1,223 @@
+//! ERC-7821 batches.
+//!
+//! Usage:
+//!   `erc7821 decode <calldata>`
+//!   `erc7821`
+//!
+//! `decode` prints the mode and calls of an `execute(bytes32,bytes)` calldata. Without arguments,
+//! spawns Anvil and sends the same batch through both executor interfaces: Alice delegates to
+//! `BatchCallAndSponsor` and Bob sponsors her batch, then Carol delegates to `ERC7821Executor`
+//! (run `make contracts` first) and Bob sponsors the same calls for her. A try-mode batch with a
+//! failing call then goes through with the failure reported. Every batch is simulated, calls and
+//! executor, before Bob pays for it.
+
+use alloy::{
+    network::TransactionBuilder7702,
+    node_bindings::Anvil,
+    primitives::{Address, Bytes, U256},
+    providers::{Provider, ProviderBuilder, WalletProvider},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+    sol_types::{SolCall, SolEvent},
+};
+use interact_eth::{
+    artifacts,
+    batch::{Batch, Executor},
+    contracts::{
+        BatchCallAndSponsor::{self, Call},
+        EventLogger,
+    },
+    decode::AbiRegistry,
+    erc7821::{ERC7821, Execution, Mode},
+    presubmit::{self, PlannedCall},
+    registry::DelegateRegistry,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [cmd, calldata] if cmd == "decode" => {
+            let calldata: Bytes = calldata.parse()?;
+            let execution = Execution::decode_calldata(&calldata)?;
+            println!(
+                "mode: {:?} ({})",
+                execution.mode(),
+                execution.mode().to_b256()
+            );
+            for (i, call) in execution.calls.iter().enumerate() {
+                println!("#{i} {} value {} data {}", call.to, call.value, call.data);
+            }
+            if let Some(op_data) = &execution.op_data {
+                println!("opData: {op_data}");
+            }
+            Ok(())
+        }
+        [] => demo().await,
+        _ => anyhow::bail!("usage: erc7821 [decode <calldata>]"),
+    }
+}
+
+async fn demo() -> anyhow::Result<()> {
+    // Ensure `anvil` is available in $PATH.
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+    let alice: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+    let dave: PrivateKeySigner = anvil.keys()[2].clone().into();
+    let carol: PrivateKeySigner = anvil.keys()[3].clone().into();
+
+    let provider = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let chain_id = anvil.chain_id();
+    let legacy = BatchCallAndSponsor::deploy(&provider).await?;
+    let executor = artifacts::deploy(&provider, "ERC7821Executor").await?;
+    let logger = EventLogger::deploy(&provider).await?;
+    let mut registry = DelegateRegistry::from_env()?;
+    registry.register("BatchCallAndSponsor", chain_id, *legacy.address())?;
+    registry.register("ERC7821Executor", chain_id, executor)?;
+    let abis = AbiRegistry::from_artifacts()?;
+
+    let supported = ERC7821::new(executor, &provider)
+        .supportsExecutionMode(Mode::TRY_BATCH_WITH_OP_DATA.to_b256())
+        .call()
+        .await?;
+    assert!(supported);
+
+    let one_eth = U256::from(1_000_000_000_000_000_000u128);
+    let calls = vec![
+        Call {
+            to: dave.address(),
+            value: one_eth,
+            data: Bytes::new(),
+        },
+        Call {
+            to: *logger.address(),
+            value: U256::ZERO,
+            data: EventLogger::emitHelloCall {}.abi_encode().into(),
+        },
+    ];
+
+    for (signer, delegate, executor) in [
+        (&alice, *legacy.address(), Executor::BatchCallAndSponsor),
+        (&carol, executor, Executor::Erc7821 { try_mode: false }),
+    ] {
+        let batch = Batch {
+            executor,
+            calls: calls.clone(),
+        };
+        let nonce = provider.get_transaction_count(signer.address()).await?;
+        let auth = registry
+            .sign_authorization(&provider, signer, chain_id, delegate, nonce)
+            .await?;
+        // Neither account is delegated yet, so both executors' nonces start at zero.
+        let input = batch.sponsored(signer, chain_id, U256::ZERO)?;
+        presubmit::simulate_sponsored(
+            &provider,
+            provider.default_signer_address(),
+            signer.address(),
+            Some(delegate),
+            input.clone(),
+            &planned(&batch),
+            &abis,
+        )
+        .await?
+        .ensure_submittable()?;
+        let dave_before = provider.get_balance(dave.address()).await?;
+        let receipt = provider
+            .send_transaction(
+                sponsored(signer.address(), input).with_authorization_list(vec![auth]),
+            )
+            .await?
+            .get_receipt()
+            .await?;
+        println!("{executor:?}: {}", receipt.transaction_hash);
+        assert!(receipt.status());
+        assert_eq!(
+            provider.get_balance(dave.address()).await? - dave_before,
+            one_eth
+        );
+    }
+
+    // In try mode a failing call is reported and the rest of the batch still runs.
+    let mut calls = calls;
+    calls.insert(
+        0,
+        Call {
+            to: *legacy.address(),
+            value: U256::ZERO,
+            data: BatchCallAndSponsor::execute_0Call { calls: vec![] }
+                .abi_encode()
+                .into(),
+        },
+    );
+    let batch = Batch {
+        executor: Executor::Erc7821 { try_mode: true },
+        calls,
+    };
+    let nonce = ERC7821::new(carol.address(), &provider)
+        .nonce()
+        .call()
+        .await?;
+    let input = batch.sponsored(&carol, chain_id, nonce)?;
+    println!("{:?}", Execution::decode_calldata(&input)?.mode());
+    let simulation = presubmit::simulate_sponsored(
+        &provider,
+        provider.default_signer_address(),
+        carol.address(),
+        None,
+        input.clone(),
+        &planned(&batch),
+        &abis,
+    )
+    .await?;
+    for call in &simulation.calls {
+        println!("{call}");
+    }
+    simulation.ensure_submittable()?;
+    let receipt = provider
+        .send_transaction(sponsored(carol.address(), input))
+        .await?
+        .get_receipt()
+        .await?;
+    assert!(receipt.status());
+    let failed: Vec<_> = receipt
+        .inner
+        .logs()
+        .iter()
+        .filter_map(|log| log.log_decode::<ERC7821::TryExecuteFailed>().ok())
+        .map(|log| log.inner.data.index)
+        .collect();
+    println!("Failed calls: {failed:?}");
+    assert_eq!(failed, vec![U256::ZERO]);
+    assert!(
+        receipt
+            .inner
+            .logs()
+            .iter()
+            .any(|log| log.topic0() == Some(&EventLogger::Hello::SIGNATURE_HASH))
+    );
+
+    Ok(())
+}
+
+/// The calls of `batch` as the executor runs them: in try mode, a failing call does not revert
+/// the batch.
+fn planned(batch: &Batch) -> Vec<PlannedCall> {
+    let try_mode = matches!(batch.executor, Executor::Erc7821 { try_mode: true });
+    batch
+        .calls
+        .iter()
+        .map(|call| PlannedCall {
+            allow_failure: try_mode,
+            ..call.into()
+        })
+        .collect()
+}
+
+fn sponsored(account: Address, input: Bytes) -> TransactionRequest {
+    TransactionRequest::default()
+        .to(account)
+        .input(input.into())
+}
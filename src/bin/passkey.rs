@@ -0,0 +1,250 @@
+//! Passkey (P-256) control of a delegated EOA through `P256Delegate`.
+//!
+//! Usage:
+//!   `passkey setup <rpc_url> <account_key> <delegate> <public_key>`
+//!   `passkey challenge <rpc_url> <account> <calls.json>`
+//!   `passkey execute <rpc_url> <sponsor_key> <account> <calls.json> <response.json>`
+//!   `passkey`
+//!
+//! `setup` upgrades the account and sets its P-256 public key (SEC1 hex, e.g. `0x04‖x‖y`) in one
+//! transaction the account sends to itself. `challenge` prints the base64url challenge to pass
+//! to `navigator.credentials.get()` for a batch (a JSON array of `{ "to", "value", "data" }`).
+//! `execute` takes the assertion's `response` as `PublicKeyCredential.toJSON()` gives it, checks
+//! it against the account's key and has the sponsor simulate and submit the batch. Without arguments, spawns
+//! Anvil, upgrades Alice to a software passkey and pays Dave once with a raw P-256 signature and
+//! once with a WebAuthn assertion.
+
+use alloy::{
+    network::TransactionBuilder,
+    node_bindings::Anvil,
+    primitives::{Address, B256, Bytes, TxHash, U256},
+    providers::{Provider, ProviderBuilder, WalletProvider},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+    sol_types::SolCall,
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use interact_eth::{
+    artifacts,
+    decode::AbiRegistry,
+    passkey::{self, Call, P256Delegate, P256Signer, PublicKey, WebAuthnResponse},
+    presubmit::{self, PlannedCall},
+    registry::DelegateRegistry,
+    revert::DecodeRevert,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [cmd, rpc_url, account, delegate, public_key] if cmd == "setup" => {
+            let account: PrivateKeySigner = account.parse()?;
+            let provider = ProviderBuilder::new()
+                .wallet(account.clone())
+                .connect_http(rpc_url.parse()?);
+            let public_key = PublicKey::from_sec1(&public_key.parse::<Bytes>()?)?;
+            let registry = DelegateRegistry::from_env()?;
+            let hash = setup(
+                &provider,
+                &registry,
+                &account,
+                delegate.parse()?,
+                &public_key,
+            )
+            .await?;
+            println!("Set up {}: {hash}", account.address());
+            Ok(())
+        }
+        [cmd, rpc_url, account, calls] if cmd == "challenge" => {
+            let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+            let calls: Vec<Call> = serde_json::from_str(&std::fs::read_to_string(calls)?)?;
+            let challenge = challenge(&provider, account.parse()?, &calls).await?;
+            println!("{challenge}");
+            println!("{}", URL_SAFE_NO_PAD.encode(challenge));
+            Ok(())
+        }
+        [cmd, rpc_url, sponsor, account, calls, response] if cmd == "execute" => {
+            let sponsor: PrivateKeySigner = sponsor.parse()?;
+            let provider = ProviderBuilder::new()
+                .wallet(sponsor)
+                .connect_http(rpc_url.parse()?);
+            let calls: Vec<Call> = serde_json::from_str(&std::fs::read_to_string(calls)?)?;
+            let response: WebAuthnResponse =
+                serde_json::from_str(&std::fs::read_to_string(response)?)?;
+            let hash = execute_with_passkey(&provider, account.parse()?, calls, &response).await?;
+            println!("Executed: {hash}");
+            Ok(())
+        }
+        [] => demo().await,
+        _ => anyhow::bail!(
+            "usage: passkey [setup|challenge|execute ...], see the module documentation"
+        ),
+    }
+}
+
+fn abis() -> anyhow::Result<AbiRegistry> {
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&passkey::abi());
+    Ok(abis)
+}
+
+/// Delegates `account` and sets its public key in a transaction to itself.
+async fn setup<P: Provider>(
+    provider: &P,
+    registry: &DelegateRegistry,
+    account: &PrivateKeySigner,
+    delegate: Address,
+    public_key: &PublicKey,
+) -> anyhow::Result<TxHash> {
+    let chain_id = provider.get_chain_id().await?;
+    // The transaction's own nonce is used first, so the authorization takes the next one.
+    let nonce = provider.get_transaction_count(account.address()).await?;
+    let auth = registry
+        .sign_authorization(provider, account, chain_id, delegate, nonce + 1)
+        .await?;
+    let receipt = P256Delegate::new(account.address(), provider)
+        .call_builder(&public_key.set_call())
+        .authorization_list(vec![auth])
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    anyhow::ensure!(receipt.status(), "{} reverted", receipt.transaction_hash);
+    Ok(receipt.transaction_hash)
+}
+
+/// The digest of `calls` at the account's current nonce.
+async fn challenge<P: Provider>(
+    provider: &P,
+    account: Address,
+    calls: &[Call],
+) -> anyhow::Result<B256> {
+    let nonce = P256Delegate::new(account, provider).nonce().call().await?;
+    let chain_id = provider.get_chain_id().await?;
+    Ok(passkey::digest(chain_id, account, nonce, calls))
+}
+
+async fn public_key<P: Provider>(provider: &P, account: Address) -> anyhow::Result<PublicKey> {
+    let delegate = P256Delegate::new(account, provider);
+    Ok(PublicKey {
+        x: delegate.publicKeyX().call().await?,
+        y: delegate.publicKeyY().call().await?,
+    })
+}
+
+/// Checks `response` against the batch and the account's key, then sends it from the
+/// provider's wallet.
+async fn execute_with_passkey<P: Provider + WalletProvider>(
+    provider: &P,
+    account: Address,
+    calls: Vec<Call>,
+    response: &WebAuthnResponse,
+) -> anyhow::Result<TxHash> {
+    let assertion = response.assertion(challenge(provider, account, &calls).await?)?;
+    public_key(provider, account).await?.verify(
+        assertion.message_hash(),
+        assertion.r,
+        assertion.s,
+    )?;
+    let planned: Vec<PlannedCall> = calls.iter().map(PlannedCall::from).collect();
+    let call = P256Delegate::executeWithPasskeyCall { calls, assertion };
+    submit(provider, account, &planned, &call).await
+}
+
+/// Sends `call` to the account from the provider's wallet, once the batch and the executor
+/// both simulate cleanly.
+async fn submit<P: Provider + WalletProvider, C: SolCall>(
+    provider: &P,
+    account: Address,
+    planned: &[PlannedCall],
+    call: &C,
+) -> anyhow::Result<TxHash> {
+    let input: Bytes = call.abi_encode().into();
+    presubmit::simulate_sponsored(
+        provider,
+        provider.default_signer_address(),
+        account,
+        None,
+        input.clone(),
+        planned,
+        &abis()?,
+    )
+    .await?
+    .ensure_submittable()?;
+    let tx = TransactionRequest::default()
+        .with_to(account)
+        .with_input(input);
+    let receipt = provider.send_transaction(tx).await?.get_receipt().await?;
+    anyhow::ensure!(receipt.status(), "{} reverted", receipt.transaction_hash);
+    Ok(receipt.transaction_hash)
+}
+
+async fn demo() -> anyhow::Result<()> {
+    // Ensure `anvil` is available in $PATH.
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+    let alice: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+    let dave: PrivateKeySigner = anvil.keys()[2].clone().into();
+    // Stands in for the passkey on Alice's phone.
+    let passkey = P256Signer::from_bytes(&alloy::primitives::keccak256("alice's passkey"))?;
+
+    let sponsor = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let delegate = artifacts::deploy(&sponsor, "P256Delegate").await?;
+    let mut registry = DelegateRegistry::from_env()?;
+    registry.register("P256Delegate", anvil.chain_id(), delegate)?;
+    let own = ProviderBuilder::new()
+        .wallet(alice.clone())
+        .connect_http(anvil.endpoint_url());
+    let hash = setup(&own, &registry, &alice, delegate, &passkey.public_key()).await?;
+    println!("Alice upgraded to {:?}: {hash}", passkey.public_key());
+
+    let one_eth = U256::from(1_000_000_000_000_000_000u128);
+    let pay_dave = vec![Call {
+        to: dave.address(),
+        value: one_eth,
+        data: Bytes::new(),
+    }];
+    let dave_before = sponsor.get_balance(dave.address()).await?;
+
+    // A raw P-256 signature over the batch digest.
+    let nonce = P256Delegate::new(alice.address(), &sponsor)
+        .nonce()
+        .call()
+        .await?;
+    let call = passkey.sign_batch(anvil.chain_id(), alice.address(), nonce, &pay_dave)?;
+    let planned: Vec<PlannedCall> = pay_dave.iter().map(PlannedCall::from).collect();
+    let hash = submit(&sponsor, alice.address(), &planned, &call).await?;
+    println!("Raw P-256 batch: {hash}");
+
+    // A WebAuthn assertion with the batch digest as its challenge.
+    let digest = challenge(&sponsor, alice.address(), &pay_dave).await?;
+    let response = passkey.sign_webauthn(digest, "wallet.example", "https://wallet.example")?;
+    println!("Assertion: {}", serde_json::to_string(&response)?);
+    let hash = execute_with_passkey(&sponsor, alice.address(), pay_dave.clone(), &response).await?;
+    println!("Passkey batch: {hash}");
+    assert_eq!(
+        sponsor.get_balance(dave.address()).await? - dave_before,
+        one_eth * U256::from(2)
+    );
+
+    // The assertion was for the previous nonce, and another key is not Alice's.
+    let replay = execute_with_passkey(&sponsor, alice.address(), pay_dave.clone(), &response).await;
+    println!("Replayed assertion: {}", replay.unwrap_err());
+    let stranger = P256Signer::from_bytes(&alloy::primitives::keccak256("someone else"))?;
+    let digest = challenge(&sponsor, alice.address(), &pay_dave).await?;
+    let response = stranger.sign_webauthn(digest, "wallet.example", "https://wallet.example")?;
+    let forged = P256Delegate::new(alice.address(), &sponsor)
+        .executeWithPasskey(pay_dave, response.assertion(digest)?)
+        .call()
+        .await
+        .decode_revert(&abis()?, "executeWithPasskey");
+    if let Err(err) = forged {
+        println!("{err}");
+    } else {
+        anyhow::bail!("a stranger's passkey was accepted");
+    }
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! Shows what a call to an account would do once it is delegated, without signing anything.
+//!
+//! Usage: `preview_delegation [<account> <target> <calldata> [value]]`
+//!
+//! With arguments, previews `calldata` (sent by the account itself) on the node at `RPC_URL`
+//! (default `http://localhost:8545`). Without arguments, spawns Anvil and previews Alice
+//! delegating to `SponsoredTransfer` and sending 1 ETH to Dave.
+
+use alloy::{
+    network::TransactionBuilder,
+    node_bindings::Anvil,
+    primitives::{Address, Bytes, I256, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+};
+use interact_eth::{
+    artifacts,
+    contracts::SponsoredTransfer,
+    decode::AbiRegistry,
+    preview::{Preview, preview_delegation},
+};
+
+fn print_preview(preview: &Preview, abis: &AbiRegistry) {
+    let status = if preview.success {
+        "success"
+    } else {
+        "reverted"
+    };
+    println!("status:   {status}");
+    println!("output:   {}", preview.output);
+    if !preview.success {
+        println!("revert:   {}", abis.decode_revert(&preview.output));
+    }
+    println!("gas used: {}", preview.gas_used);
+    println!("logs:");
+    for log in &preview.logs {
+        println!(
+            "  {} topics {:?} data {}",
+            log.address,
+            log.topics(),
+            log.data.data
+        );
+    }
+    println!("balance changes:");
+    for (address, delta) in &preview.balance_deltas {
+        println!("  {address} {delta}");
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if let [account, target, calldata, rest @ ..] = args.as_slice() {
+        let account: Address = account.parse()?;
+        let target: Address = target.parse()?;
+        let calldata: Bytes = calldata.parse()?;
+        let value: U256 = rest
+            .first()
+            .map(|v| v.parse())
+            .transpose()?
+            .unwrap_or_default();
+
+        let rpc_url = std::env::var("RPC_URL").unwrap_or_else(|_| "http://localhost:8545".into());
+        let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+
+        let tx = TransactionRequest::default()
+            .with_from(account)
+            .with_input(calldata)
+            .with_value(value);
+        print_preview(
+            &preview_delegation(&provider, account, target, tx).await?,
+            &AbiRegistry::from_artifacts()?,
+        );
+        return Ok(());
+    }
+
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+    let alice: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+    let dave: PrivateKeySigner = anvil.keys()[2].clone().into();
+
+    let provider = ProviderBuilder::new()
+        .wallet(bob.clone())
+        .connect_http(anvil.endpoint_url());
+    let target = artifacts::deploy(&provider, "SponsoredTransfer").await?;
+
+    // Bob would submit the call; Alice has not signed an authorization.
+    let amount = U256::from(1_000_000_000_000_000_000u128);
+    let calldata = SponsoredTransfer::new(target, &provider)
+        .transfer(alice.address(), dave.address(), amount)
+        .calldata()
+        .clone();
+    let tx = TransactionRequest::default()
+        .with_from(bob.address())
+        .with_input(calldata);
+    let preview = preview_delegation(&provider, alice.address(), target, tx).await?;
+    print_preview(&preview, &AbiRegistry::from_artifacts()?);
+
+    assert!(preview.success);
+    assert_eq!(preview.logs.len(), 1, "Transfer event");
+    assert_eq!(
+        preview.balance_deltas.get(&alice.address()),
+        Some(&-I256::from_raw(amount))
+    );
+    assert_eq!(
+        preview.balance_deltas.get(&dave.address()),
+        Some(&I256::from_raw(amount))
+    );
+    assert!(
+        provider.get_code_at(alice.address()).await?.is_empty(),
+        "the preview must not delegate Alice"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,349 @@
+//! Guardian recovery of the signing key of an account delegated to `RecoveryDelegate`.
+//!
+//! Usage:
+//!   `recovery setup <rpc_url> <account_key> <delegate> <threshold> <delay_secs> <guardians>`
+//!   `recovery status <rpc_url> <account>`
+//!   `recovery approve <rpc_url> <account> <new_signer> <guardian_key> <approval.json>`
+//!   `recovery initiate <rpc_url> <sponsor_key> <account> <new_signer> <approval.json>...`
+//!   `recovery cancel <rpc_url> <sponsor_key> <signer_key> <account>`
+//!   `recovery finalize <rpc_url> <sponsor_key> <account>`
+//!   `recovery`
+//!
+//! `setup` delegates the account and sets the comma-separated `guardians` in one transaction the
+//! account sends to itself. Each guardian writes an approval file with `approve`, and `initiate`
+//! starts the recovery with them. `cancel` is signed by the current signing key and submitted
+//! by the sponsor, as are `initiate` and `finalize`. Without arguments, spawns Anvil, cancels a
+//! recovery Alice did not ask for, then recovers her account to Carol's key after the timelock.
+
+use alloy::{
+    network::TransactionBuilder,
+    node_bindings::Anvil,
+    primitives::{Address, Bytes, TxHash, U256},
+    providers::{Provider, ProviderBuilder, WalletProvider, ext::AnvilApi},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+    sol_types::SolCall,
+};
+use interact_eth::{
+    artifacts,
+    batch::latest_timestamp,
+    decode::AbiRegistry,
+    presubmit::{self, PlannedCall},
+    recovery::{self, Call, GuardianConfig, RecoveryDelegate},
+    registry::DelegateRegistry,
+    revert::DecodeRevert,
+    threshold::PartialSignature,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [cmd, rpc_url, account, delegate, threshold, delay, guardians] if cmd == "setup" => {
+            let account: PrivateKeySigner = account.parse()?;
+            let provider = ProviderBuilder::new()
+                .wallet(account.clone())
+                .connect_http(rpc_url.parse()?);
+            let config = GuardianConfig {
+                guardians: guardians
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()?,
+                threshold: threshold.parse()?,
+                delay: delay.parse()?,
+            };
+            config.check(account.address())?;
+            let registry = DelegateRegistry::from_env()?;
+            let hash = setup(&provider, &registry, &account, delegate.parse()?, &config).await?;
+            println!("Set up {}: {hash}", account.address());
+            Ok(())
+        }
+        [cmd, rpc_url, account] if cmd == "status" => {
+            let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+            let status = recovery::status(&provider, account.parse()?).await?;
+            println!("{status:#?}");
+            Ok(())
+        }
+        [cmd, rpc_url, account, new_signer, guardian, out] if cmd == "approve" => {
+            let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+            let guardians = [guardian.parse()?];
+            let [approval] = approve(&provider, account.parse()?, new_signer.parse()?, &guardians)
+                .await?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("expected one approval"))?;
+            std::fs::write(out, serde_json::to_string_pretty(&approval)?)?;
+            println!("{} approved {new_signer}", approval.signer);
+            Ok(())
+        }
+        [cmd, rpc_url, sponsor, account, new_signer, approvals @ ..] if cmd == "initiate" => {
+            let provider = sponsored(rpc_url, sponsor)?;
+            let approvals = approvals
+                .iter()
+                .map(PartialSignature::load)
+                .collect::<Result<Vec<_>, _>>()?;
+            let hash =
+                initiate(&provider, account.parse()?, new_signer.parse()?, &approvals).await?;
+            println!("Recovery started: {hash}");
+            Ok(())
+        }
+        [cmd, rpc_url, sponsor, signer, account] if cmd == "cancel" => {
+            let provider = sponsored(rpc_url, sponsor)?;
+            let hash = cancel(&provider, &signer.parse()?, account.parse()?).await?;
+            println!("Recovery cancelled: {hash}");
+            Ok(())
+        }
+        [cmd, rpc_url, sponsor, account] if cmd == "finalize" => {
+            let provider = sponsored(rpc_url, sponsor)?;
+            let hash = finalize(&provider, account.parse()?).await?;
+            println!("Recovery finalized: {hash}");
+            Ok(())
+        }
+        [] => demo().await,
+        _ => anyhow::bail!(
+            "usage: recovery [setup|status|approve|initiate|cancel|finalize ...], see the module documentation"
+        ),
+    }
+}
+
+fn sponsored(
+    rpc_url: &str,
+    sponsor: &str,
+) -> anyhow::Result<impl Provider + WalletProvider + use<>> {
+    let sponsor: PrivateKeySigner = sponsor.parse()?;
+    Ok(ProviderBuilder::new()
+        .wallet(sponsor)
+        .connect_http(rpc_url.parse()?))
+}
+
+fn abis() -> anyhow::Result<AbiRegistry> {
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&recovery::abi());
+    Ok(abis)
+}
+
+/// Delegates `account` and sets its guardians in a transaction to itself.
+async fn setup<P: Provider>(
+    provider: &P,
+    registry: &DelegateRegistry,
+    account: &PrivateKeySigner,
+    delegate: Address,
+    config: &GuardianConfig,
+) -> anyhow::Result<TxHash> {
+    let chain_id = provider.get_chain_id().await?;
+    // The transaction's own nonce is used first, so the authorization takes the next one.
+    let nonce = provider.get_transaction_count(account.address()).await?;
+    let auth = registry
+        .sign_authorization(provider, account, chain_id, delegate, nonce + 1)
+        .await?;
+    let receipt = RecoveryDelegate::new(account.address(), provider)
+        .call_builder(&config.set_call())
+        .authorization_list(vec![auth])
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    anyhow::ensure!(receipt.status(), "{} reverted", receipt.transaction_hash);
+    Ok(receipt.transaction_hash)
+}
+
+/// Sends a call from the provider's wallet after checking that it does not revert.
+async fn send<P: Provider, C: SolCall>(
+    provider: &P,
+    account: Address,
+    call: &C,
+) -> anyhow::Result<TxHash> {
+    let tx = TransactionRequest::default()
+        .with_to(account)
+        .with_input(call.abi_encode());
+    provider
+        .call(tx.clone())
+        .await
+        .decode_revert(&abis()?, C::SIGNATURE)?;
+    let receipt = provider.send_transaction(tx).await?.get_receipt().await?;
+    anyhow::ensure!(receipt.status(), "{} reverted", receipt.transaction_hash);
+    Ok(receipt.transaction_hash)
+}
+
+async fn initiate<P: Provider>(
+    provider: &P,
+    account: Address,
+    new_signer: Address,
+    approvals: &[PartialSignature],
+) -> anyhow::Result<TxHash> {
+    let status = recovery::status(provider, account).await?;
+    let chain_id = provider.get_chain_id().await?;
+    let call = status
+        .recovery(chain_id, account, new_signer)
+        .initiate_call(approvals, &status.guardians)?;
+    send(provider, account, &call).await
+}
+
+/// Cancels the pending recovery with a batch signed by the current signing key.
+async fn cancel<P: Provider + WalletProvider>(
+    provider: &P,
+    signer: &PrivateKeySigner,
+    account: Address,
+) -> anyhow::Result<TxHash> {
+    let calls = vec![Call {
+        to: account,
+        value: U256::ZERO,
+        data: RecoveryDelegate::cancelRecoveryCall {}.abi_encode().into(),
+    }];
+    execute(provider, signer, account, calls).await
+}
+
+async fn finalize<P: Provider>(provider: &P, account: Address) -> anyhow::Result<TxHash> {
+    let status = recovery::status(provider, account).await?;
+    let pending = status
+        .pending
+        .ok_or_else(|| anyhow::anyhow!("no recovery pending for {account}"))?;
+    // The next block is later than the latest one, so check against that.
+    pending.check_finalizable(latest_timestamp(provider).await? + 1)?;
+    send(
+        provider,
+        account,
+        &RecoveryDelegate::finalizeRecoveryCall {},
+    )
+    .await
+}
+
+/// Signs `calls` with the account's signing key and sends them from the sponsor, once the
+/// batch and the executor both simulate cleanly.
+async fn execute<P: Provider + WalletProvider>(
+    provider: &P,
+    signer: &PrivateKeySigner,
+    account: Address,
+    calls: Vec<Call>,
+) -> anyhow::Result<TxHash> {
+    let nonce = RecoveryDelegate::new(account, provider)
+        .nonce()
+        .call()
+        .await?;
+    let chain_id = provider.get_chain_id().await?;
+    let signature = recovery::sign(signer, chain_id, account, nonce, &calls)?;
+    let planned: Vec<PlannedCall> = calls.iter().map(PlannedCall::from).collect();
+    let call = RecoveryDelegate::executeCall { calls, signature };
+    presubmit::simulate_sponsored(
+        provider,
+        provider.default_signer_address(),
+        account,
+        None,
+        call.abi_encode().into(),
+        &planned,
+        &abis()?,
+    )
+    .await?
+    .ensure_submittable()?;
+    send(provider, account, &call).await
+}
+
+/// Approvals of a recovery of `account` to `new_signer` by each of `guardians`.
+async fn approve<P: Provider>(
+    provider: &P,
+    account: Address,
+    new_signer: Address,
+    guardians: &[PrivateKeySigner],
+) -> anyhow::Result<Vec<PartialSignature>> {
+    let status = recovery::status(provider, account).await?;
+    let recovery = status.recovery(provider.get_chain_id().await?, account, new_signer);
+    Ok(guardians
+        .iter()
+        .map(|guardian| recovery.approve(guardian))
+        .collect::<Result<_, _>>()?)
+}
+
+async fn demo() -> anyhow::Result<()> {
+    // Ensure `anvil` is available in $PATH.
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+    let alice: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+    let dave: PrivateKeySigner = anvil.keys()[2].clone().into();
+    let carol: PrivateKeySigner = anvil.keys()[3].clone().into();
+    let mallory: PrivateKeySigner = anvil.keys()[7].clone().into();
+    let guardians: Vec<PrivateKeySigner> = (4..7).map(|i| anvil.keys()[i].clone().into()).collect();
+
+    let sponsor = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let delegate = artifacts::deploy(&sponsor, "RecoveryDelegate").await?;
+    let mut registry = DelegateRegistry::from_env()?;
+    registry.register("RecoveryDelegate", anvil.chain_id(), delegate)?;
+    let config = GuardianConfig {
+        guardians: guardians
+            .iter()
+            .map(|guardian| guardian.address())
+            .collect(),
+        threshold: 2,
+        delay: 86_400,
+    };
+    let own = ProviderBuilder::new()
+        .wallet(alice.clone())
+        .connect_http(anvil.endpoint_url());
+    let hash = setup(&own, &registry, &alice, delegate, &config).await?;
+    println!("Alice has 2-of-3 guardians with a one-day timelock: {hash}");
+
+    // Two guardians try to hand the account to Mallory; Alice still has her key and cancels.
+    let approvals = approve(
+        &sponsor,
+        alice.address(),
+        mallory.address(),
+        &guardians[..2],
+    )
+    .await?;
+    let hash = initiate(&sponsor, alice.address(), mallory.address(), &approvals).await?;
+    println!("Recovery to Mallory started: {hash}");
+    let hash = cancel(&sponsor, &alice, alice.address()).await?;
+    println!("Alice cancelled it: {hash}");
+    assert!(
+        recovery::status(&sponsor, alice.address())
+            .await?
+            .pending
+            .is_none()
+    );
+    // Their approvals were for that attempt only.
+    assert!(
+        initiate(&sponsor, alice.address(), mallory.address(), &approvals)
+            .await
+            .is_err()
+    );
+
+    // Alice loses her key, and her guardians move the account to Carol's.
+    let approvals = approve(&sponsor, alice.address(), carol.address(), &guardians[1..]).await?;
+    let hash = initiate(&sponsor, alice.address(), carol.address(), &approvals).await?;
+    println!("Recovery to Carol started: {hash}");
+    let early = finalize(&sponsor, alice.address()).await;
+    println!("Finalizing now: {}", early.unwrap_err());
+    let early = RecoveryDelegate::new(alice.address(), &sponsor)
+        .finalizeRecovery()
+        .call()
+        .await
+        .decode_revert(&abis()?, "finalizeRecovery");
+    if let Err(err) = early {
+        println!("{err}");
+    }
+
+    sponsor.anvil_increase_time(config.delay).await?;
+    let hash = finalize(&sponsor, alice.address()).await?;
+    println!("Recovery finalized: {hash}");
+    let status = recovery::status(&sponsor, alice.address()).await?;
+    println!("{status:#?}");
+    assert_eq!(status.signer, carol.address());
+
+    let one_eth = U256::from(1_000_000_000_000_000_000u128);
+    let pay_dave = vec![Call {
+        to: dave.address(),
+        value: one_eth,
+        data: Bytes::new(),
+    }];
+    let dave_before = sponsor.get_balance(dave.address()).await?;
+    let hash = execute(&sponsor, &carol, alice.address(), pay_dave.clone()).await?;
+    println!("Carol's batch: {hash}");
+    assert_eq!(
+        sponsor.get_balance(dave.address()).await? - dave_before,
+        one_eth
+    );
+    let stale = execute(&sponsor, &alice, alice.address(), pay_dave).await;
+    println!("Alice's old key: {}", stale.unwrap_err());
+    Ok(())
+}
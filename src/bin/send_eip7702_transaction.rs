@@ -1,48 +1,13 @@
 use alloy::{
-    eips::eip7702::{Authorization, SignedAuthorization},
     network::{TransactionBuilder, TransactionBuilder7702},
     node_bindings::Anvil,
-    primitives::{Address, U256},
+    primitives::Address,
     providers::{Provider, ProviderBuilder},
     rpc::types::TransactionRequest,
-    signers::{SignerSync, local::PrivateKeySigner},
-    sol,
+    signers::local::PrivateKeySigner,
 };
 use hex::encode as hex_encode;
-
-// Codegen from embedded Solidity code and precompiled bytecode.
-sol!(
-    #[allow(missing_docs)]
-    #[sol(rpc, bytecode = "6080806040523460135760c9908160188239f35b5f80fdfe6004361015600b575f80fd5b5f3560e01c80637b3ab2d014605f57639ee1a440146027575f80fd5b34605b575f366003190112605b577f2d67bb91f17bca05af6764ab411e86f4ddf757adb89fcec59a7d21c525d417125f80a1005b5f80fd5b34605b575f366003190112605b577fbcdfe0d5b27dd186282e187525415c57ea3077c34efb39148111e4d342e7ab0e5f80a100fea2646970667358221220f6b42b522bc9fb2b4c7d7e611c7c3e995d057ecab7fd7be4179712804c886b4f64736f6c63430008190033")]
-    contract Log {
-        #[derive(Debug)]
-        event Hello();
-        event World();
-
-        function emitHello() public {
-            emit Hello();
-        }
-
-        function emitWorld() public {
-            emit World();
-        }
-    }
-);
-
-fn sign_authorization(
-    signer: &PrivateKeySigner,
-    chain_id: u64,
-    address: Address,
-    nonce: u64,
-) -> SignedAuthorization {
-    let auth = Authorization {
-        chain_id: U256::from(chain_id),
-        address,
-        nonce,
-    };
-    let sig = signer.sign_hash_sync(&auth.signature_hash()).unwrap();
-    auth.into_signed(sig)
-}
+use interact_eth::{contracts::EventLogger, registry::DelegateRegistry};
 
 fn print_code_status(code: &[u8], label: &str) {
     let code_hex = format!("0x{}", hex_encode(code));
@@ -80,15 +45,35 @@ async fn main() -> anyhow::Result<()> {
     println!("alice balance init: {}", balance_alice);
     println!("bob balance init: {}", balance_bob);
 
-    let contract = Log::deploy(&provider).await?;
+    let contract = EventLogger::deploy(&provider).await?;
+
+    // Only approved delegates may be signed over; record where this chain's copy lives.
+    let mut registry = DelegateRegistry::from_env()?;
+    registry.register("EventLogger", anvil.chain_id(), *contract.address())?;
 
     // Alice signs delegation to contract
     let nonce = provider.get_transaction_count(alice.address()).await?;
-    let signed_auth_1 = sign_authorization(&alice, anvil.chain_id(), *contract.address(), nonce);
+    let signed_auth_1 = registry
+        .sign_authorization(
+            &provider,
+            &alice,
+            anvil.chain_id(),
+            *contract.address(),
+            nonce,
+        )
+        .await?;
 
     // Alice signs delegation to contract
     let nonce = provider.get_transaction_count(alice2.address()).await?;
-    let signed_auth_2 = sign_authorization(&alice2, anvil.chain_id(), *contract.address(), nonce);
+    let signed_auth_2 = registry
+        .sign_authorization(
+            &provider,
+            &alice2,
+            anvil.chain_id(),
+            *contract.address(),
+            nonce,
+        )
+        .await?;
 
     let emit_hello_calldata = contract.emitHello().calldata().to_owned();
 
@@ -97,6 +82,7 @@ async fn main() -> anyhow::Result<()> {
         .with_authorization_list(vec![signed_auth_1, signed_auth_2])
         .with_input(emit_hello_calldata);
 
+    registry.check_transaction(&provider, &tx).await?;
     let pending_tx = provider.send_transaction(tx).await?;
     println!("Pending transaction... {}", pending_tx.tx_hash());
 
@@ -127,7 +113,9 @@ async fn main() -> anyhow::Result<()> {
 
     // Alice signs revocation (address = zero)
     let nonce = provider.get_transaction_count(alice.address()).await?;
-    let revoke_auth = sign_authorization(&alice, anvil.chain_id(), Address::ZERO, nonce);
+    let revoke_auth = registry
+        .sign_authorization(&provider, &alice, anvil.chain_id(), Address::ZERO, nonce)
+        .await?;
 
     let tx = TransactionRequest::default()
         .with_to(alice.address())
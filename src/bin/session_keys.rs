@@ -0,0 +1,297 @@
+//! Session keys for an account delegated to `SessionKeyDelegate`.
+//!
+//! Usage:
+//!   `session_keys issue <rpc_url> <owner_key> <session_key> <valid_until> <value_limit> <targets> <selectors>`
+//!   `session_keys list <rpc_url> <account>`
+//!   `session_keys revoke <rpc_url> <owner_key> <session_key>`
+//!   `session_keys execute <rpc_url> <sponsor_key> <session_key> <account> <to> <value> [data]`
+//!   `session_keys`
+//!
+//! `issue` and `revoke` are sent by the owner to its own account. `targets` and `selectors` are
+//! comma-separated, with `0x00000000` allowing calls without a selector. `execute` signs a
+//! one-call batch with the session key, checks it against the key's policy and has the sponsor
+//! simulate and submit it. Without arguments, spawns Anvil, delegates Alice, issues Carol a key for
+//! `EventLogger` and small payments to Dave, and shows what the key can and cannot do.
+
+use alloy::{
+    node_bindings::Anvil,
+    primitives::{Address, Bytes, U256},
+    providers::{Provider, ProviderBuilder, WalletProvider},
+    signers::local::PrivateKeySigner,
+    sol_types::SolCall,
+};
+use interact_eth::{
+    artifacts,
+    batch::latest_timestamp,
+    contracts::EventLogger,
+    decode::AbiRegistry,
+    error::Error,
+    presubmit::{self, PlannedCall},
+    registry::DelegateRegistry,
+    revert::{DecodeRevert, Revert},
+    session_keys::{self, Call, NO_SELECTOR, SessionKeyDelegate, SessionKeyInfo, SessionPolicy},
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [
+            cmd,
+            rpc_url,
+            owner,
+            key,
+            valid_until,
+            value_limit,
+            targets,
+            selectors,
+        ] if cmd == "issue" => {
+            let owner: PrivateKeySigner = owner.parse()?;
+            let provider = ProviderBuilder::new()
+                .wallet(owner.clone())
+                .connect_http(rpc_url.parse()?);
+            let policy = SessionPolicy {
+                key: key.parse()?,
+                valid_until: valid_until.parse()?,
+                value_limit: value_limit.parse()?,
+                targets: split(targets)?,
+                selectors: split(selectors)?,
+            };
+            let receipt = SessionKeyDelegate::new(owner.address(), &provider)
+                .call_builder(&policy.issue_call())
+                .send()
+                .await?
+                .get_receipt()
+                .await?;
+            println!("Issued {}: {}", policy.key, receipt.transaction_hash);
+            Ok(())
+        }
+        [cmd, rpc_url, account] if cmd == "list" => {
+            let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+            for info in session_keys::list(&provider, account.parse()?).await? {
+                print(&info);
+            }
+            Ok(())
+        }
+        [cmd, rpc_url, owner, key] if cmd == "revoke" => {
+            let owner: PrivateKeySigner = owner.parse()?;
+            let provider = ProviderBuilder::new()
+                .wallet(owner.clone())
+                .connect_http(rpc_url.parse()?);
+            let receipt = SessionKeyDelegate::new(owner.address(), &provider)
+                .call_builder(&session_keys::revoke_call(key.parse()?))
+                .send()
+                .await?
+                .get_receipt()
+                .await?;
+            println!("Revoked {key}: {}", receipt.transaction_hash);
+            Ok(())
+        }
+        [cmd, rpc_url, sponsor, key, account, to, value, data @ ..]
+            if cmd == "execute" && data.len() <= 1 =>
+        {
+            let sponsor: PrivateKeySigner = sponsor.parse()?;
+            let provider = ProviderBuilder::new()
+                .wallet(sponsor)
+                .connect_http(rpc_url.parse()?);
+            let call = Call {
+                to: to.parse()?,
+                value: value.parse()?,
+                data: data
+                    .first()
+                    .map(|d| d.parse())
+                    .transpose()?
+                    .unwrap_or_default(),
+            };
+            let hash = execute(&provider, &key.parse()?, account.parse()?, vec![call]).await?;
+            println!("Executed: {hash}");
+            Ok(())
+        }
+        [] => demo().await,
+        _ => anyhow::bail!(
+            "usage: session_keys [issue|list|revoke|execute ...], see the module documentation"
+        ),
+    }
+}
+
+fn split<T: std::str::FromStr>(list: &str) -> Result<Vec<T>, T::Err> {
+    list.split(',')
+        .filter(|item| !item.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+fn print(info: &SessionKeyInfo) {
+    let policy = &info.policy;
+    println!(
+        "{}: until {}, spent {} of {} wei",
+        policy.key, policy.valid_until, info.value_spent, policy.value_limit
+    );
+    println!("    targets:   {:?}", policy.targets);
+    println!("    selectors: {:?}", policy.selectors);
+}
+
+/// Signs `calls` with `key`, checks them against its policy on `account`, simulates them and
+/// sends them from the provider's wallet.
+async fn execute<P: Provider + WalletProvider>(
+    provider: &P,
+    key: &PrivateKeySigner,
+    account: Address,
+    calls: Vec<Call>,
+) -> anyhow::Result<alloy::primitives::TxHash> {
+    let info = session_keys::list(provider, account)
+        .await?
+        .into_iter()
+        .find(|info| info.policy.key == key.address())
+        .ok_or_else(|| anyhow::anyhow!("{} is not a session key of {account}", key.address()))?;
+    // The next block is later than the latest one, so check against that.
+    let now = latest_timestamp(provider).await? + 1;
+    info.policy.check(account, &calls, info.value_spent, now)?;
+
+    let delegate = SessionKeyDelegate::new(account, provider);
+    let nonce = delegate.nonce().call().await?;
+    let chain_id = provider.get_chain_id().await?;
+    let signature = session_keys::sign(key, chain_id, account, nonce, &calls)?;
+    let planned: Vec<PlannedCall> = calls.iter().map(PlannedCall::from).collect();
+    let call = delegate.executeWithSessionKey(calls, signature);
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&session_keys::abi());
+    presubmit::simulate_sponsored(
+        provider,
+        provider.default_signer_address(),
+        account,
+        None,
+        call.calldata().clone(),
+        &planned,
+        &abis,
+    )
+    .await?
+    .ensure_submittable()?;
+    let receipt = call.send().await?.get_receipt().await?;
+    anyhow::ensure!(receipt.status(), "{} reverted", receipt.transaction_hash);
+    Ok(receipt.transaction_hash)
+}
+
+async fn demo() -> anyhow::Result<()> {
+    // Ensure `anvil` is available in $PATH.
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+    let alice: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+    let dave: PrivateKeySigner = anvil.keys()[2].clone().into();
+    let carol: PrivateKeySigner = anvil.keys()[3].clone().into();
+
+    let sponsor = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let owner = ProviderBuilder::new()
+        .wallet(alice.clone())
+        .connect_http(anvil.endpoint_url());
+    let delegate = artifacts::deploy(&sponsor, "SessionKeyDelegate").await?;
+    let mut registry = DelegateRegistry::from_env()?;
+    registry.register("SessionKeyDelegate", anvil.chain_id(), delegate)?;
+    let logger = *EventLogger::deploy(&sponsor).await?.address();
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&session_keys::abi());
+
+    // Alice delegates and issues Carol's key in one transaction to herself.
+    let policy = SessionPolicy {
+        key: carol.address(),
+        valid_until: latest_timestamp(&sponsor).await? + 3600,
+        value_limit: U256::from(10_000_000_000_000_000u64),
+        targets: vec![logger, dave.address()],
+        selectors: vec![EventLogger::emitHelloCall::SELECTOR.into(), NO_SELECTOR],
+    };
+    // The transaction's own nonce is used first, so the authorization takes the next one.
+    let nonce = owner.get_transaction_count(alice.address()).await?;
+    let auth = registry
+        .sign_authorization(&owner, &alice, anvil.chain_id(), delegate, nonce + 1)
+        .await?;
+    let receipt = SessionKeyDelegate::new(alice.address(), &owner)
+        .call_builder(&policy.issue_call())
+        .authorization_list(vec![auth])
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    assert!(receipt.status());
+    println!("Issued Carol's key: {}", receipt.transaction_hash);
+    for info in session_keys::list(&sponsor, alice.address()).await? {
+        print(&info);
+    }
+
+    let pay_dave = |value: u64| Call {
+        to: dave.address(),
+        value: U256::from(value),
+        data: Bytes::new(),
+    };
+    let hello = Call {
+        to: logger,
+        value: U256::ZERO,
+        data: EventLogger::emitHelloCall {}.abi_encode().into(),
+    };
+
+    let dave_before = sponsor.get_balance(dave.address()).await?;
+    let hash = execute(
+        &sponsor,
+        &carol,
+        alice.address(),
+        vec![pay_dave(4_000_000_000_000_000), hello.clone()],
+    )
+    .await?;
+    println!("Carol's batch: {hash}");
+    assert_eq!(
+        sponsor.get_balance(dave.address()).await? - dave_before,
+        U256::from(4_000_000_000_000_000u64)
+    );
+
+    // Over the remaining allowance: refused locally, and by the account too.
+    let too_much = vec![pay_dave(7_000_000_000_000_000)];
+    let refused = execute(&sponsor, &carol, alice.address(), too_much.clone()).await;
+    println!("Over the limit: {}", refused.as_ref().unwrap_err());
+    assert!(matches!(
+        refused.unwrap_err().downcast::<Error>(),
+        Ok(Error::NotPermitted(_))
+    ));
+    let nonce = SessionKeyDelegate::new(alice.address(), &sponsor)
+        .nonce()
+        .call()
+        .await?;
+    let signature =
+        session_keys::sign(&carol, anvil.chain_id(), alice.address(), nonce, &too_much)?;
+    let result = SessionKeyDelegate::new(alice.address(), &sponsor)
+        .executeWithSessionKey(too_much, signature)
+        .call()
+        .await
+        .decode_revert(&abis, "executeWithSessionKey");
+    match result {
+        Err(Error::Revert(err)) => {
+            println!("{err}");
+            assert!(
+                matches!(err.revert, Revert::Custom { ref name, .. } if name == "ValueLimitExceeded")
+            );
+        }
+        Err(err) => anyhow::bail!("expected ValueLimitExceeded, got: {err}"),
+        Ok(_) => anyhow::bail!("expected ValueLimitExceeded, but the call succeeded"),
+    }
+
+    let receipt = SessionKeyDelegate::new(alice.address(), &owner)
+        .call_builder(&session_keys::revoke_call(carol.address()))
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    assert!(receipt.status());
+    println!("Revoked Carol's key: {}", receipt.transaction_hash);
+    assert!(
+        session_keys::list(&sponsor, alice.address())
+            .await?
+            .is_empty()
+    );
+    assert!(
+        execute(&sponsor, &carol, alice.address(), vec![hello])
+            .await
+            .is_err()
+    );
+    Ok(())
+}
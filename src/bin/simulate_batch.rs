@@ -0,0 +1,169 @@
+//! Example: simulate each call of a sponsored batch before paying for it.
+//!
+//! Spawns Anvil and plans a batch for Alice, delegated to `BatchCallAndSponsor`, that pays Dave,
+//! calls `EventLogger` and makes a call that reverts with "Invalid authority". The simulation
+//! names that call and blocks submission. Without it, a signature for the wrong executor nonce is
+//! refused by the simulated `execute`, and the correctly signed batch simulates cleanly and Bob
+//! submits it together with Alice's authorization.
+//!
+//! Usage: cargo run --bin simulate_batch
+
+use alloy::{
+    node_bindings::Anvil,
+    primitives::{Bytes, U256},
+    providers::{Provider, ProviderBuilder, WalletProvider},
+    signers::local::PrivateKeySigner,
+    sol_types::SolCall,
+};
+use interact_eth::{
+    batch,
+    contracts::{
+        BatchCallAndSponsor::{self, Call},
+        EventLogger,
+    },
+    decode::AbiRegistry,
+    error::Error,
+    presubmit::{self, BatchSimulation, PlannedCall},
+    registry::DelegateRegistry,
+    revert::Revert,
+};
+
+fn print(simulation: &BatchSimulation) {
+    for call in &simulation.calls {
+        println!("{call}");
+        for (address, delta) in &call.balance_deltas {
+            println!("    {address}: {delta:+}");
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Ensure `anvil` is available in $PATH.
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+    let alice: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+    let dave: PrivateKeySigner = anvil.keys()[2].clone().into();
+
+    let provider = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let chain_id = anvil.chain_id();
+    let executor = BatchCallAndSponsor::deploy(&provider).await?;
+    let logger = EventLogger::deploy(&provider).await?;
+    // Calling `execute(calls)` on a plain deployment reverts with "Invalid authority".
+    let guarded = BatchCallAndSponsor::deploy(&provider).await?;
+    let abis = AbiRegistry::from_artifacts()?;
+
+    let one_eth = U256::from(1_000_000_000_000_000_000u128);
+    let mut calls = vec![
+        Call {
+            to: dave.address(),
+            value: one_eth,
+            data: Bytes::new(),
+        },
+        Call {
+            to: *logger.address(),
+            value: U256::ZERO,
+            data: EventLogger::emitHelloCall {}.abi_encode().into(),
+        },
+        Call {
+            to: *guarded.address(),
+            value: U256::ZERO,
+            data: BatchCallAndSponsor::execute_0Call { calls: vec![] }
+                .abi_encode()
+                .into(),
+        },
+    ];
+
+    let planned: Vec<PlannedCall> = calls.iter().map(PlannedCall::from).collect();
+    let simulation = presubmit::simulate_batch(
+        &provider,
+        alice.address(),
+        Some(*executor.address()),
+        &planned,
+        &abis,
+    )
+    .await?;
+    print(&simulation);
+    assert_eq!(
+        simulation.calls[0].balance_deltas.get(&dave.address()),
+        Some(&one_eth.try_into()?)
+    );
+    match simulation.ensure_submittable() {
+        Err(Error::Revert(err)) => {
+            println!("Not submitting: {err}");
+            assert_eq!(err.revert, Revert::Reason("Invalid authority".into()));
+            assert!(err.context.starts_with("call #2"));
+        }
+        other => anyhow::bail!("expected the batch to be blocked, got {other:?}"),
+    }
+
+    // Without the failing call the batch is worth sponsoring, but only with a signature for the
+    // executor's current nonce. Alice is not delegated yet, so that nonce is zero.
+    calls.pop();
+    let planned: Vec<PlannedCall> = calls.iter().map(PlannedCall::from).collect();
+    let stale = BatchCallAndSponsor::execute_1Call {
+        calls: calls.clone(),
+        signature: batch::sign(&alice, batch::digest(U256::from(1), &calls))?,
+    };
+    let simulation = presubmit::simulate_sponsored(
+        &provider,
+        provider.default_signer_address(),
+        alice.address(),
+        Some(*executor.address()),
+        stale.abi_encode().into(),
+        &planned,
+        &abis,
+    )
+    .await?;
+    print(&simulation);
+    match simulation.ensure_submittable() {
+        Err(Error::Revert(err)) => {
+            println!("Not submitting: {err}");
+            assert_eq!(err.revert, Revert::Reason("Invalid signature".into()));
+        }
+        other => anyhow::bail!("expected the stale signature to be refused, got {other:?}"),
+    }
+
+    let signature = batch::sign(&alice, batch::digest(U256::ZERO, &calls))?;
+    let input = BatchCallAndSponsor::execute_1Call {
+        calls: calls.clone(),
+        signature: signature.clone(),
+    };
+    let simulation = presubmit::simulate_sponsored(
+        &provider,
+        provider.default_signer_address(),
+        alice.address(),
+        Some(*executor.address()),
+        input.abi_encode().into(),
+        &planned,
+        &abis,
+    )
+    .await?;
+    print(&simulation);
+    simulation.ensure_submittable()?;
+
+    let nonce = provider.get_transaction_count(alice.address()).await?;
+    let mut registry = DelegateRegistry::from_env()?;
+    registry.register("BatchCallAndSponsor", chain_id, *executor.address())?;
+    let auth = registry
+        .sign_authorization(&provider, &alice, chain_id, *executor.address(), nonce)
+        .await?;
+    let dave_before = provider.get_balance(dave.address()).await?;
+    let receipt = BatchCallAndSponsor::new(alice.address(), &provider)
+        .execute_1(calls, signature)
+        .authorization_list(vec![auth])
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    println!("Transaction hash: {}", receipt.transaction_hash);
+    assert!(receipt.status());
+    assert_eq!(
+        provider.get_balance(dave.address()).await? - dave_before,
+        one_eth
+    );
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+//! Dry-runs the delegate → call → revoke flow of `send_eip7702_transaction` in one go.
+//!
+//! Usage: `simulate_flow [--replay]`
+//!
+//! Spawns Anvil, deploys `EventLogger` and simulates the flow with `eth_simulateV1`, or with a
+//! snapshot/replay when `--replay` is given. Nothing but the deployment lands on chain.
+
+use alloy::{
+    network::{TransactionBuilder, TransactionBuilder7702},
+    node_bindings::Anvil,
+    providers::{Provider, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+};
+use interact_eth::{
+    authorization::{delegated_to, sign_revocation},
+    contracts::EventLogger,
+    decode::AbiRegistry,
+    registry::DelegateRegistry,
+    simulate::{Step, replay, simulate},
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let force_replay = std::env::args().any(|a| a == "--replay");
+
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+    let alice: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+
+    let deployer = ProviderBuilder::new()
+        .wallet(bob.clone())
+        .connect_http(anvil.endpoint_url());
+    let contract = EventLogger::deploy(&deployer).await?;
+
+    // Senders are not asked to sign, but authorizations are.
+    let provider = ProviderBuilder::new().connect_http(anvil.endpoint_url());
+    let nonce = provider.get_transaction_count(alice.address()).await?;
+    let mut registry = DelegateRegistry::from_env()?;
+    registry.register("EventLogger", anvil.chain_id(), *contract.address())?;
+    let delegate = registry
+        .sign_authorization(
+            &provider,
+            &alice,
+            anvil.chain_id(),
+            *contract.address(),
+            nonce,
+        )
+        .await?;
+    let revoke = sign_revocation(&alice, anvil.chain_id(), nonce + 1)?;
+
+    let sponsored = || {
+        TransactionRequest::default()
+            .with_from(bob.address())
+            .with_to(alice.address())
+    };
+    let steps = [
+        Step::new(
+            "delegate + emitHello",
+            sponsored()
+                .with_authorization_list(vec![delegate])
+                .with_input(contract.emitHello().calldata().clone()),
+        ),
+        Step::new(
+            "emitWorld",
+            sponsored().with_input(contract.emitWorld("world".into()).calldata().clone()),
+        ),
+        Step::new("revoke", sponsored().with_authorization_list(vec![revoke])),
+    ];
+
+    let accounts = [alice.address(), bob.address()];
+    let simulation = if force_replay {
+        replay(&provider, &steps, &accounts).await?
+    } else {
+        simulate(&provider, &steps, &accounts).await?
+    };
+
+    let abis = AbiRegistry::from_artifacts()?;
+    println!("backend: {:?}", simulation.backend);
+    for step in &simulation.steps {
+        let status = if step.success { "ok" } else { "failed" };
+        println!(
+            "{:22} {status:6} gas {:7} logs {} output {}",
+            step.label,
+            step.gas_used,
+            step.logs.len(),
+            step.output
+        );
+        if !step.success {
+            println!("{:22} revert: {}", "", abis.decode_revert(&step.output));
+        }
+        if let Some(error) = &step.error {
+            println!("{:22} error: {error}", "");
+        }
+    }
+    for account in &simulation.final_state {
+        let code = match delegated_to(&account.code) {
+            Some(delegate) => format!("delegated to {delegate}"),
+            None if account.code.is_empty() => "no code".into(),
+            None => format!("{} bytes of code", account.code.len()),
+        };
+        let nonce = account
+            .nonce
+            .map(|n| format!(", nonce {n}"))
+            .unwrap_or_default();
+        println!(
+            "{}: {code}, balance {}{nonce}",
+            account.address, account.balance
+        );
+    }
+
+    assert!(simulation.steps.iter().all(|s| s.success));
+    assert_eq!(simulation.steps[0].logs.len(), 1);
+    assert_eq!(simulation.steps[1].logs.len(), 1);
+    assert!(simulation.final_state[0].code.is_empty(), "revoked");
+    assert!(
+        provider.get_code_at(alice.address()).await?.is_empty(),
+        "the simulation must not delegate Alice"
+    );
+
+    Ok(())
+}
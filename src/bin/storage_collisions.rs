@@ -0,0 +1,76 @@
+//! Checks whether re-delegating an account to another contract collides with its storage.
+//!
+//! Usage: `storage_collisions <account> <proposed-contract> [current-contract]`
+//!
+//! The node is taken from `RPC_URL` (default `http://localhost:8545`). The current delegate is
+//! identified from the account's code unless given; both layouts are read from the artifacts.
+
+use alloy::{
+    primitives::Address,
+    providers::{Provider, ProviderBuilder},
+};
+use anyhow::Context;
+use interact_eth::{
+    artifacts::{self, Artifact},
+    authorization::delegated_to,
+    layout::{StorageLayout, check_account, recommendation},
+    verify::verify_code,
+};
+
+fn storage_layout(contract: &str) -> anyhow::Result<StorageLayout> {
+    Artifact::load(contract)?.storage_layout.with_context(|| {
+        format!("the {contract} artifact has no storage layout, build it with `extra_output = [\"storageLayout\"]`")
+    })
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (Some(account), Some(proposed)) = (args.first(), args.get(1)) else {
+        anyhow::bail!("usage: storage_collisions <account> <proposed-contract> [current-contract]");
+    };
+    let account: Address = account.parse()?;
+
+    let rpc_url = std::env::var("RPC_URL").unwrap_or_else(|_| "http://localhost:8545".into());
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+
+    let current = match args.get(2) {
+        Some(current) => Some(current.clone()),
+        // Not delegated, or delegated to code no artifact matches: only the live slots can tell.
+        None if delegated_to(&provider.get_code_at(account).await?).is_none() => None,
+        None => verify_code(&provider, account, &artifacts::all()?)
+            .await?
+            .matched
+            .map(|(name, _)| name),
+    };
+    match &current {
+        Some(name) => println!("current delegate:  {name}"),
+        None => println!("current delegate:  unknown, checking live storage only"),
+    }
+    println!("proposed delegate: {proposed}");
+
+    let current_layout = current.as_deref().map(storage_layout).transpose()?;
+    let proposed_layout = storage_layout(proposed)?;
+    let collisions = check_account(
+        &provider,
+        account,
+        current_layout.as_ref(),
+        &proposed_layout,
+    )
+    .await?;
+
+    if collisions.is_empty() {
+        println!("no collisions");
+        return Ok(());
+    }
+    for collision in &collisions {
+        println!("  {collision}");
+    }
+    println!("recommendation: {}", recommendation(proposed));
+
+    anyhow::ensure!(
+        !collisions.iter().any(|c| c.is_incompatible()),
+        "incompatible storage layouts"
+    );
+    Ok(())
+}
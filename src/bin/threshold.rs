@@ -0,0 +1,258 @@
+//! M-of-N control of a delegated EOA through `ThresholdDelegate`.
+//!
+//! Usage:
+//!   `threshold setup <rpc_url> <account_key> <delegate> <threshold> <owners>`
+//!   `threshold propose <rpc_url> <account> <calls.json> <batch.json>`
+//!   `threshold sign <batch.json> <owner_key> <signature.json>`
+//!   `threshold submit <rpc_url> <sponsor_key> <batch.json> <signature.json>...`
+//!   `threshold`
+//!
+//! `setup` delegates the account to `delegate` and sets the comma-separated `owners` in one
+//! transaction the account sends to itself. `propose` writes the calls (a JSON array of
+//! `{ "to", "value", "data" }`) with the account's current nonce to a batch file, which each
+//! owner signs with `sign`. `submit` checks the collected signatures, puts them in the order the
+//! contract wants, simulates the batch and sends it from the sponsor. Without arguments, spawns Anvil and walks
+//! a 2-of-3 treasury through the same steps with files in a temporary directory.
+
+use std::path::{Path, PathBuf};
+
+use alloy::{
+    node_bindings::Anvil,
+    primitives::{Address, Bytes, U256},
+    providers::{Provider, ProviderBuilder, WalletProvider},
+    signers::local::PrivateKeySigner,
+};
+use interact_eth::{
+    artifacts,
+    decode::AbiRegistry,
+    error::Error,
+    presubmit::{self, PlannedCall},
+    registry::DelegateRegistry,
+    revert::{DecodeRevert, Revert},
+    threshold::{self, Call, Owners, PartialSignature, ThresholdBatch, ThresholdDelegate},
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [cmd, rpc_url, account, delegate, threshold, owners] if cmd == "setup" => {
+            let account: PrivateKeySigner = account.parse()?;
+            let provider = ProviderBuilder::new()
+                .wallet(account.clone())
+                .connect_http(rpc_url.parse()?);
+            let owners = Owners {
+                owners: owners
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()?,
+                threshold: threshold.parse()?,
+            };
+            let registry = DelegateRegistry::from_env()?;
+            let hash = setup(&provider, &registry, &account, delegate.parse()?, &owners).await?;
+            println!("Set up {}: {hash}", account.address());
+            Ok(())
+        }
+        [cmd, rpc_url, account, calls, out] if cmd == "propose" => {
+            let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+            let calls: Vec<Call> = serde_json::from_str(&std::fs::read_to_string(calls)?)?;
+            let batch = propose(&provider, account.parse()?, calls).await?;
+            write(out, &batch)?;
+            println!("Batch {} written to {out}", batch.digest());
+            Ok(())
+        }
+        [cmd, batch, owner, out] if cmd == "sign" => {
+            let batch = ThresholdBatch::load(batch)?;
+            let partial = batch.sign(&owner.parse()?)?;
+            write(out, &partial)?;
+            println!("{} signed {}", partial.signer, partial.digest);
+            Ok(())
+        }
+        [cmd, rpc_url, sponsor, batch, partials @ ..] if cmd == "submit" => {
+            let sponsor: PrivateKeySigner = sponsor.parse()?;
+            let provider = ProviderBuilder::new()
+                .wallet(sponsor)
+                .connect_http(rpc_url.parse()?);
+            let mut abis = AbiRegistry::from_artifacts()?;
+            abis.add(&threshold::abi());
+            let partials = partials
+                .iter()
+                .map(PartialSignature::load)
+                .collect::<Result<Vec<_>, _>>()?;
+            let hash = submit(&provider, &abis, &ThresholdBatch::load(batch)?, &partials).await?;
+            println!("Executed: {hash}");
+            Ok(())
+        }
+        [] => demo().await,
+        _ => anyhow::bail!(
+            "usage: threshold [setup|propose|sign|submit ...], see the module documentation"
+        ),
+    }
+}
+
+fn write(path: impl AsRef<Path>, value: &impl serde::Serialize) -> anyhow::Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+/// Delegates `account` and sets its owners in a transaction to itself.
+async fn setup<P: Provider>(
+    provider: &P,
+    registry: &DelegateRegistry,
+    account: &PrivateKeySigner,
+    delegate: Address,
+    owners: &Owners,
+) -> anyhow::Result<alloy::primitives::TxHash> {
+    let chain_id = provider.get_chain_id().await?;
+    // The transaction's own nonce is used first, so the authorization takes the next one.
+    let nonce = provider.get_transaction_count(account.address()).await?;
+    let auth = registry
+        .sign_authorization(provider, account, chain_id, delegate, nonce + 1)
+        .await?;
+    let receipt = ThresholdDelegate::new(account.address(), provider)
+        .call_builder(&owners.set_call())
+        .authorization_list(vec![auth])
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    anyhow::ensure!(receipt.status(), "{} reverted", receipt.transaction_hash);
+    Ok(receipt.transaction_hash)
+}
+
+async fn propose<P: Provider>(
+    provider: &P,
+    account: Address,
+    calls: Vec<Call>,
+) -> anyhow::Result<ThresholdBatch> {
+    Ok(ThresholdBatch {
+        chain_id: provider.get_chain_id().await?,
+        account,
+        nonce: ThresholdDelegate::new(account, provider)
+            .nonce()
+            .call()
+            .await?,
+        calls,
+    })
+}
+
+/// Aggregates `partials` for the account's current owners, simulates the batch and sends it.
+async fn submit<P: Provider + WalletProvider>(
+    provider: &P,
+    abis: &AbiRegistry,
+    batch: &ThresholdBatch,
+    partials: &[PartialSignature],
+) -> anyhow::Result<alloy::primitives::TxHash> {
+    let owners = threshold::owners(provider, batch.account).await?;
+    let signatures = threshold::aggregate(batch, partials, &owners)?;
+    let delegate = ThresholdDelegate::new(batch.account, provider);
+    let call = delegate.call_builder(&batch.call(signatures));
+    let planned: Vec<PlannedCall> = batch.calls.iter().map(PlannedCall::from).collect();
+    presubmit::simulate_sponsored(
+        provider,
+        provider.default_signer_address(),
+        batch.account,
+        None,
+        call.calldata().clone(),
+        &planned,
+        abis,
+    )
+    .await?
+    .ensure_submittable()?;
+    let receipt = call.send().await?.get_receipt().await?;
+    anyhow::ensure!(receipt.status(), "{} reverted", receipt.transaction_hash);
+    Ok(receipt.transaction_hash)
+}
+
+async fn demo() -> anyhow::Result<()> {
+    // Ensure `anvil` is available in $PATH.
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+    let treasury: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+    let dave: PrivateKeySigner = anvil.keys()[2].clone().into();
+    let signers: Vec<PrivateKeySigner> = (4..7).map(|i| anvil.keys()[i].clone().into()).collect();
+
+    let sponsor = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let delegate = artifacts::deploy(&sponsor, "ThresholdDelegate").await?;
+    let mut registry = DelegateRegistry::from_env()?;
+    registry.register("ThresholdDelegate", anvil.chain_id(), delegate)?;
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&threshold::abi());
+
+    let owners = Owners {
+        owners: signers.iter().map(|owner| owner.address()).collect(),
+        threshold: 2,
+    };
+    let account = ProviderBuilder::new()
+        .wallet(treasury.clone())
+        .connect_http(anvil.endpoint_url());
+    let hash = setup(&account, &registry, &treasury, delegate, &owners).await?;
+    println!("Treasury {} is 2-of-3: {hash}", treasury.address());
+    assert_eq!(
+        threshold::owners(&sponsor, treasury.address()).await?,
+        owners
+    );
+
+    let dir = std::env::temp_dir().join(format!("threshold-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let one_eth = U256::from(1_000_000_000_000_000_000u128);
+    let batch_file = dir.join("batch.json");
+    let calls = vec![Call {
+        to: dave.address(),
+        value: one_eth,
+        data: Bytes::new(),
+    }];
+    write(
+        &batch_file,
+        &propose(&sponsor, treasury.address(), calls).await?,
+    )?;
+
+    // Each owner signs on their own machine; here, the third and then the first.
+    let mut partial_files: Vec<PathBuf> = Vec::new();
+    for index in [2, 0] {
+        let batch = ThresholdBatch::load(&batch_file)?;
+        let file = dir.join(format!("owner-{index}.json"));
+        write(&file, &batch.sign(&signers[index])?)?;
+        partial_files.push(file);
+    }
+    let batch = ThresholdBatch::load(&batch_file)?;
+    let partials = partial_files
+        .iter()
+        .map(PartialSignature::load)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // One signature is not enough, neither for the aggregator nor for the account.
+    let refused = submit(&sponsor, &abis, &batch, &partials[..1]).await;
+    println!("One of three: {}", refused.unwrap_err());
+    let result = ThresholdDelegate::new(treasury.address(), &sponsor)
+        .call_builder(&batch.call(vec![partials[0].signature.clone()]))
+        .call()
+        .await
+        .decode_revert(&abis, "execute");
+    match result {
+        Err(Error::Revert(err)) => {
+            println!("{err}");
+            assert!(
+                matches!(err.revert, Revert::Custom { ref name, .. } if name == "NotEnoughSignatures")
+            );
+        }
+        Err(err) => anyhow::bail!("expected NotEnoughSignatures, got: {err}"),
+        Ok(_) => anyhow::bail!("expected NotEnoughSignatures, but the call succeeded"),
+    }
+
+    let dave_before = sponsor.get_balance(dave.address()).await?;
+    let hash = submit(&sponsor, &abis, &batch, &partials).await?;
+    println!("Two of three: {hash}");
+    assert_eq!(
+        sponsor.get_balance(dave.address()).await? - dave_before,
+        one_eth
+    );
+
+    // The nonce moved on, so the same signatures cannot be used again.
+    assert!(submit(&sponsor, &abis, &batch, &partials).await.is_err());
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,26 @@
+//! Prints the call tree of a transaction, marking frames that run delegated code.
+//!
+//! Usage: `trace <txhash>`
+//!
+//! The node is taken from `RPC_URL` (default `http://localhost:8545`) and must support
+//! `debug_traceTransaction` with the call tracer. Calls and reverts are decoded with the ABIs
+//! of the local artifacts.
+
+use alloy::{primitives::TxHash, providers::ProviderBuilder};
+use interact_eth::{decode::AbiRegistry, trace::call_tree};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let Some(hash) = std::env::args().nth(1) else {
+        anyhow::bail!("usage: trace <txhash>");
+    };
+    let hash: TxHash = hash.parse()?;
+
+    let rpc_url = std::env::var("RPC_URL").unwrap_or_else(|_| "http://localhost:8545".into());
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+
+    let tree = call_tree(&provider, hash).await?;
+    print!("{}", tree.render(&AbiRegistry::from_artifacts()?));
+
+    Ok(())
+}
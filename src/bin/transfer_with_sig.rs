@@ -0,0 +1,74 @@
+//! Example: Alice signs a transfer to Dave off-chain and Bob submits it to
+//! `SimpleTransfer.transferWithSig`.
+//!
+//! Needs the `SimpleTransfer` artifact, run `make contracts` first. The on-chain checks live in
+//! `tests/security.rs`.
+//!
+//! Usage: cargo run --bin transfer_with_sig
+
+use alloy::{
+    node_bindings::Anvil,
+    primitives::U256,
+    providers::{Provider, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+};
+use interact_eth::{
+    artifacts, contracts::SimpleTransfer, decode::AbiRegistry, revert::DecodeRevert,
+    signed_transfer::TransferIntent,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Ensure `anvil` is available in $PATH.
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+
+    // Alice pays, Bob submits, Dave receives.
+    let alice: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+    let dave: PrivateKeySigner = anvil.keys()[2].clone().into();
+
+    let provider = ProviderBuilder::new()
+        .wallet(bob.clone())
+        .connect_http(anvil.endpoint_url());
+    let address = artifacts::deploy(&provider, "SimpleTransfer").await?;
+    let contract = SimpleTransfer::new(address, &provider);
+    let abis = AbiRegistry::from_artifacts()?;
+
+    let amount = U256::from(1_000_000_000_000_000_000u128); // 1 ETH
+    let intent = TransferIntent {
+        from: alice.address(),
+        to: dave.address(),
+        amount,
+        nonce: U256::from(1),
+        contract: address,
+    };
+    let signature = intent.sign(&alice)?;
+    println!("Digest: {}", intent.digest());
+    println!("Signature: {signature}");
+    println!("Recovered signer: {}", intent.recover(&signature)?);
+
+    // The contract pays `to` out of `msg.value`, so the sponsor fronts the amount.
+    let dave_before = provider.get_balance(dave.address()).await?;
+    let receipt = contract
+        .call_builder(&intent.call(signature))
+        .value(amount)
+        .send()
+        .await
+        .decode_revert(&abis, "transferWithSig")?
+        .get_receipt()
+        .await?;
+    println!("Transaction hash: {}", receipt.transaction_hash);
+
+    let transfer = receipt
+        .decoded_log::<SimpleTransfer::Transfer>()
+        .ok_or_else(|| anyhow::anyhow!("no Transfer event"))?;
+    println!(
+        "Transferred {} wei from {} to {}, Dave's balance grew by {} wei",
+        transfer.amount,
+        transfer.from,
+        transfer.to,
+        provider.get_balance(dave.address()).await? - dave_before
+    );
+
+    Ok(())
+}
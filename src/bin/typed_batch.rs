@@ -0,0 +1,149 @@
+//! EIP-712 batches for `TypedBatchExecutor`.
+//!
+//! Usage:
+//!   `typed_batch json <chain_id> <account> <nonce> <to:wei[:data]>...`
+//!   `typed_batch verify <chain_id> <account> <nonce> <signature> <to:wei[:data]>...`
+//!   `typed_batch`
+//!
+//! `json` prints the typed data to sign with `eth_signTypedData_v4`, `verify` checks a signature
+//! a wallet returned for it. Without arguments, spawns Anvil, delegates Alice to
+//! `TypedBatchExecutor` and has Bob submit a batch Alice signed as typed data (run
+//! `make contracts` first).
+
+use alloy::{
+    node_bindings::Anvil,
+    primitives::{Address, Bytes, U256},
+    providers::{Provider, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+};
+use interact_eth::{
+    artifacts,
+    registry::DelegateRegistry,
+    typed_batch::{self, Batch, Call, TypedBatchExecutor},
+};
+
+fn parse_call(arg: &str) -> anyhow::Result<Call> {
+    let mut parts = arg.splitn(3, ':');
+    let to: Address = parts.next().unwrap_or_default().parse()?;
+    let value: U256 = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("call {arg:?} must be <to:wei[:data]>"))?
+        .parse()?;
+    let data: Bytes = parts
+        .next()
+        .map(str::parse)
+        .transpose()?
+        .unwrap_or_default();
+    Ok(Call { to, value, data })
+}
+
+fn parse_batch(nonce: &str, calls: &[String]) -> anyhow::Result<Batch> {
+    Ok(Batch {
+        calls: calls
+            .iter()
+            .map(|c| parse_call(c))
+            .collect::<anyhow::Result<_>>()?,
+        nonce: nonce.parse()?,
+    })
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [cmd, chain_id, account, nonce, calls @ ..] if cmd == "json" => {
+            let batch = parse_batch(nonce, calls)?;
+            let typed = typed_batch::typed_data(&batch, chain_id.parse()?, account.parse()?);
+            println!("{}", serde_json::to_string_pretty(&typed)?);
+            Ok(())
+        }
+        [cmd, chain_id, account, nonce, signature, calls @ ..] if cmd == "verify" => {
+            let batch = parse_batch(nonce, calls)?;
+            let signature: Bytes = signature.parse()?;
+            typed_batch::verify(&batch, chain_id.parse()?, account.parse()?, &signature)?;
+            println!("signature ok");
+            Ok(())
+        }
+        [] => demo().await,
+        _ => anyhow::bail!(
+            "usage: typed_batch [json <chain_id> <account> <nonce> <to:wei[:data]>... | \
+             verify <chain_id> <account> <nonce> <signature> <to:wei[:data]>...]"
+        ),
+    }
+}
+
+async fn demo() -> anyhow::Result<()> {
+    // Ensure `anvil` is available in $PATH.
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+    let alice: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+    let dave: PrivateKeySigner = anvil.keys()[2].clone().into();
+    let carol: PrivateKeySigner = anvil.keys()[3].clone().into();
+
+    let provider = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let chain_id = anvil.chain_id();
+    let executor = artifacts::deploy(&provider, "TypedBatchExecutor").await?;
+
+    let batch = Batch {
+        calls: vec![
+            Call {
+                to: dave.address(),
+                value: U256::from(1_000_000_000_000_000_000u128),
+                data: Bytes::new(),
+            },
+            Call {
+                to: carol.address(),
+                value: U256::from(500_000_000_000_000_000u128),
+                data: Bytes::new(),
+            },
+        ],
+        nonce: U256::ZERO,
+    };
+    let typed = typed_batch::typed_data(&batch, chain_id, alice.address());
+    println!("{}", serde_json::to_string_pretty(&typed)?);
+
+    let signature = typed_batch::sign(&alice, &batch, chain_id)?;
+    typed_batch::verify(&batch, chain_id, alice.address(), &signature)?;
+    println!("Signature: {signature}");
+
+    // Bob delegates Alice to the executor and submits the batch in the same transaction.
+    let nonce = provider.get_transaction_count(alice.address()).await?;
+    let mut registry = DelegateRegistry::from_env()?;
+    registry.register("TypedBatchExecutor", chain_id, executor)?;
+    let auth = registry
+        .sign_authorization(&provider, &alice, chain_id, executor, nonce)
+        .await?;
+    let dave_before = provider.get_balance(dave.address()).await?;
+    let carol_before = provider.get_balance(carol.address()).await?;
+    let receipt = TypedBatchExecutor::new(alice.address(), &provider)
+        .execute_1(batch.clone(), signature)
+        .authorization_list(vec![auth])
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    println!("Transaction hash: {}", receipt.transaction_hash);
+    assert!(receipt.status());
+
+    let hash = TypedBatchExecutor::new(alice.address(), &provider)
+        .hashBatch(batch.clone())
+        .call()
+        .await?;
+    assert_eq!(
+        hash,
+        typed_batch::signing_hash(&batch, chain_id, alice.address()),
+        "the executor hashes the batch like the wallet"
+    );
+    assert_eq!(
+        provider.get_balance(dave.address()).await? - dave_before,
+        batch.calls[0].value
+    );
+    assert_eq!(
+        provider.get_balance(carol.address()).await? - carol_before,
+        batch.calls[1].value
+    );
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! Example: ERC-4337 user operations from an EIP-7702 account, bundled locally.
+//!
+//! Spawns Anvil and deploys `MinimalEntryPoint` and `Simple7702Account` (run `make contracts`
+//! first). Alice signs an authorization to the account and a user operation that pays Dave and
+//! calls `EventLogger`; Bob's local bundler submits it with `handleOps`, delegating Alice in the
+//! same transaction. A second operation, now without authorization, is bundled together with
+//! one whose signature is wrong, which the bundler drops.
+//!
+//! Usage: cargo run --bin user_operation
+//!
+//! `tests/user_operation.rs` runs the same flow with assertions.
+
+use alloy::{
+    node_bindings::Anvil,
+    primitives::{Bytes, U256, aliases::U192},
+    providers::{Provider, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+    sol_types::{SolCall, SolValue},
+};
+use interact_eth::{
+    artifacts,
+    contracts::EventLogger,
+    decode::AbiRegistry,
+    registry::DelegateRegistry,
+    user_operation::{
+        self, Bundler, EntryPoint, Simple7702Account, Simple7702Account::Call, UserOperation,
+    },
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Ensure `anvil` is available in $PATH.
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+    let alice: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+    let dave: PrivateKeySigner = anvil.keys()[2].clone().into();
+    let mallory: PrivateKeySigner = anvil.keys()[3].clone().into();
+
+    let provider = ProviderBuilder::new()
+        .wallet(bob.clone())
+        .connect_http(anvil.endpoint_url());
+    let chain_id = anvil.chain_id();
+    let entry_point = artifacts::deploy(&provider, "MinimalEntryPoint").await?;
+    let account =
+        artifacts::deploy_with_args(&provider, "Simple7702Account", &entry_point.abi_encode())
+            .await?;
+    let logger = EventLogger::deploy(&provider).await?;
+    let mut registry = DelegateRegistry::from_env()?;
+    registry.register("Simple7702Account", chain_id, account)?;
+
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&user_operation::abi());
+    let fees = provider.estimate_eip1559_fees().await?;
+    let one_eth = U256::from(1_000_000_000_000_000_000u128);
+    let user_op_nonce = || async {
+        EntryPoint::new(entry_point, &provider)
+            .getNonce(alice.address(), U192::ZERO)
+            .call()
+            .await
+    };
+
+    let call_data: Bytes = Simple7702Account::executeBatchCall {
+        calls: vec![
+            Call {
+                to: dave.address(),
+                value: one_eth,
+                data: Bytes::new(),
+            },
+            Call {
+                to: *logger.address(),
+                value: U256::ZERO,
+                data: EventLogger::emitHelloCall {}.abi_encode().into(),
+            },
+        ],
+    }
+    .abi_encode()
+    .into();
+    let mut op = UserOperation::new(alice.address(), user_op_nonce().await?, call_data.clone());
+    op.max_fee_per_gas = fees.max_fee_per_gas;
+    op.max_priority_fee_per_gas = fees.max_priority_fee_per_gas;
+    let nonce = provider.get_transaction_count(alice.address()).await?;
+    op.authorization = Some(
+        registry
+            .sign_authorization(&provider, &alice, chain_id, account, nonce)
+            .await?,
+    );
+    op.signature = op.sign(&alice, entry_point, chain_id)?;
+    println!("UserOperation hash: {}", op.hash(entry_point, chain_id));
+
+    let mut bundler = Bundler::new(entry_point, bob.address());
+    let dave_before = provider.get_balance(dave.address()).await?;
+    bundler.add(op);
+    let bundle = bundler.bundle(&provider, &abis).await?;
+    let receipt = bundle
+        .receipt
+        .ok_or_else(|| anyhow::anyhow!("nothing was bundled: {:?}", bundle.dropped))?;
+    println!("handleOps: {}", receipt.transaction_hash);
+    for result in &bundle.results {
+        println!("{result}");
+    }
+    println!(
+        "Dave received {} wei",
+        provider.get_balance(dave.address()).await? - dave_before
+    );
+
+    // Alice is delegated now, so her next operation needs no authorization. Mallory's copy of
+    // it is signed with the wrong key and fails validation.
+    let mut op = UserOperation::new(alice.address(), user_op_nonce().await?, call_data);
+    op.max_fee_per_gas = fees.max_fee_per_gas;
+    op.max_priority_fee_per_gas = fees.max_priority_fee_per_gas;
+    let mut forged = op.clone();
+    forged.signature = forged.sign(&mallory, entry_point, chain_id)?;
+    op.signature = op.sign(&alice, entry_point, chain_id)?;
+    bundler.add(forged);
+    bundler.add(op);
+
+    let bundle = bundler.bundle(&provider, &abis).await?;
+    for dropped in &bundle.dropped {
+        println!("Dropped {}: {}", dropped.user_op_hash, dropped.revert);
+    }
+    for result in &bundle.results {
+        println!("{result}");
+    }
+    Ok(())
+}
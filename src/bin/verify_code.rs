@@ -0,0 +1,51 @@
+//! Verifies that the code at an address matches one of the local Forge artifacts.
+//!
+//! Usage: `verify_code <address> [contract]`
+//!
+//! The node is taken from `RPC_URL` (default `http://localhost:8545`). For an EOA delegated
+//! via EIP-7702 the delegate's code is verified.
+
+use alloy::{primitives::Address, providers::ProviderBuilder};
+use interact_eth::{
+    artifacts::{self, Artifact},
+    verify::{CodeMatch, verify_code},
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let Some(address) = args.next() else {
+        anyhow::bail!("usage: verify_code <address> [contract]");
+    };
+    let address: Address = address.parse()?;
+
+    let candidates = match args.next() {
+        Some(contract) => vec![(contract.clone(), Artifact::load(&contract)?)],
+        None => artifacts::all()?,
+    };
+
+    let rpc_url = std::env::var("RPC_URL").unwrap_or_else(|_| "http://localhost:8545".into());
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+
+    let verification = verify_code(&provider, address, &candidates).await?;
+
+    println!("address:  {}", verification.address);
+    if let Some(delegate) = verification.delegate {
+        println!("delegate: {delegate}");
+    }
+    println!("codehash: {}", verification.codehash);
+    println!("size:     {} bytes", verification.code.len());
+
+    match verification.matched {
+        Some((name, CodeMatch::Exact)) => println!("match:    {name}"),
+        Some((name, CodeMatch::IgnoringMetadata)) => {
+            println!("match:    {name} (metadata hash differs)")
+        }
+        None => {
+            let names: Vec<_> = candidates.iter().map(|(name, _)| name.as_str()).collect();
+            anyhow::bail!("mismatch: code matches none of {}", names.join(", "));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,157 @@
+//! EIP-5792 wallet JSON-RPC server.
+//!
+//! Usage:
+//!   `WALLET_RPC_TOKEN=<token> wallet_server <rpc_url> <listen_addr> <delegate> <sponsor_key> <account_key>...`
+//!   `wallet_server`
+//!
+//! Serves `wallet_sendCalls`, `wallet_getCallsStatus` and `wallet_getCapabilities` for the
+//! given accounts, delegating them to `delegate` (a `BatchCallAndSponsorHardened` deployment
+//! approved by the delegate registry, see `DELEGATES_FILE`) with their first batch. Batches
+//! requesting `paymasterService` are paid for by the sponsor. Dapps must send the token as
+//! `Authorization: Bearer <token>`. Without arguments, spawns Anvil (run `make contracts` first),
+//! serves Alice's account with Bob as the sponsor and sends a sponsored batch through the server
+//! as a dapp would. `tests/wallet_rpc.rs` checks the same flow.
+
+use std::{sync::Arc, time::Duration};
+
+use alloy::{
+    network::EthereumWallet,
+    node_bindings::Anvil,
+    primitives::U256,
+    providers::{Provider, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+    sol_types::SolCall,
+    transports::http::reqwest::Url,
+};
+use interact_eth::{
+    artifacts,
+    batch::Executor,
+    contracts::EventLogger,
+    registry::DelegateRegistry,
+    wallet_rpc::{self, WalletConfig, WalletRpc},
+};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [rpc_url, listen, delegate, sponsor, accounts @ ..] if !accounts.is_empty() => {
+            let token = std::env::var("WALLET_RPC_TOKEN")
+                .map_err(|_| anyhow::anyhow!("set WALLET_RPC_TOKEN to the dapps' bearer token"))?;
+            let sponsor: PrivateKeySigner = sponsor.parse()?;
+            let accounts = accounts
+                .iter()
+                .map(|key| key.parse())
+                .collect::<Result<Vec<PrivateKeySigner>, _>>()?;
+            let provider = connect(rpc_url.parse()?, &sponsor, &accounts);
+            let config = WalletConfig {
+                chain_id: provider.get_chain_id().await?,
+                delegate: delegate.parse()?,
+                executor: Executor::BatchCallAndSponsorHardened,
+                accounts,
+                sponsor: Some(sponsor),
+                registry: DelegateRegistry::from_env()?,
+            };
+            let listener = TcpListener::bind(listen).await?;
+            println!("Serving EIP-5792 on http://{}", listener.local_addr()?);
+            let wallet = Arc::new(WalletRpc::new(config, provider));
+            wallet_rpc::serve(wallet, listener, token).await?;
+            Ok(())
+        }
+        [] => demo().await,
+        _ => anyhow::bail!(
+            "usage: wallet_server [<rpc_url> <listen_addr> <delegate> <sponsor_key> <account_key>...]"
+        ),
+    }
+}
+
+fn connect(
+    url: Url,
+    sponsor: &PrivateKeySigner,
+    accounts: &[PrivateKeySigner],
+) -> impl Provider + 'static {
+    let mut wallet = EthereumWallet::new(sponsor.clone());
+    for account in accounts {
+        wallet.register_signer(account.clone());
+    }
+    ProviderBuilder::new().wallet(wallet).connect_http(url)
+}
+
+async fn demo() -> anyhow::Result<()> {
+    // Ensure `anvil` is available in $PATH.
+    let anvil = Anvil::new().arg("--hardfork").arg("prague").try_spawn()?;
+    let alice: PrivateKeySigner = anvil.keys()[0].clone().into();
+    let bob: PrivateKeySigner = anvil.keys()[1].clone().into();
+    let dave: PrivateKeySigner = anvil.keys()[2].clone().into();
+
+    let provider = connect(anvil.endpoint_url(), &bob, std::slice::from_ref(&alice));
+    let delegate = artifacts::deploy(&provider, "BatchCallAndSponsorHardened").await?;
+    let logger = *EventLogger::deploy(&provider).await?.address();
+    let mut registry = DelegateRegistry::from_env()?;
+    registry.register("BatchCallAndSponsorHardened", anvil.chain_id(), delegate)?;
+    let config = WalletConfig {
+        chain_id: anvil.chain_id(),
+        delegate,
+        executor: Executor::BatchCallAndSponsorHardened,
+        accounts: vec![alice.clone()],
+        sponsor: Some(bob.clone()),
+        registry,
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}", listener.local_addr()?);
+    // Any 32 random bytes make a token; a fresh key is the handiest source.
+    let token = PrivateKeySigner::random().to_bytes().to_string();
+    tokio::spawn(wallet_rpc::serve(
+        Arc::new(WalletRpc::new(config, provider)),
+        listener,
+        token.clone(),
+    ));
+
+    // The dapp's side: a plain JSON-RPC client talking to the wallet.
+    let dapp = wallet_rpc::connect(url.parse()?, &token)?;
+    let chain_id = format!("{:#x}", anvil.chain_id());
+    let capabilities: Value = dapp
+        .raw_request(
+            "wallet_getCapabilities".into(),
+            (alice.address(), [&chain_id]),
+        )
+        .await?;
+    println!("Capabilities: {capabilities}");
+
+    let one_eth = U256::from(1_000_000_000_000_000_000u128);
+    let sent: Value = dapp
+        .raw_request(
+            "wallet_sendCalls".into(),
+            [json!({
+                "version": wallet_rpc::VERSION,
+                "from": alice.address(),
+                "chainId": chain_id,
+                "atomicRequired": true,
+                "calls": [
+                    { "to": dave.address(), "value": one_eth },
+                    { "to": logger, "data": EventLogger::emitHelloCall {}.abi_encode() },
+                ],
+                "capabilities": {
+                    "paymasterService": { "url": "https://paymaster.invalid" }
+                },
+            })],
+        )
+        .await?;
+    let id = sent["id"].as_str().unwrap_or_default().to_string();
+    println!("Batch id: {id}");
+
+    let status = loop {
+        let status: Value = dapp
+            .raw_request("wallet_getCallsStatus".into(), [&id])
+            .await?;
+        if status["status"] != 100 {
+            break status;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    };
+    println!("{}", serde_json::to_string_pretty(&status)?);
+    Ok(())
+}
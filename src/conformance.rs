@@ -0,0 +1,383 @@
+//! Runs one EIP-7702 scenario against two clients and diffs what they report.
+//!
+//! Both nodes are expected to fund the accounts of [`DEV_MNEMONIC`], which is the case for
+//! `anvil` and `reth --dev`. Balances are compared as net changes with the fees paid by each
+//! account added back, so different base fees on the two chains do not show up as mismatches.
+
+use std::fmt;
+
+use alloy::{
+    network::{TransactionBuilder, TransactionBuilder7702},
+    primitives::{Address, B256, Bytes, I256, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::{TransactionReceipt, TransactionRequest},
+    signers::local::{MnemonicBuilder, PrivateKeySigner},
+    transports::http::reqwest::Url,
+};
+
+use crate::{
+    authorization::sign_revocation,
+    contracts::EventLogger,
+    error::{Error, Result},
+    registry::DelegateRegistry,
+};
+
+/// Mnemonic funding the dev accounts of both `anvil` and `reth --dev`.
+pub const DEV_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+pub fn dev_signer(index: u32) -> Result<PrivateKeySigner> {
+    Ok(MnemonicBuilder::try_from_phrase_nth(DEV_MNEMONIC, index)?)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountSnapshot {
+    pub label: &'static str,
+    pub address: Address,
+    pub code: Bytes,
+    pub nonce: u64,
+    /// Balance change since the start of the scenario, excluding fees paid by the account.
+    pub balance_change: I256,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepReport {
+    pub label: &'static str,
+    pub status: bool,
+    pub gas_used: u64,
+    pub logs: Vec<LogEntry>,
+    pub accounts: Vec<AccountSnapshot>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScenarioReport {
+    pub client: String,
+    pub steps: Vec<StepReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub step: String,
+    pub field: String,
+    pub left: String,
+    pub right: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {} != {}",
+            self.step, self.field, self.left, self.right
+        )
+    }
+}
+
+struct Tracked {
+    label: &'static str,
+    address: Address,
+    initial_balance: U256,
+    fees_paid: U256,
+}
+
+/// Deploys `EventLogger`, delegates Alice to it, calls it through a sponsored transaction,
+/// sends value to the delegated account (which has no `receive`) and finally revokes.
+///
+/// `registry` must list `EventLogger`; the new deployment is registered on a copy of it.
+pub async fn run_scenario(
+    client: &str,
+    rpc_url: Url,
+    registry: &DelegateRegistry,
+) -> Result<ScenarioReport> {
+    let sponsor = dev_signer(0)?;
+    let alice = dev_signer(1)?;
+
+    let provider = ProviderBuilder::new()
+        .wallet(sponsor.clone())
+        .connect_http(rpc_url);
+    let chain_id = provider.get_chain_id().await.map_err(Error::rpc)?;
+
+    let mut tracked = Vec::new();
+    for (label, address) in [("sponsor", sponsor.address()), ("alice", alice.address())] {
+        tracked.push(Tracked {
+            label,
+            address,
+            initial_balance: provider.get_balance(address).await.map_err(Error::rpc)?,
+            fees_paid: U256::ZERO,
+        });
+    }
+
+    let mut steps = Vec::new();
+
+    let receipt = EventLogger::deploy_builder(&provider)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    let address = receipt.contract_address.ok_or_else(|| {
+        Error::UnexpectedResponse(format!("{client}: deployment did not create a contract"))
+    })?;
+    let contract = EventLogger::new(address, &provider);
+    steps.push(record_step(&provider, "deploy", &receipt, &mut tracked).await?);
+
+    let mut registry = registry.clone();
+    registry.register("EventLogger", chain_id, address)?;
+    let nonce = provider
+        .get_transaction_count(alice.address())
+        .await
+        .map_err(Error::rpc)?;
+    let auth = registry
+        .sign_authorization(&provider, &alice, chain_id, address, nonce)
+        .await?;
+    let tx = TransactionRequest::default()
+        .with_to(alice.address())
+        .with_authorization_list(vec![auth])
+        .with_input(contract.emitHello().calldata().to_owned());
+    let receipt = provider
+        .send_transaction(tx)
+        .await
+        .map_err(Error::rpc)?
+        .get_receipt()
+        .await?;
+    steps.push(record_step(&provider, "delegate+emitHello", &receipt, &mut tracked).await?);
+
+    let tx = TransactionRequest::default()
+        .with_to(alice.address())
+        .with_input(
+            contract
+                .emitWorld("conformance".to_string())
+                .calldata()
+                .to_owned(),
+        );
+    let receipt = provider
+        .send_transaction(tx)
+        .await
+        .map_err(Error::rpc)?
+        .get_receipt()
+        .await?;
+    steps.push(record_step(&provider, "emitWorld", &receipt, &mut tracked).await?);
+
+    // A fixed gas limit skips estimation, which would refuse to send a reverting call.
+    let tx = TransactionRequest::default()
+        .with_to(alice.address())
+        .with_value(U256::from(1_000_000_000u64))
+        .with_gas_limit(100_000);
+    let receipt = provider
+        .send_transaction(tx)
+        .await
+        .map_err(Error::rpc)?
+        .get_receipt()
+        .await?;
+    steps.push(record_step(&provider, "value-to-delegated", &receipt, &mut tracked).await?);
+
+    let nonce = provider
+        .get_transaction_count(alice.address())
+        .await
+        .map_err(Error::rpc)?;
+    let revoke = sign_revocation(&alice, chain_id, nonce)?;
+    let tx = TransactionRequest::default()
+        .with_to(alice.address())
+        .with_authorization_list(vec![revoke]);
+    let receipt = provider
+        .send_transaction(tx)
+        .await
+        .map_err(Error::rpc)?
+        .get_receipt()
+        .await?;
+    steps.push(record_step(&provider, "revoke", &receipt, &mut tracked).await?);
+
+    Ok(ScenarioReport {
+        client: client.to_string(),
+        steps,
+    })
+}
+
+async fn record_step<P: Provider>(
+    provider: &P,
+    label: &'static str,
+    receipt: &TransactionReceipt,
+    tracked: &mut [Tracked],
+) -> Result<StepReport> {
+    let fee = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price);
+    if let Some(payer) = tracked.iter_mut().find(|t| t.address == receipt.from) {
+        payer.fees_paid += fee;
+    }
+
+    let logs = receipt
+        .inner
+        .logs()
+        .iter()
+        .map(|log| LogEntry {
+            address: log.address(),
+            topics: log.topics().to_vec(),
+            data: log.data().data.clone(),
+        })
+        .collect();
+
+    let mut accounts = Vec::new();
+    for t in tracked.iter() {
+        let balance = provider.get_balance(t.address).await.map_err(Error::rpc)?;
+        accounts.push(AccountSnapshot {
+            label: t.label,
+            address: t.address,
+            code: provider.get_code_at(t.address).await.map_err(Error::rpc)?,
+            nonce: provider
+                .get_transaction_count(t.address)
+                .await
+                .map_err(Error::rpc)?,
+            balance_change: I256::from_raw(balance + t.fees_paid)
+                - I256::from_raw(t.initial_balance),
+        });
+    }
+
+    Ok(StepReport {
+        label,
+        status: receipt.status(),
+        gas_used: receipt.gas_used,
+        logs,
+        accounts,
+    })
+}
+
+/// Compares two scenario runs step by step.
+pub fn diff(left: &ScenarioReport, right: &ScenarioReport) -> Vec<Mismatch> {
+    let mut out = Vec::new();
+    let mut push = |step: &str, field: String, l: String, r: String| {
+        if l != r {
+            out.push(Mismatch {
+                step: step.to_string(),
+                field,
+                left: l,
+                right: r,
+            });
+        }
+    };
+
+    push(
+        "scenario",
+        "steps".into(),
+        left.steps.len().to_string(),
+        right.steps.len().to_string(),
+    );
+
+    for (l, r) in left.steps.iter().zip(&right.steps) {
+        let step = l.label;
+        push(
+            step,
+            "status".into(),
+            l.status.to_string(),
+            r.status.to_string(),
+        );
+        push(
+            step,
+            "gas_used".into(),
+            l.gas_used.to_string(),
+            r.gas_used.to_string(),
+        );
+        push(
+            step,
+            "logs.len".into(),
+            l.logs.len().to_string(),
+            r.logs.len().to_string(),
+        );
+        for (i, (ll, rl)) in l.logs.iter().zip(&r.logs).enumerate() {
+            push(
+                step,
+                format!("logs[{i}]"),
+                format!("{ll:?}"),
+                format!("{rl:?}"),
+            );
+        }
+        for (la, ra) in l.accounts.iter().zip(&r.accounts) {
+            let who = la.label;
+            push(
+                step,
+                format!("{who}.code"),
+                la.code.to_string(),
+                ra.code.to_string(),
+            );
+            push(
+                step,
+                format!("{who}.nonce"),
+                la.nonce.to_string(),
+                ra.nonce.to_string(),
+            );
+            push(
+                step,
+                format!("{who}.balance_change"),
+                la.balance_change.to_string(),
+                ra.balance_change.to_string(),
+            );
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(
+        client: &str,
+        status: bool,
+        gas_used: u64,
+        code: &[u8],
+        data: &[u8],
+    ) -> ScenarioReport {
+        ScenarioReport {
+            client: client.into(),
+            steps: vec![StepReport {
+                label: "delegate+emitHello",
+                status,
+                gas_used,
+                logs: vec![LogEntry {
+                    address: Address::repeat_byte(0x11),
+                    topics: vec![B256::repeat_byte(0x22)],
+                    data: Bytes::copy_from_slice(data),
+                }],
+                accounts: vec![AccountSnapshot {
+                    label: "alice",
+                    address: Address::repeat_byte(0x33),
+                    code: Bytes::copy_from_slice(code),
+                    nonce: 1,
+                    balance_change: I256::ZERO,
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn reports_each_differing_field() {
+        let anvil = report("anvil", true, 46_000, &[0xef, 0x01, 0x00], b"hello");
+        assert!(
+            diff(
+                &anvil,
+                &report("reth", true, 46_000, &[0xef, 0x01, 0x00], b"hello")
+            )
+            .is_empty()
+        );
+
+        let reth = report("reth", false, 47_000, &[], b"world");
+        let fields: Vec<_> = diff(&anvil, &reth)
+            .into_iter()
+            .map(|mismatch| {
+                assert_eq!(mismatch.step, "delegate+emitHello");
+                mismatch.field
+            })
+            .collect();
+        assert_eq!(fields, ["status", "gas_used", "logs[0]", "alice.code"]);
+
+        let mut short = anvil.clone();
+        short.steps.clear();
+        let mismatches = diff(&anvil, &short);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].to_string(), "[scenario] steps: 1 != 0");
+    }
+}
@@ -0,0 +1,228 @@
+//! Bindings for the contracts under `forge-contracts`.
+//!
+//! Contracts with a committed artifact get their bytecode at compile time. Refresh the
+//! artifacts with `make artifacts` after changing a contract.
+//!
+//! The other contracts have hand-written interfaces, here and in the modules that use them.
+//! [`interfaces`] lists them so that `check_artifacts` can compare them with a fresh Forge build
+//! until their artifacts are committed too.
+
+use alloy::{json_abi::JsonAbi, sol};
+
+// Codegen from artifact.
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    EventLogger,
+    "artifacts/EventLogger.json"
+);
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    SponsoredTransfer,
+    "artifacts/SponsoredTransfer.json"
+);
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    BatchCallAndSponsor,
+    "artifacts/BatchCallAndSponsor.json"
+);
+
+// `SimpleTransfer` has no committed artifact yet, deploy it with `artifacts::deploy`.
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc, abi)]
+    interface SimpleTransfer {
+        event Transfer(address indexed from, address indexed to, uint256 amount);
+
+        function transfer(address from_alice, address to_bob, uint256 amount) external payable;
+
+        function transferWithSig(
+            address from_alice,
+            address to_bob,
+            uint256 amount,
+            uint256 nonce,
+            bytes calldata signature
+        ) external payable;
+    }
+);
+
+// Hardened variants of the contracts above, without committed artifacts either.
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc, abi)]
+    interface SimpleTransferHardened {
+        event Transfer(address indexed from, address indexed to, uint256 amount);
+
+        function nonceUsed(address signer, uint256 nonce) external view returns (bool);
+
+        function transfer(address from_alice, address to_bob, uint256 amount) external payable;
+
+        function transferWithSig(
+            address from_alice,
+            address to_bob,
+            uint256 amount,
+            uint256 nonce,
+            bytes calldata signature
+        ) external payable;
+    }
+);
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc, abi)]
+    interface SponsoredTransferHardened {
+        event Transfer(address indexed from, address indexed to, uint256 amount);
+
+        function nonce() external view returns (uint256);
+
+        function transfer(address to, uint256 amount, bytes calldata signature) external;
+    }
+);
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc, abi)]
+    interface BatchCallAndSponsorHardened {
+        struct Call {
+            address to;
+            uint256 value;
+            bytes data;
+        }
+
+        event CallExecuted(address indexed sender, address indexed to, uint256 value, bytes data);
+        event BatchExecuted(uint256 indexed nonce, Call[] calls);
+
+        function nonce() external view returns (uint256);
+
+        // Same order as in `BatchCallAndSponsor`, so `execute_1` is the signed overload in both.
+        function execute(Call[] calldata calls) external payable;
+
+        function execute(Call[] calldata calls, bytes calldata signature) external payable;
+    }
+);
+
+impl From<BatchCallAndSponsor::Call> for BatchCallAndSponsorHardened::Call {
+    fn from(call: BatchCallAndSponsor::Call) -> Self {
+        Self {
+            to: call.to,
+            value: call.value,
+            data: call.data,
+        }
+    }
+}
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc, abi)]
+    interface DomainBatchExecutor {
+        struct Call {
+            address to;
+            uint256 value;
+            bytes data;
+        }
+
+        event CallExecuted(address indexed sender, address indexed to, uint256 value, bytes data);
+        event BatchExecuted(uint256 indexed nonce, Call[] calls);
+
+        function nonce() external view returns (uint256);
+
+        function hashCalls(Call[] calldata calls) external pure returns (bytes32);
+
+        function execute(Call[] calldata calls) external payable;
+
+        function execute(Call[] calldata calls, uint256 validUntil, bytes calldata signature)
+            external
+            payable;
+    }
+);
+
+impl From<BatchCallAndSponsor::Call> for DomainBatchExecutor::Call {
+    fn from(call: BatchCallAndSponsor::Call) -> Self {
+        Self {
+            to: call.to,
+            value: call.value,
+            data: call.data,
+        }
+    }
+}
+
+/// Hand-written interfaces, keyed by the name of the contract they bind.
+pub fn interfaces() -> Vec<(&'static str, JsonAbi)> {
+    use crate::{
+        batch_report, erc7821, passkey, recovery, session_keys, threshold, typed_batch,
+        user_operation,
+    };
+    vec![
+        ("SimpleTransfer", SimpleTransfer::abi::contract()),
+        (
+            "SimpleTransferHardened",
+            SimpleTransferHardened::abi::contract(),
+        ),
+        (
+            "SponsoredTransferHardened",
+            SponsoredTransferHardened::abi::contract(),
+        ),
+        (
+            "BatchCallAndSponsorHardened",
+            BatchCallAndSponsorHardened::abi::contract(),
+        ),
+        ("DomainBatchExecutor", DomainBatchExecutor::abi::contract()),
+        (
+            "TypedBatchExecutor",
+            typed_batch::TypedBatchExecutor::abi::contract(),
+        ),
+        (
+            "ReportingBatchExecutor",
+            batch_report::ReportingBatchExecutor::abi::contract(),
+        ),
+        ("ERC7821Executor", erc7821::ERC7821::abi::contract()),
+        (
+            "MinimalEntryPoint",
+            user_operation::EntryPoint::abi::contract(),
+        ),
+        (
+            "Simple7702Account",
+            user_operation::Simple7702Account::abi::contract(),
+        ),
+        (
+            "SessionKeyDelegate",
+            session_keys::SessionKeyDelegate::abi::contract(),
+        ),
+        (
+            "ThresholdDelegate",
+            threshold::ThresholdDelegate::abi::contract(),
+        ),
+        (
+            "RecoveryDelegate",
+            recovery::RecoveryDelegate::abi::contract(),
+        ),
+        ("P256Delegate", passkey::P256Delegate::abi::contract()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifacts::{SOURCES, source};
+
+    #[test]
+    fn every_source_is_committed_or_has_an_interface() {
+        let interfaces = interfaces();
+        for (name, _) in &interfaces {
+            assert!(source(name).is_some(), "{name} is not in SOURCES");
+        }
+        for source in SOURCES {
+            let committed = source.committed_path().exists();
+            let bound = interfaces.iter().any(|(name, _)| *name == source.contract);
+            assert!(
+                committed != bound,
+                "{} needs either a committed artifact or a hand-written interface, not both",
+                source.contract
+            );
+        }
+    }
+}
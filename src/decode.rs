@@ -0,0 +1,154 @@
+//! Decoding calldata and revert data with the ABIs of every known contract.
+
+use std::collections::HashMap;
+
+use alloy::{
+    dyn_abi::{DynSolValue, JsonAbiExt},
+    json_abi::{Error, Function, JsonAbi},
+    primitives::{Bytes, Selector},
+    sol_types::{self, Panic, SolError},
+};
+
+use crate::{artifacts, error::Result, revert::Revert};
+
+#[derive(Debug, Clone, Default)]
+pub struct AbiRegistry {
+    functions: HashMap<Selector, Function>,
+    errors: HashMap<Selector, Error>,
+}
+
+impl AbiRegistry {
+    /// Registry of all local artifacts, see [`artifacts::all`].
+    pub fn from_artifacts() -> Result<Self> {
+        let mut registry = Self::default();
+        for (_, artifact) in artifacts::all()? {
+            registry.add(&artifact.abi);
+        }
+        Ok(registry)
+    }
+
+    /// Adds the functions and errors of `abi`. Selectors already known are kept.
+    pub fn add(&mut self, abi: &JsonAbi) {
+        for function in abi.functions() {
+            self.functions
+                .entry(function.selector())
+                .or_insert_with(|| function.clone());
+        }
+        for error in abi.errors() {
+            self.errors
+                .entry(error.selector())
+                .or_insert_with(|| error.clone());
+        }
+    }
+
+    pub fn function(&self, selector: Selector) -> Option<&Function> {
+        self.functions.get(&selector)
+    }
+
+    pub fn error(&self, selector: Selector) -> Option<&Error> {
+        self.errors.get(&selector)
+    }
+
+    /// Formats calldata as `name(arg, ...)`, or `None` for unknown selectors.
+    pub fn decode_call(&self, input: &[u8]) -> Option<String> {
+        let selector = Selector::try_from(input.get(..4)?).ok()?;
+        let function = self.function(selector)?;
+        let args = function.abi_decode_input(&input[4..]).ok()?;
+        Some(format!("{}({})", function.name, format_values(&args)))
+    }
+
+    /// Decodes revert data as a custom error from the registry, `Error(string)` or
+    /// `Panic(uint256)`.
+    pub fn decode_revert(&self, data: &[u8]) -> Revert {
+        let Some(selector) = data.get(..4).and_then(|s| Selector::try_from(s).ok()) else {
+            return Revert::Unknown(Bytes::copy_from_slice(data));
+        };
+        if let Some(error) = self.error(selector)
+            && let Ok(args) = error.abi_decode_input(&data[4..])
+        {
+            return Revert::Custom {
+                name: error.name.clone(),
+                args: format_values(&args),
+            };
+        }
+        if let Ok(revert) = sol_types::Revert::abi_decode(data) {
+            return Revert::Reason(revert.reason);
+        }
+        if let Ok(panic) = Panic::abi_decode(data) {
+            return Revert::Panic(panic.code);
+        }
+        Revert::Unknown(Bytes::copy_from_slice(data))
+    }
+}
+
+pub fn format_values(values: &[DynSolValue]) -> String {
+    values
+        .iter()
+        .map(format_value)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn format_value(value: &DynSolValue) -> String {
+    match value {
+        DynSolValue::Bool(b) => b.to_string(),
+        DynSolValue::Int(i, _) => i.to_string(),
+        DynSolValue::Uint(u, _) => u.to_string(),
+        DynSolValue::FixedBytes(word, size) => format!("0x{}", hex::encode(&word[..*size])),
+        DynSolValue::Address(address) => address.to_string(),
+        DynSolValue::Function(function) => function.to_string(),
+        DynSolValue::Bytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        DynSolValue::String(s) => format!("{s:?}"),
+        DynSolValue::Array(items) | DynSolValue::FixedArray(items) => {
+            format!("[{}]", format_values(items))
+        }
+        DynSolValue::Tuple(items) => format!("({})", format_values(items)),
+        DynSolValue::CustomStruct { name, tuple, .. } => {
+            format!("{name}({})", format_values(tuple))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::{BatchCallAndSponsor, EventLogger};
+    use alloy::{
+        primitives::U256,
+        sol_types::{SolCall, SolError},
+    };
+
+    #[test]
+    fn decodes_calls_and_custom_errors() {
+        let registry = AbiRegistry::from_artifacts().unwrap();
+
+        let call = EventLogger::emitWorldCall {
+            world_name: "world".into(),
+        }
+        .abi_encode();
+        assert_eq!(
+            registry.decode_call(&call).as_deref(),
+            Some(r#"emitWorld("world")"#)
+        );
+
+        let revert = BatchCallAndSponsor::ECDSAInvalidSignatureLength {
+            length: U256::from(64),
+        }
+        .abi_encode();
+        assert_eq!(
+            registry.decode_revert(&revert).to_string(),
+            "ECDSAInvalidSignatureLength(64)"
+        );
+
+        let revert = sol_types::Revert::from("Invalid signature").abi_encode();
+        assert_eq!(
+            registry.decode_revert(&revert),
+            Revert::Reason("Invalid signature".into())
+        );
+        let panic = Panic::from(U256::from(0x11)).abi_encode();
+        assert_eq!(
+            registry.decode_revert(&panic).to_string(),
+            "Panic(0x11: arithmetic underflow or overflow)"
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! Dynamic "can a stranger drain this account" probing.
+//!
+//! Every function of the delegate's ABI is called on the delegated account from an unrelated,
+//! funded address, with arguments pointing funds at that address. Each call runs inside an
+//! Anvil snapshot that is reverted afterwards, so run this against a local node or fork only.
+
+use alloy::{
+    dyn_abi::{DynSolType, DynSolValue, JsonAbiExt, Specifier},
+    json_abi::{Function, JsonAbi, StateMutability},
+    network::TransactionBuilder,
+    primitives::{Address, Bytes, I256, U256},
+    providers::{Provider, ext::AnvilApi},
+    rpc::types::TransactionRequest,
+    sol,
+};
+
+use crate::error::{Error, Result};
+
+sol!(
+    #[sol(rpc)]
+    interface IERC20 {
+        function balanceOf(address owner) external view returns (uint256);
+    }
+);
+
+/// Upper bound on argument combinations tried per function.
+const MAX_CANDIDATES: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct Probe {
+    pub function: String,
+    pub calldata: Bytes,
+    pub success: bool,
+    /// Change of the account's ETH balance.
+    pub eth_delta: I256,
+    /// Change of the account's balance of each probed token.
+    pub token_deltas: Vec<(Address, I256)>,
+}
+
+impl Probe {
+    pub fn drains(&self) -> bool {
+        self.eth_delta.is_negative() || self.token_deltas.iter().any(|(_, d)| d.is_negative())
+    }
+}
+
+/// Argument values worth trying for a parameter: the attacker as recipient, the account as
+/// source, and `amount` for every integer.
+fn candidate_values(
+    ty: &DynSolType,
+    account: Address,
+    attacker: Address,
+    amount: U256,
+) -> Vec<DynSolValue> {
+    let first = |ty: &DynSolType| candidate_values(ty, account, attacker, amount).swap_remove(0);
+    match ty {
+        DynSolType::Address => vec![
+            DynSolValue::Address(attacker),
+            DynSolValue::Address(account),
+        ],
+        DynSolType::Uint(bits) => {
+            let max = if *bits == 256 {
+                U256::MAX
+            } else {
+                (U256::from(1) << bits) - U256::from(1)
+            };
+            vec![DynSolValue::Uint(amount.min(max), *bits)]
+        }
+        DynSolType::Int(bits) => vec![DynSolValue::Int(I256::ZERO, *bits)],
+        DynSolType::Bool => vec![DynSolValue::Bool(true)],
+        DynSolType::Bytes => vec![DynSolValue::Bytes(Vec::new())],
+        DynSolType::String => vec![DynSolValue::String(String::new())],
+        DynSolType::FixedBytes(size) => vec![DynSolValue::FixedBytes(Default::default(), *size)],
+        DynSolType::Array(inner) => vec![DynSolValue::Array(vec![first(inner)])],
+        DynSolType::FixedArray(inner, len) => {
+            vec![DynSolValue::FixedArray(vec![first(inner); *len])]
+        }
+        DynSolType::Tuple(types) | DynSolType::CustomStruct { tuple: types, .. } => {
+            vec![DynSolValue::Tuple(types.iter().map(first).collect())]
+        }
+        DynSolType::Function => vec![DynSolValue::Function(Default::default())],
+    }
+}
+
+/// Calldata for the combinations of candidate arguments of `function`.
+pub fn candidate_calls(
+    function: &Function,
+    account: Address,
+    attacker: Address,
+    amount: U256,
+) -> Result<Vec<Bytes>> {
+    let mut combinations: Vec<Vec<DynSolValue>> = vec![Vec::new()];
+    for param in &function.inputs {
+        let values = candidate_values(&param.resolve()?, account, attacker, amount);
+        combinations = combinations
+            .iter()
+            .flat_map(|prefix| {
+                values.iter().map(move |value| {
+                    let mut args = prefix.clone();
+                    args.push(value.clone());
+                    args
+                })
+            })
+            .take(MAX_CANDIDATES)
+            .collect();
+    }
+
+    combinations
+        .iter()
+        .map(|args| Ok(function.abi_encode_input(args)?.into()))
+        .collect()
+}
+
+async fn balances<P: Provider>(
+    provider: &P,
+    account: Address,
+    tokens: &[Address],
+) -> Result<(U256, Vec<U256>)> {
+    let eth = provider.get_balance(account).await.map_err(Error::rpc)?;
+    let mut token_balances = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        token_balances.push(
+            IERC20::new(*token, provider)
+                .balanceOf(account)
+                .call()
+                .await?,
+        );
+    }
+    Ok((eth, token_balances))
+}
+
+fn delta(before: U256, after: U256) -> I256 {
+    I256::from_raw(after.wrapping_sub(before))
+}
+
+/// Calls every non-view function of `abi` on `account` from `attacker` and reports the balance
+/// changes of each call.
+///
+/// `attacker` is impersonated and funded, so `provider` must talk to Anvil and must not carry a
+/// wallet of its own. The node is put back as it was afterwards, also when probing fails.
+pub async fn probe<P: Provider>(
+    provider: &P,
+    account: Address,
+    abi: &JsonAbi,
+    attacker: Address,
+    tokens: &[Address],
+) -> Result<Vec<Probe>> {
+    let snapshot = provider.anvil_snapshot().await.map_err(Error::rpc)?;
+    let probes = probe_functions(provider, account, abi, attacker, tokens).await;
+    let stopped = provider.anvil_stop_impersonating_account(attacker).await;
+    provider.anvil_revert(snapshot).await.map_err(Error::rpc)?;
+    stopped.map_err(Error::rpc)?;
+    probes
+}
+
+async fn probe_functions<P: Provider>(
+    provider: &P,
+    account: Address,
+    abi: &JsonAbi,
+    attacker: Address,
+    tokens: &[Address],
+) -> Result<Vec<Probe>> {
+    provider
+        .anvil_set_balance(attacker, U256::from(10).pow(U256::from(20)))
+        .await
+        .map_err(Error::rpc)?;
+    provider
+        .anvil_impersonate_account(attacker)
+        .await
+        .map_err(Error::rpc)?;
+
+    let balance = provider.get_balance(account).await.map_err(Error::rpc)?;
+    let amount = (balance / U256::from(2)).max(U256::from(1));
+
+    let mut probes = Vec::new();
+    for function in abi.functions() {
+        if matches!(
+            function.state_mutability,
+            StateMutability::View | StateMutability::Pure
+        ) {
+            continue;
+        }
+        for calldata in candidate_calls(function, account, attacker, amount)? {
+            let snapshot = provider.anvil_snapshot().await.map_err(Error::rpc)?;
+            let probe = probe_call(provider, account, attacker, tokens, function, calldata).await;
+            provider.anvil_revert(snapshot).await.map_err(Error::rpc)?;
+            probes.push(probe?);
+        }
+    }
+    Ok(probes)
+}
+
+async fn probe_call<P: Provider>(
+    provider: &P,
+    account: Address,
+    attacker: Address,
+    tokens: &[Address],
+    function: &Function,
+    calldata: Bytes,
+) -> Result<Probe> {
+    let (eth_before, tokens_before) = balances(provider, account, tokens).await?;
+
+    // A fixed gas limit so that reverting calls are mined instead of failing estimation.
+    let tx = TransactionRequest::default()
+        .with_from(attacker)
+        .with_to(account)
+        .with_input(calldata.clone())
+        .with_gas_limit(1_000_000);
+    let receipt = provider
+        .send_transaction(tx)
+        .await
+        .map_err(Error::rpc)?
+        .get_receipt()
+        .await?;
+
+    let (eth_after, tokens_after) = balances(provider, account, tokens).await?;
+    Ok(Probe {
+        function: function.signature(),
+        calldata,
+        success: receipt.status(),
+        eth_delta: delta(eth_before, eth_after),
+        token_deltas: tokens
+            .iter()
+            .zip(tokens_before.into_iter().zip(tokens_after))
+            .map(|(token, (before, after))| (*token, delta(before, after)))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::SponsoredTransfer;
+    use alloy::{primitives::address, sol_types::SolCall};
+
+    #[test]
+    fn points_transfers_at_the_attacker() {
+        let abi = crate::artifacts::Artifact::load("SponsoredTransfer")
+            .unwrap()
+            .abi;
+        let account = address!("0x00000000000000000000000000000000000a11ce");
+        let attacker = address!("0x000000000000000000000000000000000000bad0");
+        let amount = U256::from(5);
+
+        let calls = candidate_calls(
+            &abi.function("transfer").unwrap()[0],
+            account,
+            attacker,
+            amount,
+        )
+        .unwrap();
+        assert_eq!(calls.len(), 4);
+        let drain = SponsoredTransfer::transferCall {
+            from_alice: account,
+            to_bob: attacker,
+            amount,
+        }
+        .abi_encode();
+        assert!(calls.iter().any(|c| c[..] == drain[..]));
+    }
+}
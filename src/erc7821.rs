@@ -0,0 +1,257 @@
+//! ERC-7821 minimal batch executor: `execute(bytes32 mode, bytes executionData)`.
+//!
+//! The mode is `callType ‖ execType ‖ 4 unused bytes ‖ modeSelector ‖ 22 bytes of payload`.
+//! This crate speaks the batch call type, with or without `opData`, in default or try
+//! execution. `ERC7821Executor` (run `make contracts`) is the bundled reference executor; its
+//! `opData` is the account's signature over [`digest`].
+
+use alloy::{
+    primitives::{Address, B256, Bytes, U256, keccak256},
+    signers::local::PrivateKeySigner,
+    sol,
+    sol_types::{SolCall, SolValue},
+};
+
+use crate::{
+    batch,
+    error::{Error, Result},
+};
+
+pub use crate::contracts::BatchCallAndSponsor::Call;
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc, abi)]
+    interface ERC7821 {
+        event TryExecuteFailed(uint256 indexed index, bytes returnData);
+        event BatchExecuted(bytes32 indexed mode, uint256 failed);
+
+        error UnsupportedExecutionMode();
+
+        function nonce() external view returns (uint256);
+
+        function execute(bytes32 mode, bytes calldata executionData) external payable;
+
+        function supportsExecutionMode(bytes32 mode) external view returns (bool);
+    }
+);
+
+const CALL_TYPE_BATCH: u8 = 0x01;
+const EXEC_TYPE_DEFAULT: u8 = 0x00;
+const EXEC_TYPE_TRY: u8 = 0x01;
+const OP_DATA_SELECTOR: [u8; 4] = [0x78, 0x21, 0x00, 0x01];
+
+/// A batch execution mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Mode {
+    /// Carry on past failing calls instead of reverting the batch.
+    pub try_mode: bool,
+    /// `executionData` is `abi.encode(calls, opData)` rather than `abi.encode(calls)`.
+    pub op_data: bool,
+}
+
+impl Mode {
+    pub const BATCH: Self = Self {
+        try_mode: false,
+        op_data: false,
+    };
+    pub const BATCH_WITH_OP_DATA: Self = Self {
+        try_mode: false,
+        op_data: true,
+    };
+    pub const TRY_BATCH: Self = Self {
+        try_mode: true,
+        op_data: false,
+    };
+    pub const TRY_BATCH_WITH_OP_DATA: Self = Self {
+        try_mode: true,
+        op_data: true,
+    };
+
+    pub fn to_b256(self) -> B256 {
+        let mut mode = B256::ZERO;
+        mode[0] = CALL_TYPE_BATCH;
+        mode[1] = if self.try_mode {
+            EXEC_TYPE_TRY
+        } else {
+            EXEC_TYPE_DEFAULT
+        };
+        if self.op_data {
+            mode[6..10].copy_from_slice(&OP_DATA_SELECTOR);
+        }
+        mode
+    }
+
+    /// Parses a mode, rejecting call types, execution types and selectors this crate does not
+    /// speak, e.g. batches of batches.
+    pub fn from_b256(mode: B256) -> Result<Self> {
+        let unsupported = || Error::Abi(format!("unsupported ERC-7821 execution mode {mode}"));
+        if mode[0] != CALL_TYPE_BATCH || mode[2..6] != [0; 4] || mode[10..] != [0; 22] {
+            return Err(unsupported());
+        }
+        let try_mode = match mode[1] {
+            EXEC_TYPE_DEFAULT => false,
+            EXEC_TYPE_TRY => true,
+            _ => return Err(unsupported()),
+        };
+        let op_data = if mode[6..10] == [0; 4] {
+            false
+        } else if mode[6..10] == OP_DATA_SELECTOR {
+            true
+        } else {
+            return Err(unsupported());
+        };
+        Ok(Self { try_mode, op_data })
+    }
+}
+
+/// The arguments of one `execute(mode, executionData)` call.
+#[derive(Clone)]
+pub struct Execution {
+    pub try_mode: bool,
+    pub calls: Vec<Call>,
+    /// Selects the opData variant of the mode when set.
+    pub op_data: Option<Bytes>,
+}
+
+impl Execution {
+    pub fn mode(&self) -> Mode {
+        Mode {
+            try_mode: self.try_mode,
+            op_data: self.op_data.is_some(),
+        }
+    }
+
+    pub fn execution_data(&self) -> Bytes {
+        match &self.op_data {
+            Some(op_data) => (self.calls.clone(), op_data.clone()).abi_encode_params(),
+            None => self.calls.abi_encode(),
+        }
+        .into()
+    }
+
+    pub fn call(&self) -> ERC7821::executeCall {
+        ERC7821::executeCall {
+            mode: self.mode().to_b256(),
+            executionData: self.execution_data(),
+        }
+    }
+
+    pub fn decode(mode: B256, execution_data: &[u8]) -> Result<Self> {
+        let mode = Mode::from_b256(mode)?;
+        let (calls, op_data) = if mode.op_data {
+            let (calls, op_data) = <(Vec<Call>, Bytes)>::abi_decode_params(execution_data)?;
+            (calls, Some(op_data))
+        } else {
+            (Vec::<Call>::abi_decode(execution_data)?, None)
+        };
+        Ok(Self {
+            try_mode: mode.try_mode,
+            calls,
+            op_data,
+        })
+    }
+
+    /// Decodes the calldata of an `execute(mode, executionData)` transaction.
+    pub fn decode_calldata(input: &[u8]) -> Result<Self> {
+        let call = ERC7821::executeCall::abi_decode(input)?;
+        Self::decode(call.mode, &call.executionData)
+    }
+}
+
+/// Digest the account signs as `opData` for `ERC7821Executor`:
+/// `keccak256(abi.encode(chainid, account, nonce, mode, calls))`.
+pub fn digest(chain_id: u64, account: Address, nonce: U256, mode: Mode, calls: &[Call]) -> B256 {
+    keccak256(
+        (
+            U256::from(chain_id),
+            account,
+            nonce,
+            mode.to_b256(),
+            calls.to_vec(),
+        )
+            .abi_encode_params(),
+    )
+}
+
+/// Signs `calls` for `ERC7821Executor` and returns the execution, ready for any sponsor.
+pub fn sign(
+    signer: &PrivateKeySigner,
+    chain_id: u64,
+    nonce: U256,
+    try_mode: bool,
+    calls: Vec<Call>,
+) -> Result<Execution> {
+    let mode = Mode {
+        try_mode,
+        op_data: true,
+    };
+    let digest = digest(chain_id, signer.address(), nonce, mode, &calls);
+    Ok(Execution {
+        try_mode,
+        calls,
+        op_data: Some(batch::sign(signer, digest)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, b256, bytes};
+
+    #[test]
+    fn encodes_and_decodes_modes() {
+        assert_eq!(
+            Mode::BATCH.to_b256(),
+            b256!("0x0100000000000000000000000000000000000000000000000000000000000000")
+        );
+        assert_eq!(
+            Mode::TRY_BATCH_WITH_OP_DATA.to_b256(),
+            b256!("0x0101000000007821000100000000000000000000000000000000000000000000")
+        );
+        for mode in [
+            Mode::BATCH,
+            Mode::BATCH_WITH_OP_DATA,
+            Mode::TRY_BATCH,
+            Mode::TRY_BATCH_WITH_OP_DATA,
+        ] {
+            assert_eq!(Mode::from_b256(mode.to_b256()).unwrap(), mode);
+        }
+        // Batch of batches and the single call type are not spoken here.
+        let batch_of_batches =
+            b256!("0x0100000000007821000200000000000000000000000000000000000000000000");
+        assert!(Mode::from_b256(batch_of_batches).is_err());
+        assert!(Mode::from_b256(B256::ZERO).is_err());
+
+        let execution = Execution {
+            try_mode: true,
+            calls: vec![Call {
+                to: address!("0x00000000000000000000000000000000000000d1"),
+                value: U256::from(7),
+                data: bytes!("0xdeadbeef"),
+            }],
+            op_data: Some(bytes!("0x01")),
+        };
+        let decoded = Execution::decode_calldata(&execution.call().abi_encode()).unwrap();
+        assert_eq!(decoded.mode(), Mode::TRY_BATCH_WITH_OP_DATA);
+        assert_eq!(decoded.calls[0].data, execution.calls[0].data);
+        assert_eq!(decoded.op_data, execution.op_data);
+
+        let plain = Execution {
+            op_data: None,
+            ..execution
+        };
+        // Without opData the execution data is just the encoded array.
+        assert_eq!(
+            plain.execution_data(),
+            Bytes::from(plain.calls.abi_encode())
+        );
+        assert_eq!(
+            Execution::decode(Mode::TRY_BATCH.to_b256(), &plain.execution_data())
+                .unwrap()
+                .calls[0]
+                .value,
+            U256::from(7)
+        );
+    }
+}
@@ -0,0 +1,320 @@
+//! Error type of the library.
+//!
+//! Failures are split by what a caller can do about them: node rejections carry a
+//! [`Rejection`] kind (a too-low nonce can be refreshed, an unsupported transaction type
+//! cannot), reverts carry the decoded reason, and transport failures and timeouts are
+//! retryable as is. Binaries keep using `anyhow`, which accepts this type through `?`.
+
+use std::{fmt, path::PathBuf};
+
+use alloy::{
+    providers::{PendingTransactionError, WatchTxError},
+    rpc::types::trace::geth::UnexpectedTracerError,
+    transports::{RpcError, TransportErrorKind},
+};
+
+use crate::{decode::AbiRegistry, revert::RevertError};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Signing a hash, authorization or transaction, or loading a key, failed.
+    #[error("signing failed: {0}")]
+    Signing(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// The node could not be reached or the connection failed.
+    #[error("RPC transport error: {0}")]
+    Transport(#[source] RpcError<TransportErrorKind>),
+    /// The request could not be serialized, the response could not be deserialized or was
+    /// null, or the client does not support the request. Sending it again will not help.
+    #[error("RPC error: {0}")]
+    Rpc(#[source] RpcError<TransportErrorKind>),
+    /// The node refused the request.
+    #[error("node rejected the request ({kind}): {message}")]
+    Rejected {
+        kind: Rejection,
+        code: i64,
+        message: String,
+    },
+    /// A call, gas estimate or transaction reverted.
+    #[error(transparent)]
+    Revert(#[from] RevertError),
+    /// An authorization that must not be signed or submitted, e.g. for an unapproved delegate.
+    #[error("invalid authorization: {0}")]
+    InvalidAuthorization(String),
+    /// An off-chain signature that the contract checking it would reject.
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+    /// A call a session key or other scoped signer is not allowed to make.
+    #[error("not permitted: {0}")]
+    NotPermitted(String),
+    /// A signature used after its `validUntil` deadline.
+    #[error("signature expired at {valid_until}, now {now}")]
+    Expired { valid_until: u64, now: u64 },
+    #[error("timed out waiting for {0}")]
+    Timeout(String),
+    #[error("ABI encoding or decoding failed: {0}")]
+    Abi(String),
+    /// The node answered, but not with what the request implies (missing receipt fields,
+    /// unknown transactions, mismatched result counts).
+    #[error("unexpected response: {0}")]
+    UnexpectedResponse(String),
+    #[error("failed to read {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid {}: {source}", path.display())]
+    Json {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    /// Missing artifacts, unknown contracts and bad settings.
+    #[error("{0}")]
+    Config(String),
+    /// Accepting or serving a connection of a local server failed.
+    #[error("server error: {0}")]
+    Server(#[source] std::io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    NonceTooLow,
+    NonceTooHigh,
+    /// Typically a type-4 transaction sent to a node without Prague.
+    UnsupportedTxType,
+    InsufficientFunds,
+    /// Fees below the base fee or too low to replace a pending transaction.
+    Underpriced,
+    AlreadyKnown,
+    GasLimit,
+    /// The RPC method is not available on this node.
+    MethodNotFound,
+    Other,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::NonceTooLow => "nonce too low",
+            Self::NonceTooHigh => "nonce too high",
+            Self::UnsupportedTxType => "unsupported transaction type",
+            Self::InsufficientFunds => "insufficient funds",
+            Self::Underpriced => "underpriced",
+            Self::AlreadyKnown => "already known",
+            Self::GasLimit => "gas limit",
+            Self::MethodNotFound => "method not found",
+            Self::Other => "other",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Rejection {
+    /// Classifies a JSON-RPC error by code and by the messages geth, reth and Anvil use.
+    pub fn classify(code: i64, message: &str) -> Self {
+        let message = message.to_lowercase();
+        let has = |needle: &str| message.contains(needle);
+        // geth says "the method X does not exist/is not available"; other things do not exist
+        // too, e.g. accounts and block headers.
+        if code == -32601
+            || has("method not found")
+            || (has("the method ") && has("does not exist"))
+        {
+            Self::MethodNotFound
+        } else if has("nonce too low") || has("nonce is too low") {
+            Self::NonceTooLow
+        } else if has("nonce too high") {
+            Self::NonceTooHigh
+        } else if has("transaction type not supported")
+            || has("unsupported transaction type")
+            || has("tx type not supported")
+            || has("eip-7702 not activated")
+        {
+            Self::UnsupportedTxType
+        } else if has("insufficient funds") {
+            Self::InsufficientFunds
+        } else if has("underpriced") || has("fee cap less than block base fee") {
+            Self::Underpriced
+        } else if has("already known") || has("already imported") {
+            Self::AlreadyKnown
+        } else if has("gas too low") || has("exceeds block gas limit") || has("out of gas") {
+            Self::GasLimit
+        } else {
+            Self::Other
+        }
+    }
+}
+
+impl Error {
+    pub fn rejection(&self) -> Option<Rejection> {
+        match self {
+            Self::Rejected { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+
+    /// Whether the same request may succeed later, as is or with a refreshed nonce or fees.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Transport(_) | Self::Timeout(_) => true,
+            Self::Rejected { kind, .. } => matches!(
+                kind,
+                Rejection::NonceTooLow | Rejection::Underpriced | Rejection::AlreadyKnown
+            ),
+            _ => false,
+        }
+    }
+
+    /// Converts the error of a request that runs no contract code, such as a state read, a
+    /// receipt or trace lookup or an Anvil cheat code. Requests that can revert go through
+    /// [`DecodeRevert`](crate::revert::DecodeRevert) instead, with the ABIs of the contracts
+    /// they reach.
+    pub fn rpc(err: RpcError<TransportErrorKind>) -> Self {
+        Self::from_rpc(err, &AbiRegistry::default(), "request")
+    }
+
+    /// Converts an RPC error, decoding revert data with `abis`.
+    pub fn from_rpc(err: RpcError<TransportErrorKind>, abis: &AbiRegistry, context: &str) -> Self {
+        match err.as_error_resp() {
+            Some(resp) => match resp.as_revert_data() {
+                Some(data) => RevertError::new(abis, context, data).into(),
+                None => Self::Rejected {
+                    kind: Rejection::classify(resp.code, &resp.message),
+                    code: resp.code,
+                    message: resp.message.to_string(),
+                },
+            },
+            None if matches!(err, RpcError::Transport(_)) => Self::Transport(err),
+            None => Self::Rpc(err),
+        }
+    }
+}
+
+impl From<PendingTransactionError> for Error {
+    fn from(err: PendingTransactionError) -> Self {
+        match err {
+            PendingTransactionError::TransportError(err) => Self::rpc(err),
+            PendingTransactionError::TxWatcher(WatchTxError::Timeout) => {
+                Self::Timeout("transaction confirmation".into())
+            }
+            err => Self::UnexpectedResponse(err.to_string()),
+        }
+    }
+}
+
+impl From<alloy::contract::Error> for Error {
+    fn from(err: alloy::contract::Error) -> Self {
+        match err {
+            alloy::contract::Error::TransportError(err) => Self::rpc(err),
+            alloy::contract::Error::PendingTransactionError(err) => err.into(),
+            err => Self::Abi(err.to_string()),
+        }
+    }
+}
+
+impl From<alloy::signers::Error> for Error {
+    fn from(err: alloy::signers::Error) -> Self {
+        Self::Signing(Box::new(err))
+    }
+}
+
+impl From<alloy::signers::local::LocalSignerError> for Error {
+    fn from(err: alloy::signers::local::LocalSignerError) -> Self {
+        Self::Signing(Box::new(err))
+    }
+}
+
+impl From<alloy::dyn_abi::Error> for Error {
+    fn from(err: alloy::dyn_abi::Error) -> Self {
+        Self::Abi(err.to_string())
+    }
+}
+
+impl From<alloy::sol_types::Error> for Error {
+    fn from(err: alloy::sol_types::Error) -> Self {
+        Self::Abi(err.to_string())
+    }
+}
+
+impl From<UnexpectedTracerError> for Error {
+    fn from(err: UnexpectedTracerError) -> Self {
+        Self::UnexpectedResponse(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_node_rejections() {
+        let cases = [
+            (
+                -32000,
+                "nonce too low: next nonce 5, tx nonce 4",
+                Rejection::NonceTooLow,
+            ),
+            (-32003, "Nonce too low", Rejection::NonceTooLow),
+            (
+                -32602,
+                "transaction type not supported",
+                Rejection::UnsupportedTxType,
+            ),
+            (
+                -32000,
+                "insufficient funds for gas * price + value",
+                Rejection::InsufficientFunds,
+            ),
+            (
+                -32000,
+                "replacement transaction underpriced",
+                Rejection::Underpriced,
+            ),
+            (
+                -32601,
+                "the method eth_simulateV1 does not exist/is not available",
+                Rejection::MethodNotFound,
+            ),
+            (
+                -32000,
+                "the method eth_simulateV1 does not exist/is not available",
+                Rejection::MethodNotFound,
+            ),
+            (-32000, "account does not exist", Rejection::Other),
+            (
+                -32000,
+                "header for block 0x1234 does not exist",
+                Rejection::Other,
+            ),
+            (-32000, "something else", Rejection::Other),
+        ];
+        for (code, message, kind) in cases {
+            assert_eq!(Rejection::classify(code, message), kind, "{message}");
+        }
+    }
+
+    #[test]
+    fn retries_only_transport_failures() {
+        let transport = Error::rpc(RpcError::Transport(TransportErrorKind::BackendGone));
+        assert!(matches!(transport, Error::Transport(_)));
+        assert!(transport.is_retryable());
+
+        let local = [
+            RpcError::NullResp,
+            RpcError::UnsupportedFeature("batching"),
+            RpcError::LocalUsageError("no signer".into()),
+            RpcError::DeserError {
+                err: serde_json::from_str::<u64>("\"x\"").unwrap_err(),
+                text: "\"x\"".into(),
+            },
+        ];
+        for err in local {
+            let err = Error::rpc(err);
+            assert!(matches!(err, Error::Rpc(_)), "{err}");
+            assert!(!err.is_retryable(), "{err}");
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! Storage layout collisions when an EOA is re-delegated.
+//!
+//! Storage belongs to the account, not to the delegate, so whatever the previous delegate wrote
+//! (e.g. `BatchCallAndSponsor.nonce` at slot 0) is still there when the account is pointed at a
+//! new implementation. Layouts come from the `storageLayout` output of Forge artifacts.
+
+use std::{collections::BTreeMap, fmt};
+
+use alloy::{
+    primitives::{Address, B256, U256, keccak256},
+    providers::Provider,
+};
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageEntry {
+    pub label: String,
+    /// Byte offset within the slot, for packed variables.
+    pub offset: u64,
+    #[serde(with = "decimal")]
+    pub slot: U256,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageType {
+    pub encoding: String,
+    pub label: String,
+    #[serde(with = "decimal")]
+    pub number_of_bytes: U256,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StorageLayout {
+    pub storage: Vec<StorageEntry>,
+    /// `null` in solc output for contracts without state variables.
+    #[serde(default)]
+    pub types: Option<BTreeMap<String, StorageType>>,
+}
+
+mod decimal {
+    use alloy::primitives::U256;
+    use serde::{Deserialize, Deserializer, de::Error};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        U256::from_str_radix(&raw, 10).map_err(D::Error::custom)
+    }
+}
+
+/// A variable resolved to the slots and bytes it occupies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    pub label: String,
+    pub ty: String,
+    pub slot: U256,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl Variable {
+    /// Absolute byte range, counting each slot as 32 bytes.
+    fn range(&self) -> (U256, U256) {
+        let start = self.slot * U256::from(32) + U256::from(self.offset);
+        (start, start + U256::from(self.size))
+    }
+
+    /// Slots the variable touches. Dynamic types only own their base slot here; their data
+    /// lives at hashed locations.
+    pub fn slots(&self) -> impl Iterator<Item = U256> + '_ {
+        let count = (self.offset + self.size).div_ceil(32).max(1);
+        (0..count).map(move |i| self.slot + U256::from(i))
+    }
+}
+
+impl fmt::Display for Variable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} (slot {}", self.ty, self.label, self.slot)?;
+        if self.offset != 0 {
+            write!(f, ", offset {}", self.offset)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl StorageLayout {
+    pub fn variables(&self) -> Vec<Variable> {
+        self.storage
+            .iter()
+            .map(|entry| {
+                let ty = self.types.as_ref().and_then(|types| types.get(&entry.ty));
+                Variable {
+                    label: entry.label.clone(),
+                    ty: ty.map_or_else(|| entry.ty.clone(), |t| t.label.clone()),
+                    slot: entry.slot,
+                    offset: entry.offset,
+                    size: ty.map_or(32, |t| t.number_of_bytes.saturating_to()),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Collision {
+    /// Both layouts use the same bytes for different types or at different offsets.
+    Incompatible {
+        current: Variable,
+        proposed: Variable,
+    },
+    /// Same type and position under another name: the new delegate reuses the old value as is.
+    Renamed {
+        current: Variable,
+        proposed: Variable,
+    },
+    /// A slot the proposed delegate reads holds a value that the current layout does not explain.
+    Dirty { proposed: Variable, value: B256 },
+}
+
+impl Collision {
+    pub fn is_incompatible(&self) -> bool {
+        matches!(self, Self::Incompatible { .. })
+    }
+}
+
+impl fmt::Display for Collision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Incompatible { current, proposed } => {
+                write!(f, "incompatible: {current} overlaps {proposed}")
+            }
+            Self::Renamed { current, proposed } => {
+                write!(f, "reused: {proposed} keeps the value of {current}")
+            }
+            Self::Dirty { proposed, value } => {
+                write!(f, "dirty: {proposed} starts out as {value}")
+            }
+        }
+    }
+}
+
+/// Compares the variables of the current and proposed delegate.
+pub fn compare(current: &StorageLayout, proposed: &StorageLayout) -> Vec<Collision> {
+    let current = current.variables();
+    let mut collisions = Vec::new();
+    for new in proposed.variables() {
+        let (new_start, new_end) = new.range();
+        for old in &current {
+            let (old_start, old_end) = old.range();
+            if new_start >= old_end || old_start >= new_end {
+                continue;
+            }
+            let same_shape = old.ty == new.ty && old.offset == new.offset && old.slot == new.slot;
+            let collision = if !same_shape {
+                Collision::Incompatible {
+                    current: old.clone(),
+                    proposed: new.clone(),
+                }
+            } else if old.label != new.label {
+                Collision::Renamed {
+                    current: old.clone(),
+                    proposed: new.clone(),
+                }
+            } else {
+                continue;
+            };
+            collisions.push(collision);
+        }
+    }
+    collisions
+}
+
+/// Like [`compare`], and additionally reads the account's live slots used by the proposed
+/// delegate. Non-zero slots not covered by the current layout are reported as dirty, since
+/// some earlier delegate must have written them.
+pub async fn check_account<P: Provider>(
+    provider: &P,
+    account: Address,
+    current: Option<&StorageLayout>,
+    proposed: &StorageLayout,
+) -> Result<Vec<Collision>> {
+    let mut collisions = current
+        .map(|current| compare(current, proposed))
+        .unwrap_or_default();
+
+    for variable in proposed.variables() {
+        if collisions.iter().any(|c| match c {
+            Collision::Incompatible { proposed, .. } | Collision::Renamed { proposed, .. } => {
+                *proposed == variable
+            }
+            Collision::Dirty { .. } => false,
+        }) {
+            continue;
+        }
+        let explained = current.is_some_and(|current| {
+            current
+                .variables()
+                .iter()
+                .any(|old| old.slots().any(|s| variable.slots().any(|n| n == s)))
+        });
+        if explained {
+            continue;
+        }
+        for slot in variable.slots() {
+            let value = B256::from(
+                provider
+                    .get_storage_at(account, slot)
+                    .await
+                    .map_err(Error::rpc)?,
+            );
+            if !value.is_zero() {
+                collisions.push(Collision::Dirty {
+                    proposed: variable.clone(),
+                    value,
+                });
+                break;
+            }
+        }
+    }
+    Ok(collisions)
+}
+
+/// Base slot of an ERC-7201 namespace: `keccak256(keccak256(id) - 1) & ~0xff`.
+pub fn erc7201_slot(namespace: &str) -> B256 {
+    let inner = U256::from_be_bytes(keccak256(namespace.as_bytes()).0) - U256::from(1);
+    let mut slot = keccak256(inner.to_be_bytes::<32>());
+    slot.0[31] = 0;
+    slot
+}
+
+pub fn recommendation(contract: &str) -> String {
+    let namespace = format!("eip7702.{}", contract.to_lowercase());
+    format!(
+        "move {contract}'s state into an ERC-7201 namespaced struct \
+         (`@custom:storage-location erc7201:{namespace}`, base slot {}) so that delegates \
+         never share slots",
+        erc7201_slot(&namespace)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifacts::Artifact;
+    use alloy::primitives::b256;
+
+    #[test]
+    fn erc7201_example_slot() {
+        // From the ERC-7201 specification.
+        assert_eq!(
+            erc7201_slot("example.main"),
+            b256!("0x183a6125c38840424c4a85fa12bab2ab606c4b6d0e7cc73c0c06ba5300eab500")
+        );
+    }
+
+    #[test]
+    fn flags_address_over_nonce() {
+        let batch = Artifact::load("BatchCallAndSponsor")
+            .unwrap()
+            .storage_layout
+            .unwrap();
+        assert!(compare(&batch, &batch).is_empty());
+
+        let owner: StorageLayout = serde_json::from_value(serde_json::json!({
+            "storage": [{ "label": "owner", "offset": 0, "slot": "0", "type": "t_address" }],
+            "types": { "t_address": { "encoding": "inplace", "label": "address", "numberOfBytes": "20" } }
+        }))
+        .unwrap();
+        let collisions = compare(&batch, &owner);
+        assert_eq!(collisions.len(), 1);
+        assert!(collisions[0].is_incompatible());
+    }
+}
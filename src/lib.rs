@@ -0,0 +1,30 @@
+//! Shared building blocks for the EIP-7702 examples and tools in `src/bin`.
+
+pub mod analyzer;
+pub mod artifacts;
+pub mod authorization;
+pub mod batch;
+pub mod batch_report;
+pub mod conformance;
+pub mod contracts;
+pub mod decode;
+pub mod drain;
+pub mod erc7821;
+pub mod error;
+pub mod layout;
+pub mod passkey;
+pub mod presubmit;
+pub mod preview;
+pub mod recovery;
+pub mod registry;
+pub mod revert;
+pub mod session_keys;
+pub mod signed_batch;
+pub mod signed_transfer;
+pub mod simulate;
+pub mod threshold;
+pub mod trace;
+pub mod typed_batch;
+pub mod user_operation;
+pub mod verify;
+pub mod wallet_rpc;
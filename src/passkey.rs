@@ -0,0 +1,346 @@
+//! P-256 (passkey) signers for accounts delegated to `P256Delegate`.
+//!
+//! [`P256Signer`] signs batch digests with a raw P-256 key, for `execute`. A passkey cannot sign
+//! arbitrary digests: it signs WebAuthn assertions, with the digest as the challenge.
+//! [`WebAuthnResponse`] holds the parts of `navigator.credentials.get()`'s response the account
+//! needs, and [`WebAuthnResponse::assertion`] encodes them for `executeWithPasskey`.
+//! [`P256Signer::sign_webauthn`] produces such responses in software, for tests and tools.
+
+use alloy::primitives::{Address, B256, Bytes, U256};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use p256::ecdsa::{
+    Signature, SigningKey, VerifyingKey,
+    signature::hazmat::{PrehashSigner, PrehashVerifier},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{Error, Result},
+    signed_batch,
+};
+
+pub use crate::signed_batch::{Call, P256Delegate, WebAuthnAssertion};
+
+/// Tags the digest the key signs, directly or as a WebAuthn challenge.
+pub const BATCH_TAG: &str = "P256Delegate.batch";
+
+/// The `flags` bit of authenticator data set when the user was present.
+pub const USER_PRESENT: u8 = 0x01;
+/// The `flags` bit of authenticator data set when the user was verified, e.g. by biometrics.
+pub const USER_VERIFIED: u8 = 0x04;
+
+const WEBAUTHN_GET: &str = r#""type":"webauthn.get""#;
+const CHALLENGE: &str = r#""challenge":""#;
+
+/// An uncompressed P-256 public key, as `P256Delegate` stores it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicKey {
+    pub x: B256,
+    pub y: B256,
+}
+
+impl PublicKey {
+    /// From a SEC1-encoded point, such as the end of a passkey's SPKI public key.
+    pub fn from_sec1(bytes: &[u8]) -> Result<Self> {
+        let key = VerifyingKey::from_sec1_bytes(bytes)
+            .map_err(|err| Error::Config(format!("invalid P-256 public key: {err}")))?;
+        Ok(Self::from(&key))
+    }
+
+    /// The call the account makes on itself to set its key.
+    pub fn set_call(&self) -> P256Delegate::setPublicKeyCall {
+        P256Delegate::setPublicKeyCall {
+            x: self.x,
+            y: self.y,
+        }
+    }
+
+    /// Checks a signature over `hash` the way `P256Delegate` does, which rejects high-s values.
+    pub fn verify(&self, hash: B256, r: B256, s: B256) -> Result<()> {
+        let mut sec1 = [0u8; 65];
+        sec1[0] = 0x04;
+        sec1[1..33].copy_from_slice(self.x.as_slice());
+        sec1[33..].copy_from_slice(self.y.as_slice());
+        let key = VerifyingKey::from_sec1_bytes(&sec1)
+            .map_err(|err| Error::InvalidSignature(format!("invalid P-256 public key: {err}")))?;
+        let signature = Signature::from_scalars(r.0, s.0)
+            .map_err(|err| Error::InvalidSignature(err.to_string()))?;
+        if signature.normalize_s().is_some() {
+            return Err(Error::InvalidSignature("high-s P-256 signature".into()));
+        }
+        key.verify_prehash(hash.as_slice(), &signature)
+            .map_err(|_| Error::InvalidSignature(format!("not signed by {self:?}")))
+    }
+}
+
+impl From<&VerifyingKey> for PublicKey {
+    fn from(key: &VerifyingKey) -> Self {
+        let point = key.to_encoded_point(false);
+        Self {
+            x: B256::from_slice(point.x().expect("uncompressed point")),
+            y: B256::from_slice(point.y().expect("uncompressed point")),
+        }
+    }
+}
+
+/// `r` and `s` of a signature, with `s` in the lower half of the order as `P256Delegate` wants.
+fn scalars(signature: Signature) -> (B256, B256) {
+    let signature = signature.normalize_s().unwrap_or(signature);
+    let (r, s) = signature.split_bytes();
+    (B256::from_slice(&r), B256::from_slice(&s))
+}
+
+/// A P-256 key held in memory.
+#[derive(Clone)]
+pub struct P256Signer {
+    key: SigningKey,
+}
+
+impl P256Signer {
+    pub fn from_bytes(secret: &B256) -> Result<Self> {
+        let key = SigningKey::from_slice(secret.as_slice())
+            .map_err(|err| Error::Signing(Box::new(err)))?;
+        Ok(Self { key })
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from(self.key.verifying_key())
+    }
+
+    /// Signs `hash` as is, returning low-s `(r, s)`.
+    pub fn sign_hash(&self, hash: B256) -> Result<(B256, B256)> {
+        let signature: Signature = self
+            .key
+            .sign_prehash(hash.as_slice())
+            .map_err(|err| Error::Signing(Box::new(err)))?;
+        Ok(scalars(signature))
+    }
+
+    /// Signs a batch for `execute`.
+    pub fn sign_batch(
+        &self,
+        chain_id: u64,
+        account: Address,
+        nonce: U256,
+        calls: &[Call],
+    ) -> Result<P256Delegate::executeCall> {
+        let (r, s) = self.sign_hash(digest(chain_id, account, nonce, calls))?;
+        Ok(P256Delegate::executeCall {
+            calls: calls.to_vec(),
+            r,
+            s,
+        })
+    }
+
+    /// Answers a WebAuthn `get` for `challenge` the way an authenticator for `rp_id` on `origin`
+    /// would, with the user present and verified.
+    pub fn sign_webauthn(
+        &self,
+        challenge: B256,
+        rp_id: &str,
+        origin: &str,
+    ) -> Result<WebAuthnResponse> {
+        let authenticator_data = authenticator_data(rp_id, USER_PRESENT | USER_VERIFIED, 0);
+        let client_data_json = client_data_json(challenge, origin);
+        let hash = message_hash(&authenticator_data, &client_data_json);
+        let signature: Signature = self
+            .key
+            .sign_prehash(hash.as_slice())
+            .map_err(|err| Error::Signing(Box::new(err)))?;
+        Ok(WebAuthnResponse {
+            authenticator_data,
+            client_data_json,
+            signature: signature.to_der().as_bytes().to_vec().into(),
+        })
+    }
+}
+
+impl std::fmt::Debug for P256Signer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("P256Signer")
+            .field("public_key", &self.public_key())
+            .finish_non_exhaustive()
+    }
+}
+
+/// `keccak256(abi.encode(BATCH, chainid, account, nonce, calls))`, the digest the key signs and
+/// the challenge of passkey assertions.
+pub fn digest(chain_id: u64, account: Address, nonce: U256, calls: &[Call]) -> B256 {
+    signed_batch::digest(BATCH_TAG, chain_id, account, nonce, calls)
+}
+
+/// Authenticator data without attested credential data or extensions:
+/// `sha256(rp_id) || flags || sign_count`.
+pub fn authenticator_data(rp_id: &str, flags: u8, sign_count: u32) -> Bytes {
+    let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+    data.push(flags);
+    data.extend_from_slice(&sign_count.to_be_bytes());
+    data.into()
+}
+
+/// The client data a browser on `origin` collects for a WebAuthn `get` of `challenge`.
+pub fn client_data_json(challenge: B256, origin: &str) -> String {
+    format!(
+        r#"{{{WEBAUTHN_GET},{CHALLENGE}{}","origin":{},"crossOrigin":false}}"#,
+        URL_SAFE_NO_PAD.encode(challenge),
+        serde_json::Value::from(origin)
+    )
+}
+
+/// What the authenticator signs: `sha256(authenticator_data || sha256(client_data_json))`.
+pub fn message_hash(authenticator_data: &[u8], client_data_json: &str) -> B256 {
+    let client_data_hash = Sha256::digest(client_data_json.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(authenticator_data);
+    hasher.update(client_data_hash);
+    B256::from_slice(&hasher.finalize())
+}
+
+/// The `response` of a WebAuthn assertion, as `PublicKeyCredential.toJSON()` gives it, with
+/// base64url fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebAuthnResponse {
+    pub authenticator_data: Bytes,
+    pub client_data_json: String,
+    /// DER-encoded ECDSA signature.
+    pub signature: Bytes,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WebAuthnResponseJson {
+    authenticator_data: String,
+    #[serde(rename = "clientDataJSON")]
+    client_data_json: String,
+    signature: String,
+}
+
+impl Serialize for WebAuthnResponse {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        WebAuthnResponseJson {
+            authenticator_data: URL_SAFE_NO_PAD.encode(&self.authenticator_data),
+            client_data_json: URL_SAFE_NO_PAD.encode(&self.client_data_json),
+            signature: URL_SAFE_NO_PAD.encode(&self.signature),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for WebAuthnResponse {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error as _;
+        let json = WebAuthnResponseJson::deserialize(deserializer)?;
+        let decode = |field: &str| {
+            URL_SAFE_NO_PAD
+                .decode(field.trim_end_matches('='))
+                .map_err(D::Error::custom)
+        };
+        Ok(Self {
+            authenticator_data: decode(&json.authenticator_data)?.into(),
+            client_data_json: String::from_utf8(decode(&json.client_data_json)?)
+                .map_err(D::Error::custom)?,
+            signature: decode(&json.signature)?.into(),
+        })
+    }
+}
+
+impl WebAuthnResponse {
+    /// The challenge the browser was asked to sign.
+    pub fn challenge(&self) -> Result<B256> {
+        let value: serde_json::Value = serde_json::from_str(&self.client_data_json)
+            .map_err(|err| Error::InvalidSignature(format!("invalid clientDataJSON: {err}")))?;
+        let challenge = value["challenge"]
+            .as_str()
+            .and_then(|challenge| URL_SAFE_NO_PAD.decode(challenge).ok())
+            .filter(|challenge| challenge.len() == 32)
+            .ok_or_else(|| {
+                Error::InvalidSignature("clientDataJSON has no 32-byte challenge".into())
+            })?;
+        Ok(B256::from_slice(&challenge))
+    }
+
+    /// Encodes the response for `executeWithPasskey`, checking what the contract checks:
+    /// a `webauthn.get` of `challenge` with the user present.
+    pub fn assertion(&self, challenge: B256) -> Result<WebAuthnAssertion> {
+        let invalid =
+            |reason: &str| Error::InvalidSignature(format!("WebAuthn assertion {reason}"));
+        let type_index = self
+            .client_data_json
+            .find(WEBAUTHN_GET)
+            .ok_or_else(|| invalid("is not a webauthn.get"))?;
+        let expected = format!("{CHALLENGE}{}\"", URL_SAFE_NO_PAD.encode(challenge));
+        let challenge_index = self
+            .client_data_json
+            .find(&expected)
+            .ok_or_else(|| invalid(&format!("is not for challenge {challenge}")))?;
+        if self.authenticator_data.len() < 37 || self.authenticator_data[32] & USER_PRESENT == 0 {
+            return Err(invalid("has no user presence"));
+        }
+        let signature = Signature::from_der(&self.signature)
+            .map_err(|err| invalid(&format!("has an invalid signature: {err}")))?;
+        let (r, s) = scalars(signature);
+        Ok(WebAuthnAssertion {
+            authenticatorData: self.authenticator_data.clone(),
+            clientDataJSON: self.client_data_json.clone(),
+            challengeIndex: U256::from(challenge_index),
+            typeIndex: U256::from(type_index),
+            r,
+            s,
+        })
+    }
+}
+
+impl WebAuthnAssertion {
+    /// The hash the passkey signed.
+    pub fn message_hash(&self) -> B256 {
+        message_hash(&self.authenticatorData, &self.clientDataJSON)
+    }
+}
+
+/// `P256Delegate`'s ABI. `InvalidClientData` and `UserNotPresent` in it explain refused
+/// assertions.
+pub fn abi() -> alloy::json_abi::JsonAbi {
+    P256Delegate::abi::contract()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_webauthn_assertions() {
+        let signer = P256Signer::from_bytes(&B256::with_last_byte(7)).unwrap();
+        let key = signer.public_key();
+        let challenge = digest(31337, Address::with_last_byte(0xaa), U256::ZERO, &[]);
+
+        let (r, s) = signer.sign_hash(challenge).unwrap();
+        key.verify(challenge, r, s).unwrap();
+
+        let response = signer
+            .sign_webauthn(challenge, "example.com", "https://example.com")
+            .unwrap();
+        let json = serde_json::to_string(&response).unwrap();
+        let response: WebAuthnResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(response.challenge().unwrap(), challenge);
+
+        let assertion = response.assertion(challenge).unwrap();
+        let client_data = assertion.clientDataJSON.as_bytes();
+        let type_index = assertion.typeIndex.to::<usize>();
+        assert_eq!(
+            &client_data[type_index..type_index + 21],
+            WEBAUTHN_GET.as_bytes()
+        );
+        key.verify(assertion.message_hash(), assertion.r, assertion.s)
+            .unwrap();
+
+        assert!(response.assertion(B256::ZERO).is_err());
+        let other = P256Signer::from_bytes(&B256::with_last_byte(8)).unwrap();
+        assert!(
+            other
+                .public_key()
+                .verify(assertion.message_hash(), assertion.r, assertion.s)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,423 @@
+//! Per-call simulation of a batch before a sponsor pays for `execute(calls, signature)`.
+//!
+//! The calls run in order as transactions from the account, in a single `eth_simulateV1` block
+//! whose state overrides make the account look delegated. The balances of the account and of
+//! every call target are read after each call, so a call that would revert is reported along
+//! with the balance changes the calls before it made.
+//!
+//! The calls alone do not exercise the executor, so a stale nonce or a signature it rejects goes
+//! unnoticed. [`simulate_sponsored`] also runs the sponsor's transaction to the account and
+//! blocks submission if the executor reverts.
+
+use std::{collections::BTreeMap, fmt};
+
+use alloy::{
+    network::TransactionBuilder,
+    primitives::{Address, Bytes, I256, U256},
+    providers::Provider,
+    rpc::types::{
+        TransactionRequest,
+        simulate::{SimBlock, SimCallResult, SimulatePayload},
+        state::StateOverridesBuilder,
+    },
+};
+
+use crate::{
+    authorization::DELEGATION_PREFIX,
+    batch_report, contracts,
+    decode::AbiRegistry,
+    error::{Error, Result},
+    revert::{Revert, RevertError},
+    signed_batch,
+    simulate::{DEFAULT_GAS, READER, READER_CODE, decode_reader, reader_call},
+    typed_batch,
+};
+
+/// A call of a batch, whatever executor it is meant for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedCall {
+    pub to: Address,
+    pub value: U256,
+    pub data: Bytes,
+    /// Whether the executor carries on when this call fails.
+    pub allow_failure: bool,
+}
+
+impl From<&contracts::BatchCallAndSponsor::Call> for PlannedCall {
+    fn from(call: &contracts::BatchCallAndSponsor::Call) -> Self {
+        Self {
+            to: call.to,
+            value: call.value,
+            data: call.data.clone(),
+            allow_failure: false,
+        }
+    }
+}
+
+impl From<&typed_batch::Call> for PlannedCall {
+    fn from(call: &typed_batch::Call) -> Self {
+        Self {
+            to: call.to,
+            value: call.value,
+            data: call.data.clone(),
+            allow_failure: false,
+        }
+    }
+}
+
+impl From<&signed_batch::Call> for PlannedCall {
+    fn from(call: &signed_batch::Call) -> Self {
+        Self {
+            to: call.to,
+            value: call.value,
+            data: call.data.clone(),
+            allow_failure: false,
+        }
+    }
+}
+
+impl From<&batch_report::Call> for PlannedCall {
+    fn from(call: &batch_report::Call) -> Self {
+        Self {
+            to: call.to,
+            value: call.value,
+            data: call.data.clone(),
+            allow_failure: call.allowFailure,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CallOutcome {
+    pub index: usize,
+    pub to: Address,
+    pub allow_failure: bool,
+    pub success: bool,
+    /// Return data, or the revert data if the call reverted.
+    pub output: Bytes,
+    pub revert: Option<Revert>,
+    /// The node's message for a failed call, e.g. for failures without revert data.
+    pub error: Option<String>,
+    pub gas_used: u64,
+    /// Net balance changes of the account and the call targets, up to and including this call.
+    pub balance_deltas: BTreeMap<Address, I256>,
+}
+
+impl fmt::Display for CallOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} {}", self.index, self.to)?;
+        if self.success {
+            write!(f, " ok, gas {}", self.gas_used)
+        } else {
+            let kind = if self.allow_failure {
+                "optional call failed"
+            } else {
+                "reverts"
+            };
+            match (&self.revert, &self.error) {
+                (Some(Revert::Unknown(data)), Some(error)) if data.is_empty() => {
+                    write!(f, " {kind}: {error}")
+                }
+                (Some(revert), _) => write!(f, " {kind}: {revert}"),
+                (None, _) => write!(f, " {kind}"),
+            }
+        }
+    }
+}
+
+/// The sponsor's transaction to the account, run through the executor.
+#[derive(Debug, Clone)]
+pub struct ExecutionOutcome {
+    pub account: Address,
+    pub success: bool,
+    pub output: Bytes,
+    pub revert: Option<Revert>,
+    pub error: Option<String>,
+    pub gas_used: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchSimulation {
+    pub calls: Vec<CallOutcome>,
+    /// Set by [`simulate_sponsored`].
+    pub execution: Option<ExecutionOutcome>,
+}
+
+impl BatchSimulation {
+    /// The first failing call without `allowFailure`, where the executor would revert.
+    pub fn blocking(&self) -> Option<&CallOutcome> {
+        self.calls
+            .iter()
+            .find(|call| !call.success && !call.allow_failure)
+    }
+
+    /// Errors with the revert of the [blocking](Self::blocking) call, if there is one, or else
+    /// with the revert of the sponsor's transaction.
+    pub fn ensure_submittable(&self) -> Result<()> {
+        if let Some(call) = self.blocking() {
+            return Err(Error::Revert(RevertError {
+                context: format!("call #{} to {}", call.index, call.to),
+                revert: call
+                    .revert
+                    .clone()
+                    .unwrap_or_else(|| Revert::Unknown(call.output.clone())),
+                data: call.output.clone(),
+            }));
+        }
+        match &self.execution {
+            Some(execution) if !execution.success => Err(Error::Revert(RevertError {
+                context: format!("execute on {}", execution.account),
+                revert: execution
+                    .revert
+                    .clone()
+                    .unwrap_or_else(|| Revert::Unknown(execution.output.clone())),
+                data: execution.output.clone(),
+            })),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Simulates `calls` in order from `account`.
+///
+/// With `delegate` set the account's code is overridden with a delegation to it, for batches
+/// submitted together with the authorization. Calls after a blocking one are still simulated,
+/// although the executor would never reach them.
+pub async fn simulate_batch<P: Provider>(
+    provider: &P,
+    account: Address,
+    delegate: Option<Address>,
+    calls: &[PlannedCall],
+    abis: &AbiRegistry,
+) -> Result<BatchSimulation> {
+    let mut tracked = vec![account];
+    for call in calls {
+        if !tracked.contains(&call.to) {
+            tracked.push(call.to);
+        }
+    }
+    let reads: Vec<TransactionRequest> = tracked.iter().map(|a| reader_call(*a)).collect();
+
+    let mut txs = reads.clone();
+    for call in calls {
+        txs.push(
+            TransactionRequest::default()
+                .with_from(account)
+                .with_to(call.to)
+                .with_value(call.value)
+                .with_input(call.data.clone())
+                .with_gas_limit(DEFAULT_GAS),
+        );
+        txs.extend(reads.iter().cloned());
+    }
+
+    let mut overrides = StateOverridesBuilder::default().with_code(READER, READER_CODE);
+    if let Some(delegate) = delegate {
+        overrides = overrides.with_code(account, delegation_code(delegate));
+    }
+    let payload = SimulatePayload {
+        block_state_calls: vec![SimBlock {
+            block_overrides: None,
+            state_overrides: Some(overrides.build()),
+            calls: txs,
+        }],
+        trace_transfers: false,
+        validation: false,
+        return_full_transactions: false,
+    };
+
+    let results = provider
+        .simulate(&payload)
+        .await
+        .map_err(Error::rpc)?
+        .into_iter()
+        .next()
+        .map(|block| block.calls)
+        .unwrap_or_default();
+    let stride = 1 + tracked.len();
+    if results.len() != tracked.len() + calls.len() * stride {
+        return Err(Error::UnexpectedResponse(format!(
+            "eth_simulateV1 returned {} results for {} calls",
+            results.len(),
+            tracked.len() + calls.len() * stride
+        )));
+    }
+
+    let balances = |reads: &[SimCallResult]| -> Result<Vec<U256>> {
+        reads
+            .iter()
+            .map(|read| Ok(decode_reader(&read.return_data)?.0))
+            .collect()
+    };
+    let initial = balances(&results[..tracked.len()])?;
+
+    let mut outcomes = Vec::with_capacity(calls.len());
+    for (index, (call, chunk)) in calls
+        .iter()
+        .zip(results[tracked.len()..].chunks(stride))
+        .enumerate()
+    {
+        let (result, reads) = chunk.split_first().expect("chunks are never empty");
+        let balance_deltas = tracked
+            .iter()
+            .zip(initial.iter().zip(balances(reads)?))
+            .map(|(address, (before, after))| {
+                (*address, I256::from_raw(after.wrapping_sub(*before)))
+            })
+            .filter(|(_, delta)| !delta.is_zero())
+            .collect();
+        outcomes.push(CallOutcome {
+            index,
+            to: call.to,
+            allow_failure: call.allow_failure,
+            success: result.status,
+            output: result.return_data.clone(),
+            revert: (!result.status).then(|| abis.decode_revert(&result.return_data)),
+            error: result.error.as_ref().map(|e| e.message.clone()),
+            gas_used: result.gas_used,
+            balance_deltas,
+        });
+    }
+
+    Ok(BatchSimulation {
+        calls: outcomes,
+        execution: None,
+    })
+}
+
+/// Simulates `calls` with [`simulate_batch`], then `input` sent from `sponsor` to `account`,
+/// the transaction the sponsor is about to pay for.
+pub async fn simulate_sponsored<P: Provider>(
+    provider: &P,
+    sponsor: Address,
+    account: Address,
+    delegate: Option<Address>,
+    input: Bytes,
+    calls: &[PlannedCall],
+    abis: &AbiRegistry,
+) -> Result<BatchSimulation> {
+    let mut simulation = simulate_batch(provider, account, delegate, calls, abis).await?;
+
+    let mut overrides = StateOverridesBuilder::default();
+    if let Some(delegate) = delegate {
+        overrides = overrides.with_code(account, delegation_code(delegate));
+    }
+    let payload = SimulatePayload {
+        block_state_calls: vec![SimBlock {
+            block_overrides: None,
+            state_overrides: Some(overrides.build()),
+            calls: vec![
+                TransactionRequest::default()
+                    .with_from(sponsor)
+                    .with_to(account)
+                    .with_input(input)
+                    .with_gas_limit(DEFAULT_GAS),
+            ],
+        }],
+        trace_transfers: false,
+        validation: false,
+        return_full_transactions: false,
+    };
+    let result = provider
+        .simulate(&payload)
+        .await
+        .map_err(Error::rpc)?
+        .into_iter()
+        .next()
+        .and_then(|block| block.calls.into_iter().next())
+        .ok_or_else(|| {
+            Error::UnexpectedResponse("eth_simulateV1 returned no result for execute".into())
+        })?;
+
+    simulation.execution = Some(ExecutionOutcome {
+        account,
+        success: result.status,
+        revert: (!result.status).then(|| abis.decode_revert(&result.return_data)),
+        output: result.return_data,
+        error: result.error.map(|e| e.message),
+        gas_used: result.gas_used,
+    });
+    Ok(simulation)
+}
+
+fn delegation_code(delegate: Address) -> Bytes {
+    [&DELEGATION_PREFIX[..], delegate.as_slice()]
+        .concat()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::address;
+
+    fn outcome(index: usize, success: bool, allow_failure: bool) -> CallOutcome {
+        CallOutcome {
+            index,
+            to: address!("0x00000000000000000000000000000000000000d1"),
+            allow_failure,
+            success,
+            output: Bytes::new(),
+            revert: (!success).then(|| Revert::Reason("nope".into())),
+            error: None,
+            gas_used: 21_000,
+            balance_deltas: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn only_required_failures_block() {
+        let simulation = BatchSimulation {
+            calls: vec![outcome(0, true, false), outcome(1, false, true)],
+            execution: None,
+        };
+        assert!(simulation.ensure_submittable().is_ok());
+
+        let simulation = BatchSimulation {
+            calls: vec![
+                outcome(0, false, true),
+                outcome(1, false, false),
+                outcome(2, false, false),
+            ],
+            execution: None,
+        };
+        assert_eq!(simulation.blocking().unwrap().index, 1);
+        match simulation.ensure_submittable() {
+            Err(Error::Revert(err)) => {
+                assert!(err.context.starts_with("call #1"));
+                assert_eq!(err.revert, Revert::Reason("nope".into()));
+            }
+            other => panic!("expected a revert, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn a_reverting_execution_blocks() {
+        let account = address!("0x00000000000000000000000000000000000a11ce");
+        let mut simulation = BatchSimulation {
+            calls: vec![outcome(0, true, false)],
+            execution: Some(ExecutionOutcome {
+                account,
+                success: false,
+                output: Bytes::new(),
+                revert: Some(Revert::Reason("Invalid signature".into())),
+                error: None,
+                gas_used: 30_000,
+            }),
+        };
+        match simulation.ensure_submittable() {
+            Err(Error::Revert(err)) => {
+                assert_eq!(err.context, format!("execute on {account}"));
+                assert_eq!(err.revert, Revert::Reason("Invalid signature".into()));
+            }
+            other => panic!("expected a revert, got {other:?}"),
+        }
+
+        // A blocking call explains the failure better than the executor's revert.
+        simulation.calls.push(outcome(1, false, false));
+        match simulation.ensure_submittable() {
+            Err(Error::Revert(err)) => assert!(err.context.starts_with("call #1")),
+            other => panic!("expected a revert, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Previewing what a delegated account would do, before anything is signed.
+//!
+//! The account's code is overridden with the delegation designator `0xef0100 ‖ target` for a
+//! single `eth_call`, so no authorization, signature or gas is needed. Logs and balance changes
+//! are taken from a `debug_traceCall` with the same override.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    authorization::DELEGATION_PREFIX,
+    error::{Error, Result},
+};
+use alloy::{
+    eips::BlockId,
+    primitives::{Address, Bytes, I256, Log},
+    providers::{Provider, ext::DebugApi},
+    rpc::types::{
+        TransactionRequest,
+        state::{StateOverride, StateOverridesBuilder},
+        trace::geth::{
+            CallConfig, CallFrame, GethDebugTracingCallOptions, GethDebugTracingOptions,
+        },
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct Preview {
+    pub success: bool,
+    /// Return data, or the revert data if the call reverted.
+    pub output: Bytes,
+    pub gas_used: u64,
+    /// Logs of all frames that did not revert, in execution order.
+    pub logs: Vec<Log>,
+    /// Net ETH moved in or out of every touched address.
+    pub balance_deltas: BTreeMap<Address, I256>,
+}
+
+/// State override that makes `account` look delegated to `target`.
+pub fn delegation_override(account: Address, target: Address) -> StateOverride {
+    let code: Bytes = [&DELEGATION_PREFIX[..], target.as_slice()].concat().into();
+    StateOverridesBuilder::default()
+        .with_code(account, code)
+        .build()
+}
+
+/// Runs `tx` as if `account` were already delegated to `target`. `tx.to` defaults to `account`.
+pub async fn preview_delegation<P: Provider>(
+    provider: &P,
+    account: Address,
+    target: Address,
+    mut tx: TransactionRequest,
+) -> Result<Preview> {
+    if tx.to.is_none() {
+        tx.to = Some(account.into());
+    }
+    let overrides = delegation_override(account, target);
+
+    let (success, output) = match provider.call(tx.clone()).overrides(overrides.clone()).await {
+        Ok(output) => (true, output),
+        Err(err) => match err.as_error_resp().and_then(|e| e.as_revert_data()) {
+            Some(data) => (false, data),
+            None => return Err(Error::rpc(err)),
+        },
+    };
+
+    let options = GethDebugTracingCallOptions::new(GethDebugTracingOptions::call_tracer(
+        CallConfig::default().with_log(),
+    ))
+    .with_state_overrides(overrides);
+    let frame = provider
+        .debug_trace_call(tx, BlockId::latest(), options)
+        .await
+        .map_err(Error::rpc)?
+        .try_into_call_frame()?;
+
+    let mut preview = Preview {
+        success,
+        output,
+        gas_used: frame.gas_used.saturating_to(),
+        logs: Vec::new(),
+        balance_deltas: BTreeMap::new(),
+    };
+    collect(&frame, &mut preview);
+    preview.balance_deltas.retain(|_, delta| !delta.is_zero());
+    Ok(preview)
+}
+
+fn collect(frame: &CallFrame, preview: &mut Preview) {
+    if frame.error.is_some() {
+        return;
+    }
+    // Delegate and static calls never move ETH; CALLCODE sends it back to the caller.
+    let moves_value = !matches!(
+        frame.typ.as_str(),
+        "DELEGATECALL" | "STATICCALL" | "CALLCODE"
+    );
+    if let (true, Some(value), Some(to)) = (moves_value, frame.value, frame.to) {
+        let value = I256::from_raw(value);
+        *preview.balance_deltas.entry(frame.from).or_default() -= value;
+        *preview.balance_deltas.entry(to).or_default() += value;
+    }
+
+    // Logs and sub-calls are interleaved by position; order them as they happened.
+    let mut logs = frame.logs.iter().peekable();
+    for (index, call) in frame.calls.iter().enumerate() {
+        while let Some(log) = logs.next_if(|l| l.position.unwrap_or(0) <= index as u64) {
+            preview.logs.push(log.clone().into_log());
+        }
+        collect(call, preview);
+    }
+    preview.logs.extend(logs.map(|log| log.clone().into_log()));
+}
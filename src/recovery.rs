@@ -0,0 +1,290 @@
+//! Guardian recovery of the signing key of accounts delegated to `RecoveryDelegate`.
+//!
+//! The account names guardians with a [`GuardianConfig`]. If its signing key is lost, each
+//! guardian signs an approval of a [`Recovery`] to a new key, and once enough have signed anyone
+//! can start it. The current key can cancel a recovery it did not ask for until the timelock
+//! runs out; after that anyone can finalize it. Guardian approvals are
+//! [`PartialSignature`]s, so they are collected and ordered like threshold signatures.
+
+use alloy::{
+    primitives::{Address, B256, Bytes, U256, keccak256},
+    providers::Provider,
+    signers::local::PrivateKeySigner,
+    sol_types::SolValue,
+};
+
+use crate::{
+    batch,
+    error::{Error, Result},
+    signed_batch,
+    threshold::{self, PartialSignature},
+};
+
+pub use crate::signed_batch::{Call, RecoveryDelegate};
+
+/// Tags the digest of the signing key's batches; guardian approvals use [`RECOVERY_TAG`].
+pub const BATCH_TAG: &str = "RecoveryDelegate.batch";
+
+/// What `RecoveryDelegate.RECOVERY` hashes, so a guardian's approval never passes as a batch.
+pub const RECOVERY_TAG: &str = "RecoveryDelegate.recovery";
+
+/// `RecoveryDelegate.MIN_RECOVERY_DELAY`: one day, in seconds.
+pub const MIN_RECOVERY_DELAY: u64 = 86_400;
+
+/// The guardians of an account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuardianConfig {
+    pub guardians: Vec<Address>,
+    /// Approvals needed to start a recovery, zero while no guardians are set.
+    pub threshold: usize,
+    /// Seconds between starting and finalizing a recovery.
+    pub delay: u64,
+}
+
+impl GuardianConfig {
+    /// Checks the config the way `setGuardians` of `account` does, so a config it would refuse
+    /// is caught before the account sends it.
+    pub fn check(&self, account: Address) -> Result<()> {
+        if self.threshold == 0 || self.threshold > self.guardians.len() {
+            return Err(Error::Config(format!(
+                "threshold {} is not between 1 and the {} guardians",
+                self.threshold,
+                self.guardians.len()
+            )));
+        }
+        if self.delay < MIN_RECOVERY_DELAY {
+            return Err(Error::Config(format!(
+                "recovery delay {}s is shorter than the minimum of {MIN_RECOVERY_DELAY}s",
+                self.delay
+            )));
+        }
+        for (i, guardian) in self.guardians.iter().enumerate() {
+            let problem = if guardian.is_zero() {
+                "is the zero address"
+            } else if *guardian == account {
+                "is the account itself"
+            } else if self.guardians[..i].contains(guardian) {
+                "is listed twice"
+            } else {
+                continue;
+            };
+            return Err(Error::Config(format!("guardian #{i} {guardian} {problem}")));
+        }
+        Ok(())
+    }
+
+    /// The call the account makes on itself to set its guardians. Setting guardians drops a
+    /// pending recovery and the approvals collected so far.
+    pub fn set_call(&self) -> RecoveryDelegate::setGuardiansCall {
+        RecoveryDelegate::setGuardiansCall {
+            guardians: self.guardians.clone(),
+            threshold: U256::from(self.threshold),
+            delay: self.delay,
+        }
+    }
+}
+
+/// A rotation of the signing key of `account` to `new_signer`, at the account's recovery nonce.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovery {
+    pub chain_id: u64,
+    pub account: Address,
+    pub nonce: U256,
+    pub new_signer: Address,
+}
+
+impl Recovery {
+    /// `keccak256(abi.encode(RECOVERY, chainid, account, recoveryNonce, newSigner))`, as
+    /// `hashRecovery` computes it.
+    pub fn digest(&self) -> B256 {
+        keccak256(
+            (
+                keccak256(RECOVERY_TAG),
+                U256::from(self.chain_id),
+                self.account,
+                self.nonce,
+                self.new_signer,
+            )
+                .abi_encode_params(),
+        )
+    }
+
+    /// A guardian's approval of the recovery.
+    pub fn approve(&self, guardian: &PrivateKeySigner) -> Result<PartialSignature> {
+        PartialSignature::sign(guardian, self.digest())
+    }
+
+    /// Checks `approvals` against the guardians and builds the `initiateRecovery` call.
+    pub fn initiate_call(
+        &self,
+        approvals: &[PartialSignature],
+        guardians: &GuardianConfig,
+    ) -> Result<RecoveryDelegate::initiateRecoveryCall> {
+        let approvals = threshold::order_signatures(
+            self.digest(),
+            approvals,
+            &guardians.guardians,
+            guardians.threshold,
+        )?;
+        Ok(RecoveryDelegate::initiateRecoveryCall {
+            newSigner: self.new_signer,
+            approvals,
+        })
+    }
+}
+
+/// A recovery that has been started but not finalized or cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingRecovery {
+    pub new_signer: Address,
+    /// First block timestamp at which `finalizeRecovery` succeeds.
+    pub execute_after: u64,
+}
+
+impl PendingRecovery {
+    /// Checks that the timelock has passed in a block at `now`.
+    pub fn check_finalizable(&self, now: u64) -> Result<()> {
+        if now < self.execute_after {
+            return Err(Error::NotPermitted(format!(
+                "recovery to {} is timelocked for another {}s",
+                self.new_signer,
+                self.execute_after - now
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The recovery state of an account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveryStatus {
+    pub signer: Address,
+    pub guardians: GuardianConfig,
+    pub recovery_nonce: U256,
+    pub pending: Option<PendingRecovery>,
+}
+
+impl RecoveryStatus {
+    /// The recovery to `new_signer` that guardians approve next.
+    pub fn recovery(&self, chain_id: u64, account: Address, new_signer: Address) -> Recovery {
+        Recovery {
+            chain_id,
+            account,
+            nonce: self.recovery_nonce,
+            new_signer,
+        }
+    }
+}
+
+pub async fn status<P: Provider>(provider: &P, account: Address) -> Result<RecoveryStatus> {
+    let delegate = RecoveryDelegate::new(account, provider);
+    let pending_signer = delegate.pendingSigner().call().await?;
+    let pending = if pending_signer == Address::ZERO {
+        None
+    } else {
+        Some(PendingRecovery {
+            new_signer: pending_signer,
+            execute_after: delegate.recoveryExecuteAfter().call().await?,
+        })
+    };
+    Ok(RecoveryStatus {
+        signer: delegate.signer().call().await?,
+        guardians: GuardianConfig {
+            guardians: delegate.guardians().call().await?,
+            threshold: delegate.guardianThreshold().call().await?.saturating_to(),
+            delay: delegate.recoveryDelay().call().await?,
+        },
+        recovery_nonce: delegate.recoveryNonce().call().await?,
+        pending,
+    })
+}
+
+/// `keccak256(abi.encode(BATCH, chainid, account, nonce, calls))`, signed by the signing key.
+pub fn digest(chain_id: u64, account: Address, nonce: U256, calls: &[Call]) -> B256 {
+    signed_batch::digest(BATCH_TAG, chain_id, account, nonce, calls)
+}
+
+/// Signs a batch with the account's signing key.
+pub fn sign(
+    signer: &PrivateKeySigner,
+    chain_id: u64,
+    account: Address,
+    nonce: U256,
+    calls: &[Call],
+) -> Result<Bytes> {
+    batch::sign(signer, digest(chain_id, account, nonce, calls))
+}
+
+/// `RecoveryDelegate`'s ABI. A refused approval or recovery step reverts with one of its errors,
+/// such as `RecoveryTimelocked`.
+pub fn abi() -> alloy::json_abi::JsonAbi {
+    RecoveryDelegate::abi::contract()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approvals_are_bound_to_one_attempt() {
+        let guardians: Vec<PrivateKeySigner> = (1..=3u8)
+            .map(|i| PrivateKeySigner::from_bytes(&B256::with_last_byte(i)).unwrap())
+            .collect();
+        let config = GuardianConfig {
+            guardians: guardians
+                .iter()
+                .map(|guardian| guardian.address())
+                .collect(),
+            threshold: 2,
+            delay: 86_400,
+        };
+        let recovery = Recovery {
+            chain_id: 31337,
+            account: Address::with_last_byte(0xaa),
+            nonce: U256::ZERO,
+            new_signer: Address::with_last_byte(0xbb),
+        };
+        let approvals: Vec<PartialSignature> = guardians
+            .iter()
+            .map(|guardian| recovery.approve(guardian).unwrap())
+            .collect();
+
+        config.check(recovery.account).unwrap();
+        let instant = GuardianConfig {
+            delay: 0,
+            ..config.clone()
+        };
+        assert!(instant.check(recovery.account).is_err());
+        for guardian in [Address::ZERO, recovery.account, config.guardians[0]] {
+            let mut invalid = config.clone();
+            invalid.guardians.push(guardian);
+            assert!(matches!(
+                invalid.check(recovery.account),
+                Err(Error::Config(_))
+            ));
+        }
+
+        let call = recovery.initiate_call(&approvals[1..], &config).unwrap();
+        assert_eq!(call.newSigner, recovery.new_signer);
+        assert_eq!(call.approvals.len(), 2);
+        assert!(recovery.initiate_call(&approvals[..1], &config).is_err());
+
+        let next = Recovery {
+            nonce: U256::from(1),
+            ..recovery.clone()
+        };
+        assert!(next.initiate_call(&approvals, &config).is_err());
+        let elsewhere = Recovery {
+            new_signer: Address::with_last_byte(0xcc),
+            ..recovery
+        };
+        assert!(elsewhere.initiate_call(&approvals, &config).is_err());
+
+        let pending = PendingRecovery {
+            new_signer: Address::with_last_byte(0xbb),
+            execute_after: 100,
+        };
+        assert!(pending.check_finalizable(99).is_err());
+        pending.check_finalizable(100).unwrap();
+    }
+}
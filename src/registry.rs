@@ -0,0 +1,238 @@
+//! Allowlist of delegate implementations an EOA may be pointed at.
+//!
+//! Each approved delegate is pinned by the `extcodehash` of its runtime code and listed with
+//! its address on every chain it is deployed to. The default registry is `delegates.json` at
+//! the repository root; set `DELEGATES_FILE` to use another one and `DELEGATE_POLICY=warn` to
+//! only warn about unapproved delegates instead of refusing them.
+//!
+//! [`DelegateRegistry::sign_authorization`] is the way to sign a delegation; the raw signer in
+//! [`authorization`](crate::authorization) is kept for revocations and the drain probe.
+
+use std::{collections::BTreeMap, fmt, path::PathBuf};
+
+use alloy::{
+    eips::eip7702::SignedAuthorization,
+    primitives::{Address, B256, keccak256},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+};
+use serde::Deserialize;
+
+use crate::{
+    authorization::sign_unchecked,
+    error::{Error, Result},
+};
+
+pub const DEFAULT_REGISTRY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/delegates.json");
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApprovedDelegate {
+    pub name: String,
+    /// Expected `extcodehash` of the delegate.
+    pub codehash: B256,
+    /// Deployment address keyed by chain id.
+    #[serde(default)]
+    pub addresses: BTreeMap<u64, Address>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Refuse to sign or submit authorizations for unapproved delegates.
+    Enforce,
+    /// Print a warning and carry on.
+    Warn,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Approved {
+        name: String,
+    },
+    /// Delegation to the zero address clears the account's code.
+    Revocation,
+    Unknown {
+        address: Address,
+    },
+    CodehashMismatch {
+        name: String,
+        address: Address,
+        expected: B256,
+        actual: B256,
+    },
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Approved { name } => write!(f, "approved delegate {name}"),
+            Self::Revocation => write!(f, "revocation"),
+            Self::Unknown { address } => write!(f, "{address} is not an approved delegate"),
+            Self::CodehashMismatch {
+                name,
+                address,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{address} is registered as {name} but its codehash {actual} differs from the pinned {expected}"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryFile {
+    delegates: Vec<ApprovedDelegate>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DelegateRegistry {
+    pub delegates: Vec<ApprovedDelegate>,
+    pub policy: Policy,
+}
+
+impl DelegateRegistry {
+    pub fn from_path(path: impl Into<PathBuf>, policy: Policy) -> Result<Self> {
+        let path = path.into();
+        let raw = match std::fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(source) => return Err(Error::Io { path, source }),
+        };
+        let file: RegistryFile =
+            serde_json::from_str(&raw).map_err(|source| Error::Json { path, source })?;
+        Ok(Self {
+            delegates: file.delegates,
+            policy,
+        })
+    }
+
+    /// Loads the registry selected by `DELEGATES_FILE` and `DELEGATE_POLICY`.
+    pub fn from_env() -> Result<Self> {
+        let path = std::env::var("DELEGATES_FILE").unwrap_or_else(|_| DEFAULT_REGISTRY.into());
+        let policy = match std::env::var("DELEGATE_POLICY").as_deref() {
+            Ok("warn") => Policy::Warn,
+            Ok("enforce") | Err(_) => Policy::Enforce,
+            Ok(other) => {
+                return Err(Error::Config(format!("unknown DELEGATE_POLICY {other:?}")));
+            }
+        };
+        Self::from_path(path, policy)
+    }
+
+    /// Records where an approved delegate lives on `chain_id`, e.g. after a local deployment.
+    /// The codehash pin still applies to the new address.
+    pub fn register(&mut self, name: &str, chain_id: u64, address: Address) -> Result<()> {
+        let delegate = self
+            .delegates
+            .iter_mut()
+            .find(|d| d.name == name)
+            .ok_or_else(|| Error::Config(format!("{name} is not in the delegate registry")))?;
+        delegate.addresses.insert(chain_id, address);
+        Ok(())
+    }
+
+    pub fn lookup(&self, chain_id: u64, address: Address) -> Option<&ApprovedDelegate> {
+        self.delegates
+            .iter()
+            .find(|d| d.addresses.get(&chain_id) == Some(&address))
+    }
+
+    /// Checks `address` on `chain_id` against the registry and the code currently deployed.
+    pub async fn check<P: Provider>(
+        &self,
+        provider: &P,
+        chain_id: u64,
+        address: Address,
+    ) -> Result<Verdict> {
+        if address == Address::ZERO {
+            return Ok(Verdict::Revocation);
+        }
+        let Some(delegate) = self.lookup(chain_id, address) else {
+            return Ok(Verdict::Unknown { address });
+        };
+
+        let actual = keccak256(provider.get_code_at(address).await.map_err(Error::rpc)?);
+        if actual != delegate.codehash {
+            return Ok(Verdict::CodehashMismatch {
+                name: delegate.name.clone(),
+                address,
+                expected: delegate.codehash,
+                actual,
+            });
+        }
+        Ok(Verdict::Approved {
+            name: delegate.name.clone(),
+        })
+    }
+
+    /// Applies the policy to a verdict: errors with [`Error::InvalidAuthorization`] under
+    /// [`Policy::Enforce`], warns otherwise.
+    pub fn enforce(&self, verdict: &Verdict) -> Result<()> {
+        if matches!(verdict, Verdict::Approved { .. } | Verdict::Revocation) {
+            return Ok(());
+        }
+        match self.policy {
+            Policy::Enforce => Err(Error::InvalidAuthorization(format!(
+                "refusing to delegate: {verdict}"
+            ))),
+            Policy::Warn => {
+                eprintln!("WARNING: delegating to an unapproved target: {verdict}");
+                Ok(())
+            }
+        }
+    }
+
+    /// Signs an authorization after checking the delegate against the registry.
+    pub async fn sign_authorization<P: Provider>(
+        &self,
+        provider: &P,
+        signer: &PrivateKeySigner,
+        chain_id: u64,
+        address: Address,
+        nonce: u64,
+    ) -> Result<SignedAuthorization> {
+        self.enforce(&self.check(provider, chain_id, address).await?)?;
+        sign_unchecked(signer, chain_id, address, nonce)
+    }
+
+    /// Checks every authorization of a transaction before it is submitted.
+    pub async fn check_transaction<P: Provider>(
+        &self,
+        provider: &P,
+        tx: &TransactionRequest,
+    ) -> Result<()> {
+        for auth in tx.authorization_list.iter().flatten() {
+            // Chain id 0 makes the authorization valid on every chain, including this one.
+            let chain_id = if auth.chain_id.is_zero() {
+                provider.get_chain_id().await.map_err(Error::rpc)?
+            } else {
+                u64::try_from(auth.chain_id).map_err(|_| {
+                    Error::InvalidAuthorization(format!("chain id {} overflows u64", auth.chain_id))
+                })?
+            };
+            self.enforce(&self.check(provider, chain_id, auth.address).await?)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifacts::Artifact;
+
+    #[test]
+    fn pins_match_committed_artifacts() {
+        let registry = DelegateRegistry::from_path(DEFAULT_REGISTRY, Policy::Enforce).unwrap();
+        for delegate in &registry.delegates {
+            let artifact = Artifact::load(&delegate.name).unwrap();
+            assert_eq!(
+                keccak256(&artifact.deployed_bytecode.object),
+                delegate.codehash,
+                "{}",
+                delegate.name
+            );
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Readable revert reasons for failed calls, gas estimates and transactions.
+//!
+//! Revert data is decoded as one of the custom errors of the known ABIs (see
+//! [`AbiRegistry`]), `Error(string)` or `Panic(uint256)`, and returned to the caller as
+//! [`Error::Revert`].
+
+use std::fmt;
+
+use crate::{
+    decode::AbiRegistry,
+    error::{Error, Result},
+};
+use alloy::{
+    primitives::{Bytes, U256},
+    providers::Provider,
+    rpc::types::TransactionReceipt,
+    sol_types::PanicKind,
+    transports::{RpcError, TransportErrorKind},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Revert {
+    /// `Error(string)`, from `require(cond, "...")` and `revert("...")`.
+    Reason(String),
+    /// `Panic(uint256)`, from failed asserts, arithmetic errors and the like.
+    Panic(U256),
+    /// A custom error found in one of the known ABIs.
+    Custom { name: String, args: String },
+    /// Empty revert data or data matching nothing known.
+    Unknown(Bytes),
+}
+
+impl fmt::Display for Revert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reason(reason) => write!(f, "Error({reason:?})"),
+            Self::Panic(code) => {
+                let kind = u32::try_from(*code).ok().and_then(PanicKind::from_number);
+                match kind {
+                    Some(kind) => write!(f, "Panic({code:#x}: {kind})"),
+                    None => write!(f, "Panic({code:#x})"),
+                }
+            }
+            Self::Custom { name, args } => write!(f, "{name}({args})"),
+            Self::Unknown(data) if data.is_empty() => write!(f, "no revert data"),
+            Self::Unknown(data) => write!(f, "unknown error {data}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RevertError {
+    /// What reverted, e.g. `eth_call` or the transaction hash.
+    pub context: String,
+    pub revert: Revert,
+    pub data: Bytes,
+}
+
+impl fmt::Display for RevertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} reverted: {}", self.context, self.revert)
+    }
+}
+
+impl std::error::Error for RevertError {}
+
+impl RevertError {
+    pub fn new(abis: &AbiRegistry, context: impl Into<String>, data: Bytes) -> Self {
+        Self {
+            context: context.into(),
+            revert: abis.decode_revert(&data),
+            data,
+        }
+    }
+}
+
+/// Decodes the revert data of failed `eth_call`s and `eth_estimateGas`es.
+pub trait DecodeRevert<T> {
+    /// Turns an error carrying revert data into [`Error::Revert`]; other errors are converted
+    /// as usual.
+    fn decode_revert(self, abis: &AbiRegistry, context: &str) -> Result<T>;
+}
+
+impl<T> DecodeRevert<T> for Result<T, RpcError<TransportErrorKind>> {
+    fn decode_revert(self, abis: &AbiRegistry, context: &str) -> Result<T> {
+        self.map_err(|err| Error::from_rpc(err, abis, context))
+    }
+}
+
+impl<T> DecodeRevert<T> for Result<T, alloy::contract::Error> {
+    fn decode_revert(self, abis: &AbiRegistry, context: &str) -> Result<T> {
+        self.map_err(|err| match err.as_revert_data() {
+            Some(data) => RevertError::new(abis, context, data).into(),
+            None => err.into(),
+        })
+    }
+}
+
+/// Errors with the decoded revert reason if `receipt` is of a failed transaction.
+///
+/// Receipts carry no revert data, so the transaction is re-run with `eth_call` on top of its
+/// parent block. Transactions earlier in the same block are not replayed, so the reason can be
+/// missing if the failure depended on them.
+pub async fn check_receipt<P: Provider>(
+    provider: &P,
+    abis: &AbiRegistry,
+    receipt: &TransactionReceipt,
+) -> Result<()> {
+    if receipt.status() {
+        return Ok(());
+    }
+    let hash = receipt.transaction_hash;
+    let context = format!("transaction {hash}");
+    let tx = provider
+        .get_transaction_by_hash(hash)
+        .await
+        .map_err(Error::rpc)?
+        .ok_or_else(|| Error::UnexpectedResponse(format!("{context} not found")))?;
+    let parent = receipt
+        .block_number
+        .ok_or_else(|| Error::UnexpectedResponse("receipt without block number".into()))?
+        .saturating_sub(1);
+
+    provider
+        .call(tx.into_request())
+        .block(parent.into())
+        .await
+        .decode_revert(abis, &context)?;
+    Err(RevertError {
+        context,
+        revert: Revert::Unknown(Bytes::new()),
+        data: Bytes::new(),
+    }
+    .into())
+}
@@ -0,0 +1,207 @@
+//! Session keys for accounts delegated to `SessionKeyDelegate`.
+//!
+//! The owner issues a key with a [`SessionPolicy`]: the targets and selectors it may call, the
+//! total value it may send and an expiry. Batches signed by the key go through
+//! `executeWithSessionKey`. [`SessionPolicy::check`] applies the contract's rules off chain, so
+//! a batch outside the policy is refused before anyone pays gas for it.
+
+use alloy::{
+    primitives::{Address, B256, Bytes, FixedBytes, U256},
+    providers::Provider,
+    signers::local::PrivateKeySigner,
+};
+
+use crate::{
+    batch,
+    error::{Error, Result},
+    signed_batch,
+};
+
+pub use crate::signed_batch::{Call, SessionKeyDelegate};
+
+/// Tags the digest of the owner's batches and of the keys' batches alike.
+pub const BATCH_TAG: &str = "SessionKeyDelegate.batch";
+
+/// The selector of calls with less than four bytes of data, such as plain transfers.
+pub const NO_SELECTOR: FixedBytes<4> = FixedBytes::ZERO;
+
+/// What a session key may do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionPolicy {
+    pub key: Address,
+    /// Last block timestamp at which the key is accepted.
+    pub valid_until: u64,
+    /// Total value the key may send over its lifetime.
+    pub value_limit: U256,
+    pub targets: Vec<Address>,
+    /// Allowed selectors, [`NO_SELECTOR`] for calls without one.
+    pub selectors: Vec<FixedBytes<4>>,
+}
+
+impl SessionPolicy {
+    /// The call the owner makes on its own account to issue the key.
+    pub fn issue_call(&self) -> SessionKeyDelegate::addSessionKeyCall {
+        SessionKeyDelegate::addSessionKeyCall {
+            key: self.key,
+            validUntil: self.valid_until,
+            valueLimit: self.value_limit,
+            targets: self.targets.clone(),
+            selectors: self.selectors.clone(),
+        }
+    }
+
+    /// Checks `calls` the way `executeWithSessionKey` of `account` does in a block at `now`, with
+    /// `value_spent` already used up. Calls to `account` itself are refused even when it is one of
+    /// the targets, since they could issue keys or run owner batches.
+    pub fn check(
+        &self,
+        account: Address,
+        calls: &[Call],
+        value_spent: U256,
+        now: u64,
+    ) -> Result<()> {
+        if now > self.valid_until {
+            return Err(Error::Expired {
+                valid_until: self.valid_until,
+                now,
+            });
+        }
+        let mut remaining = self.value_limit.saturating_sub(value_spent);
+        for (i, call) in calls.iter().enumerate() {
+            if call.to == account || !self.targets.contains(&call.to) {
+                return Err(Error::NotPermitted(format!(
+                    "call #{i}: target {} is not allowed for {}",
+                    call.to, self.key
+                )));
+            }
+            let selector = selector(&call.data);
+            if !self.selectors.contains(&selector) {
+                return Err(Error::NotPermitted(format!(
+                    "call #{i}: selector {selector} is not allowed for {}",
+                    self.key
+                )));
+            }
+            remaining = remaining.checked_sub(call.value).ok_or_else(|| {
+                Error::NotPermitted(format!(
+                    "call #{i}: value {} exceeds the {remaining} wei left to {}",
+                    call.value, self.key
+                ))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// A session key as the account stores it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionKeyInfo {
+    pub policy: SessionPolicy,
+    pub value_spent: U256,
+}
+
+fn selector(data: &[u8]) -> FixedBytes<4> {
+    match data.get(..4) {
+        Some(selector) => FixedBytes::from_slice(selector),
+        None => NO_SELECTOR,
+    }
+}
+
+pub fn revoke_call(key: Address) -> SessionKeyDelegate::revokeSessionKeyCall {
+    SessionKeyDelegate::revokeSessionKeyCall { key }
+}
+
+/// `keccak256(abi.encode(BATCH, chainid, account, nonce, calls))`, signed by the owner and
+/// session keys alike.
+pub fn digest(chain_id: u64, account: Address, nonce: U256, calls: &[Call]) -> B256 {
+    signed_batch::digest(BATCH_TAG, chain_id, account, nonce, calls)
+}
+
+/// Signs a batch with a session key, for `executeWithSessionKey`.
+pub fn sign(
+    session_key: &PrivateKeySigner,
+    chain_id: u64,
+    account: Address,
+    nonce: U256,
+    calls: &[Call],
+) -> Result<Bytes> {
+    batch::sign(session_key, digest(chain_id, account, nonce, calls))
+}
+
+/// The session keys of `account` that have not been revoked, expired ones included.
+pub async fn list<P: Provider>(provider: &P, account: Address) -> Result<Vec<SessionKeyInfo>> {
+    let delegate = SessionKeyDelegate::new(account, provider);
+    let mut keys = Vec::new();
+    for key in delegate.sessionKeys().call().await? {
+        let session = delegate.getSessionKey(key).call().await?;
+        keys.push(SessionKeyInfo {
+            policy: SessionPolicy {
+                key,
+                valid_until: session.validUntil,
+                value_limit: session.valueLimit,
+                targets: session.targets,
+                selectors: session.selectors,
+            },
+            value_spent: session.valueSpent,
+        });
+    }
+    Ok(keys)
+}
+
+/// `SessionKeyDelegate`'s ABI. Its errors say which limit a key's batch broke.
+pub fn abi() -> alloy::json_abi::JsonAbi {
+    SessionKeyDelegate::abi::contract()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, bytes, fixed_bytes};
+
+    #[test]
+    fn checks_calls_against_the_policy() {
+        let token = address!("0x00000000000000000000000000000000000000d1");
+        let transfer = fixed_bytes!("0xa9059cbb");
+        let account = address!("0x00000000000000000000000000000000000000aa");
+        let policy = SessionPolicy {
+            key: address!("0x0000000000000000000000000000000000005e55"),
+            valid_until: 1_000,
+            value_limit: U256::from(10),
+            targets: vec![token, account],
+            selectors: vec![transfer, NO_SELECTOR],
+        };
+        let call = |to, value: u64, data: Bytes| Call {
+            to,
+            value: U256::from(value),
+            data,
+        };
+
+        let allowed = [
+            call(token, 0, bytes!("0xa9059cbb0000")),
+            call(token, 4, Bytes::new()),
+        ];
+        policy.check(account, &allowed, U256::ZERO, 1_000).unwrap();
+        assert!(matches!(
+            policy.check(account, &allowed, U256::ZERO, 1_001),
+            Err(Error::Expired { .. })
+        ));
+        assert!(matches!(
+            policy.check(account, &allowed, U256::from(7), 0),
+            Err(Error::NotPermitted(_))
+        ));
+
+        let other_target = [call(Address::ZERO, 0, Bytes::new())];
+        assert!(policy.check(account, &other_target, U256::ZERO, 0).is_err());
+        let other_selector = [call(token, 0, bytes!("0x095ea7b3"))];
+        assert!(
+            policy
+                .check(account, &other_selector, U256::ZERO, 0)
+                .is_err()
+        );
+        // The account is never a target, even when the policy lists it.
+        let to_account = [call(account, 0, Bytes::new())];
+        assert!(matches!(
+            policy.check(account, &to_account, U256::ZERO, 0),
+            Err(Error::NotPermitted(_))
+        ));
+    }
+}
@@ -0,0 +1,243 @@
+//! Batches signed off chain for the delegates that check their own signers: `SessionKeyDelegate`,
+//! `ThresholdDelegate`, `RecoveryDelegate` and `P256Delegate`.
+//!
+//! They all inherit `SignedBatchExecutor`, so their interfaces share one [`Call`] struct and a
+//! batch built for one of them is the same value for all. Each delegate tags its digest (see
+//! [`digest`]), so a signature made for one of them never passes on another, even when both hold
+//! the same key. The module for each delegate has its tag as `BATCH_TAG` and its ABI, with the
+//! custom errors it refuses batches with, as `abi()`.
+
+use alloy::{
+    primitives::{Address, B256, U256, keccak256},
+    sol,
+    sol_types::SolValue,
+};
+
+sol!(
+    #[allow(missing_docs)]
+    #[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    struct Call {
+        address to;
+        uint256 value;
+        bytes data;
+    }
+
+    #[allow(missing_docs)]
+    #[derive(Debug, PartialEq, Eq)]
+    struct WebAuthnAssertion {
+        bytes authenticatorData;
+        string clientDataJSON;
+        uint256 challengeIndex;
+        uint256 typeIndex;
+        bytes32 r;
+        bytes32 s;
+    }
+
+    #[allow(missing_docs)]
+    #[sol(rpc, abi)]
+    interface SessionKeyDelegate {
+        struct SessionKey {
+            uint64 validUntil;
+            uint256 valueLimit;
+            uint256 valueSpent;
+            address[] targets;
+            bytes4[] selectors;
+        }
+
+        event SessionKeyAdded(address indexed key, uint64 validUntil, uint256 valueLimit, address[] targets, bytes4[] selectors);
+        event SessionKeyRevoked(address indexed key);
+        event BatchExecuted(uint256 indexed nonce, address indexed signer);
+
+        error UnknownSessionKey(address key);
+        error SessionKeyExpired(address key, uint64 validUntil);
+        error TargetNotAllowed(address key, address target);
+        error SelectorNotAllowed(address key, bytes4 selector);
+        error ValueLimitExceeded(address key, uint256 value, uint256 remaining);
+
+        function BATCH() external view returns (bytes32);
+
+        function nonce() external view returns (uint256);
+
+        function addSessionKey(
+            address key,
+            uint64 validUntil,
+            uint256 valueLimit,
+            address[] calldata targets,
+            bytes4[] calldata selectors
+        ) external;
+
+        function revokeSessionKey(address key) external;
+
+        function sessionKeys() external view returns (address[] memory);
+
+        function getSessionKey(address key) external view returns (SessionKey memory);
+
+        function execute(Call[] calldata calls, bytes calldata signature) external payable;
+
+        function executeWithSessionKey(Call[] calldata calls, bytes calldata signature) external payable;
+    }
+
+    #[allow(missing_docs)]
+    #[sol(rpc, abi)]
+    interface ThresholdDelegate {
+        event OwnersChanged(address[] owners, uint256 threshold);
+        event BatchExecuted(uint256 indexed nonce, address[] signers);
+
+        error NotConfigured();
+        error NotEnoughSignatures(uint256 count, uint256 threshold);
+        error NotAnOwner(address signer);
+        error SignersNotAscending(address signer, address previous);
+
+        function BATCH() external view returns (bytes32);
+
+        function nonce() external view returns (uint256);
+
+        function threshold() external view returns (uint256);
+
+        function isOwner(address owner) external view returns (bool);
+
+        function owners() external view returns (address[] memory);
+
+        function setOwners(address[] calldata owners, uint256 threshold) external;
+
+        function hashBatch(Call[] calldata calls) external view returns (bytes32);
+
+        function execute(Call[] calldata calls, bytes[] calldata signatures) external payable;
+    }
+
+    #[allow(missing_docs)]
+    #[sol(rpc, abi)]
+    interface RecoveryDelegate {
+        event GuardiansChanged(address[] guardians, uint256 threshold, uint64 delay);
+        event RecoveryInitiated(address indexed newSigner, uint64 executeAfter);
+        event RecoveryCancelled(address indexed newSigner);
+        event RecoveryFinalized(address indexed oldSigner, address indexed newSigner);
+        event BatchExecuted(uint256 indexed nonce, address indexed signer);
+
+        error NoGuardians();
+        error NotEnoughApprovals(uint256 count, uint256 threshold);
+        error NotAGuardian(address signer);
+        error GuardiansNotAscending(address signer, address previous);
+        error RecoveryPending(address newSigner);
+        error NoRecoveryPending();
+        error RecoveryTimelocked(uint64 executeAfter);
+
+        function RECOVERY() external view returns (bytes32);
+
+        function BATCH() external view returns (bytes32);
+
+        function MIN_RECOVERY_DELAY() external view returns (uint64);
+
+        function nonce() external view returns (uint256);
+
+        function recoveryNonce() external view returns (uint256);
+
+        function signer() external view returns (address);
+
+        function guardians() external view returns (address[] memory);
+
+        function guardianThreshold() external view returns (uint256);
+
+        function recoveryDelay() external view returns (uint64);
+
+        function pendingSigner() external view returns (address);
+
+        function recoveryExecuteAfter() external view returns (uint64);
+
+        function setGuardians(address[] calldata guardians, uint256 threshold, uint64 delay) external;
+
+        function hashRecovery(address newSigner) external view returns (bytes32);
+
+        function initiateRecovery(address newSigner, bytes[] calldata approvals) external;
+
+        function cancelRecovery() external;
+
+        function finalizeRecovery() external;
+
+        function execute(Call[] calldata calls, bytes calldata signature) external payable;
+    }
+
+    #[allow(missing_docs)]
+    #[sol(rpc, abi)]
+    interface P256Delegate {
+        event PublicKeySet(bytes32 x, bytes32 y);
+        event BatchExecuted(uint256 indexed nonce);
+
+        error NoPublicKey();
+        error InvalidP256Signature();
+        error InvalidClientData();
+        error UserNotPresent();
+
+        function BATCH() external view returns (bytes32);
+
+        function nonce() external view returns (uint256);
+
+        function publicKeyX() external view returns (bytes32);
+
+        function publicKeyY() external view returns (bytes32);
+
+        function setPublicKey(bytes32 x, bytes32 y) external;
+
+        function hashBatch(Call[] calldata calls) external view returns (bytes32);
+
+        function execute(Call[] calldata calls, bytes32 r, bytes32 s) external payable;
+
+        function executeWithPasskey(Call[] calldata calls, WebAuthnAssertion calldata assertion) external payable;
+    }
+);
+
+/// `keccak256(abi.encode(keccak256(tag), chainid, account, nonce, calls))`, the digest a delegate
+/// tagged with `tag` checks the signature of a batch against.
+pub fn digest(tag: &str, chain_id: u64, account: Address, nonce: U256, calls: &[Call]) -> B256 {
+    keccak256(
+        (
+            keccak256(tag),
+            U256::from(chain_id),
+            account,
+            nonce,
+            calls.to_vec(),
+        )
+            .abi_encode_params(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_separate_delegates() {
+        let account = Address::with_last_byte(0xaa);
+        let calls = [Call {
+            to: Address::with_last_byte(0xd1),
+            value: U256::from(1),
+            data: Default::default(),
+        }];
+        let tagged = digest(
+            "SessionKeyDelegate.batch",
+            31337,
+            account,
+            U256::ZERO,
+            &calls,
+        );
+        // The tag is the first word of what is hashed.
+        let mut encoded = (
+            keccak256("SessionKeyDelegate.batch"),
+            U256::from(31337),
+            account,
+            U256::ZERO,
+            calls.to_vec(),
+        )
+            .abi_encode_params();
+        assert_eq!(tagged, keccak256(&encoded));
+        encoded[..32].copy_from_slice(keccak256("RecoveryDelegate.batch").as_slice());
+        assert_eq!(
+            digest("RecoveryDelegate.batch", 31337, account, U256::ZERO, &calls),
+            keccak256(&encoded)
+        );
+        assert_ne!(
+            tagged,
+            digest("P256Delegate.batch", 31337, account, U256::ZERO, &calls)
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Off-chain signatures for `SimpleTransfer.transferWithSig`.
+//!
+//! The payer signs `keccak256(abi.encodePacked(from, to, amount, nonce, address(this)))` as an
+//! EIP-191 personal message and anyone, typically a sponsor, submits it. The contract recovers
+//! the signer with OpenZeppelin's `ECDSA.recover` and requires it to be `from`.
+//!
+//! `SponsoredTransferHardened` instead requires the delegated account's own signature over
+//! `keccak256(abi.encode(block.chainid, address(this), nonce, to, amount))`, see
+//! [`SponsoredIntent`].
+
+use alloy::{
+    primitives::{Address, B256, Bytes, Signature, U256, keccak256},
+    signers::{SignerSync, local::PrivateKeySigner},
+    sol_types::SolValue,
+};
+
+use crate::{
+    contracts::{SimpleTransfer, SponsoredTransferHardened},
+    error::{Error, Result},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferIntent {
+    pub from: Address,
+    pub to: Address,
+    pub amount: U256,
+    pub nonce: U256,
+    /// `address(this)` during the call: the `SimpleTransfer` deployment, or the payer's own
+    /// account when it is delegated to `SimpleTransfer`.
+    pub contract: Address,
+}
+
+impl TransferIntent {
+    /// The digest hashed by the contract, before the EIP-191 prefix is applied.
+    pub fn digest(&self) -> B256 {
+        keccak256((self.from, self.to, self.amount, self.nonce, self.contract).abi_encode_packed())
+    }
+
+    /// Signs the digest as a personal message; the result is `r ‖ s ‖ v` with `v` in {27, 28}.
+    pub fn sign(&self, signer: &PrivateKeySigner) -> Result<Bytes> {
+        let signature = signer.sign_message_sync(self.digest().as_slice())?;
+        Ok(signature.as_bytes().into())
+    }
+
+    /// Recovers the signer the way the contract does.
+    pub fn recover(&self, signature: &[u8]) -> Result<Address> {
+        Signature::from_raw(signature)
+            .and_then(|sig| sig.recover_address_from_msg(self.digest()))
+            .map_err(|err| Error::Signing(Box::new(err)))
+    }
+
+    pub fn call(&self, signature: Bytes) -> SimpleTransfer::transferWithSigCall {
+        SimpleTransfer::transferWithSigCall {
+            from_alice: self.from,
+            to_bob: self.to,
+            amount: self.amount,
+            nonce: self.nonce,
+            signature,
+        }
+    }
+}
+
+/// A transfer out of an account delegated to `SponsoredTransferHardened`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SponsoredIntent {
+    pub chain_id: u64,
+    pub account: Address,
+    /// The account's current `SponsoredTransferHardened.nonce()`.
+    pub nonce: U256,
+    pub to: Address,
+    pub amount: U256,
+}
+
+impl SponsoredIntent {
+    pub fn digest(&self) -> B256 {
+        keccak256(
+            (
+                U256::from(self.chain_id),
+                self.account,
+                self.nonce,
+                self.to,
+                self.amount,
+            )
+                .abi_encode(),
+        )
+    }
+
+    /// Signs the digest as a personal message with the account's key.
+    pub fn sign(&self, signer: &PrivateKeySigner) -> Result<Bytes> {
+        let signature = signer.sign_message_sync(self.digest().as_slice())?;
+        Ok(signature.as_bytes().into())
+    }
+
+    pub fn call(&self, signature: Bytes) -> SponsoredTransferHardened::transferCall {
+        SponsoredTransferHardened::transferCall {
+            to: self.to,
+            amount: self.amount,
+            signature,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, b256, bytes};
+
+    /// Same vector as `test_VerifyProvidedSignature` in the Forge tests of `SimpleTransfer`.
+    #[test]
+    fn matches_forge_vector() {
+        let alice = PrivateKeySigner::from_bytes(&b256!(
+            "0xd2eb31e7ec97467f3e382903851bced12ba44fc230d93cdddcfd7726c94a2f6e"
+        ))
+        .unwrap();
+        let intent = TransferIntent {
+            from: alice.address(),
+            to: address!("0x87D20bDC94DCB9bF27cCf59E2B7be7C5afBac84b"),
+            amount: U256::from(100_000_000_000_000u64),
+            nonce: U256::from(1),
+            contract: address!("0xA773b4CAfe39cf46e524F0f06e3Bd6C7eB396eba"),
+        };
+
+        let signature = intent.sign(&alice).unwrap();
+        assert_eq!(
+            signature,
+            bytes!(
+                "0x37235b5a4ca2938d6e8c22e137fbf2bc4c8909bbc9f4b4097848914129e4828e6f56cfbd4994b11551f328d49190cf77af99be28b98dfc7489637b5b8ddf227c1c"
+            )
+        );
+        assert_eq!(intent.recover(&signature).unwrap(), alice.address());
+
+        let tampered = TransferIntent {
+            amount: intent.amount + U256::from(1),
+            ..intent
+        };
+        assert_ne!(tampered.recover(&signature).unwrap(), alice.address());
+    }
+}
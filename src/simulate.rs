@@ -0,0 +1,307 @@
+//! Dry runs of multi-transaction flows, including type-4 transactions.
+//!
+//! A flow such as delegate → call → revoke is sent as a single `eth_simulateV1` block. Nodes
+//! without that method fall back to replaying the steps on Anvil between a snapshot and a
+//! revert, so nothing is left on chain either way.
+
+use alloy::{
+    network::TransactionBuilder,
+    primitives::{Address, Bytes, Log, U256, address, bytes},
+    providers::{Provider, ext::AnvilApi},
+    rpc::types::{
+        TransactionRequest,
+        simulate::{SimBlock, SimulatePayload},
+        state::StateOverridesBuilder,
+    },
+    sol_types::{SolType, sol_data},
+    transports::{RpcError, TransportErrorKind},
+};
+
+use crate::error::{Error, Rejection, Result};
+
+/// Where the state reader is installed for the simulation.
+pub(crate) const READER: Address = address!("0x0000000000000000000000000000000000007702");
+
+/// Returns `abi.encode(balance, code)` of the address in the first calldata word.
+/// EXTCODECOPY on a delegated account copies its `0xef0100 ‖ delegate` designator.
+pub(crate) const READER_CODE: Bytes =
+    bytes!("5f3580315f526040602052803b80604052805f6060843c601f01601f19166060015ff3");
+
+/// Gas limit for steps that do not set one, so that reverting steps still execute.
+pub(crate) const DEFAULT_GAS: u64 = 3_000_000;
+
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub label: String,
+    /// Must set `from`; the sender is not asked to sign.
+    pub tx: TransactionRequest,
+}
+
+impl Step {
+    pub fn new(label: impl Into<String>, tx: TransactionRequest) -> Self {
+        Self {
+            label: label.into(),
+            tx,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    pub label: String,
+    pub success: bool,
+    pub output: Bytes,
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountState {
+    pub address: Address,
+    pub code: Bytes,
+    pub balance: U256,
+    /// Not observable inside `eth_simulateV1`, only set by the Anvil replay.
+    pub nonce: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    SimulateV1,
+    AnvilReplay,
+}
+
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub backend: Backend,
+    pub steps: Vec<StepResult>,
+    pub final_state: Vec<AccountState>,
+}
+
+pub(crate) fn reader_call(account: Address) -> TransactionRequest {
+    TransactionRequest::default()
+        .with_to(READER)
+        .with_input(Bytes::copy_from_slice(account.into_word().as_slice()))
+}
+
+/// Decodes the `(balance, code)` returned by a [`reader_call`].
+pub(crate) fn decode_reader(output: &[u8]) -> Result<(U256, Bytes)> {
+    Ok(<(sol_data::Uint<256>, sol_data::Bytes)>::abi_decode_params(
+        output,
+    )?)
+}
+
+/// Simulates `steps` in order and reports the final state of `accounts`.
+///
+/// Uses `eth_simulateV1` and falls back to [`replay`] if the node does not know the method.
+pub async fn simulate<P: Provider>(
+    provider: &P,
+    steps: &[Step],
+    accounts: &[Address],
+) -> Result<Simulation> {
+    let mut calls: Vec<TransactionRequest> = steps
+        .iter()
+        .map(|step| {
+            let mut tx = step.tx.clone();
+            tx.gas.get_or_insert(DEFAULT_GAS);
+            tx
+        })
+        .collect();
+    calls.extend(accounts.iter().map(|a| reader_call(*a)));
+
+    let payload = SimulatePayload {
+        block_state_calls: vec![SimBlock {
+            block_overrides: None,
+            state_overrides: Some(
+                StateOverridesBuilder::default()
+                    .with_code(READER, READER_CODE)
+                    .build(),
+            ),
+            calls,
+        }],
+        trace_transfers: false,
+        validation: false,
+        return_full_transactions: false,
+    };
+
+    let blocks = match provider.simulate(&payload).await {
+        Ok(blocks) => blocks,
+        Err(err) if is_unsupported(&err) => return replay(provider, steps, accounts).await,
+        Err(err) => return Err(Error::rpc(err)),
+    };
+    let results = blocks
+        .into_iter()
+        .next()
+        .map(|block| block.calls)
+        .unwrap_or_default();
+    if results.len() != steps.len() + accounts.len() {
+        return Err(Error::UnexpectedResponse(format!(
+            "eth_simulateV1 returned {} results for {} calls",
+            results.len(),
+            steps.len() + accounts.len()
+        )));
+    }
+    let (step_results, reads) = results.split_at(steps.len());
+
+    let steps = steps
+        .iter()
+        .zip(step_results)
+        .map(|(step, result)| StepResult {
+            label: step.label.clone(),
+            success: result.status,
+            output: result.return_data.clone(),
+            gas_used: result.gas_used,
+            logs: result.logs.iter().map(|l| l.inner.clone()).collect(),
+            error: result.error.as_ref().map(|e| e.message.clone()),
+        })
+        .collect();
+
+    let final_state = accounts
+        .iter()
+        .zip(reads)
+        .map(|(address, read)| {
+            let (balance, code) = decode_reader(&read.return_data)?;
+            Ok(AccountState {
+                address: *address,
+                code,
+                balance,
+                nonce: None,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Simulation {
+        backend: Backend::SimulateV1,
+        steps,
+        final_state,
+    })
+}
+
+/// Whether the node does not know `eth_simulateV1`, as opposed to failing to simulate.
+fn is_unsupported(err: &RpcError<TransportErrorKind>) -> bool {
+    err.as_error_resp().is_some_and(|resp| {
+        Rejection::classify(resp.code, &resp.message) == Rejection::MethodNotFound
+    })
+}
+
+/// Replays `steps` on Anvil with impersonated senders and reverts to the starting state.
+///
+/// Return data is taken from an `eth_call` of each step right before it is sent. `provider`
+/// must not carry a wallet, since the senders are impersonated.
+pub async fn replay<P: Provider>(
+    provider: &P,
+    steps: &[Step],
+    accounts: &[Address],
+) -> Result<Simulation> {
+    let snapshot = provider.anvil_snapshot().await.map_err(Error::rpc)?;
+    let result = replay_steps(provider, steps, accounts).await;
+    provider.anvil_revert(snapshot).await.map_err(Error::rpc)?;
+    result
+}
+
+async fn replay_steps<P: Provider>(
+    provider: &P,
+    steps: &[Step],
+    accounts: &[Address],
+) -> Result<Simulation> {
+    let mut results = Vec::with_capacity(steps.len());
+    for step in steps {
+        let mut tx = step.tx.clone();
+        tx.gas.get_or_insert(DEFAULT_GAS);
+        let from = tx
+            .from
+            .ok_or_else(|| Error::Config(format!("step {} has no sender", step.label)))?;
+
+        let (output, error) = match provider.call(tx.clone()).await {
+            Ok(output) => (output, None),
+            Err(err) => (
+                err.as_error_resp()
+                    .and_then(|e| e.as_revert_data())
+                    .unwrap_or_default(),
+                Some(err.to_string()),
+            ),
+        };
+
+        provider
+            .anvil_impersonate_account(from)
+            .await
+            .map_err(Error::rpc)?;
+        // Reverting the snapshot keeps the impersonation, so stop it even if the step failed.
+        let sent: Result<_> = async {
+            Ok(provider
+                .send_transaction(tx)
+                .await
+                .map_err(Error::rpc)?
+                .get_receipt()
+                .await?)
+        }
+        .await;
+        provider
+            .anvil_stop_impersonating_account(from)
+            .await
+            .map_err(Error::rpc)?;
+        let receipt = sent?;
+
+        results.push(StepResult {
+            label: step.label.clone(),
+            success: receipt.status(),
+            output,
+            gas_used: receipt.gas_used,
+            logs: receipt
+                .inner
+                .logs()
+                .iter()
+                .map(|l| l.inner.clone())
+                .collect(),
+            error: if receipt.status() { None } else { error },
+        });
+    }
+
+    let mut final_state = Vec::with_capacity(accounts.len());
+    for address in accounts {
+        final_state.push(AccountState {
+            address: *address,
+            code: provider.get_code_at(*address).await.map_err(Error::rpc)?,
+            balance: provider.get_balance(*address).await.map_err(Error::rpc)?,
+            nonce: Some(
+                provider
+                    .get_transaction_count(*address)
+                    .await
+                    .map_err(Error::rpc)?,
+            ),
+        });
+    }
+
+    Ok(Simulation {
+        backend: Backend::AnvilReplay,
+        steps: results,
+        final_state,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::rpc::json_rpc::ErrorPayload;
+
+    fn error(code: i64, message: &str) -> RpcError<TransportErrorKind> {
+        RpcError::ErrorResp(ErrorPayload {
+            code,
+            message: message.to_string().into(),
+            data: None,
+        })
+    }
+
+    #[test]
+    fn only_missing_methods_are_unsupported() {
+        assert!(is_unsupported(&error(-32601, "Method not found")));
+        assert!(is_unsupported(&error(
+            -32000,
+            "the method eth_simulateV1 does not exist/is not available"
+        )));
+        assert!(!is_unsupported(&error(-32000, "header not found")));
+        assert!(!is_unsupported(&error(-32000, "block not found")));
+        assert!(!is_unsupported(&error(-32000, "account does not exist")));
+        assert!(!is_unsupported(&RpcError::NullResp));
+    }
+}
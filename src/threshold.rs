@@ -0,0 +1,256 @@
+//! M-of-N owner control of accounts delegated to `ThresholdDelegate`.
+//!
+//! A [`ThresholdBatch`] is written to a file and handed to the owners, each of whom signs it
+//! offline into a [`PartialSignature`] file. [`aggregate`] checks the partial signatures against
+//! the batch and the owner set and orders them by signer, as `execute` requires, so that any
+//! sponsor can submit the batch once enough owners have signed.
+
+use std::path::Path;
+
+use alloy::{
+    primitives::{Address, B256, Bytes, Signature, U256},
+    providers::Provider,
+    signers::local::PrivateKeySigner,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    batch,
+    error::{Error, Result},
+    signed_batch,
+};
+
+pub use crate::signed_batch::{Call, ThresholdDelegate};
+
+/// Tags the digest owners sign, see [`ThresholdBatch::digest`].
+pub const BATCH_TAG: &str = "ThresholdDelegate.batch";
+
+/// A batch for one account at one nonce, as passed around between owners.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThresholdBatch {
+    pub chain_id: u64,
+    pub account: Address,
+    pub nonce: U256,
+    pub calls: Vec<Call>,
+}
+
+impl ThresholdBatch {
+    /// `keccak256(abi.encode(BATCH, chainid, account, nonce, calls))`, as `hashBatch` computes
+    /// it.
+    pub fn digest(&self) -> B256 {
+        signed_batch::digest(
+            BATCH_TAG,
+            self.chain_id,
+            self.account,
+            self.nonce,
+            &self.calls,
+        )
+    }
+
+    pub fn sign(&self, owner: &PrivateKeySigner) -> Result<PartialSignature> {
+        PartialSignature::sign(owner, self.digest())
+    }
+
+    pub fn call(&self, signatures: Vec<Bytes>) -> ThresholdDelegate::executeCall {
+        ThresholdDelegate::executeCall {
+            calls: self.calls.clone(),
+            signatures,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        load(path.as_ref())
+    }
+}
+
+/// One owner's signature of a batch, or any other signer's of a digest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialSignature {
+    /// The digest signed, so signatures of another batch are caught early.
+    pub digest: B256,
+    pub signer: Address,
+    pub signature: Bytes,
+}
+
+impl PartialSignature {
+    /// Signs `digest` as an EIP-191 message.
+    pub fn sign(signer: &PrivateKeySigner, digest: B256) -> Result<Self> {
+        Ok(Self {
+            digest,
+            signer: signer.address(),
+            signature: batch::sign(signer, digest)?,
+        })
+    }
+
+    /// Checks that the signature is `signer`'s over `digest`.
+    pub fn verify(&self, digest: B256) -> Result<()> {
+        if self.digest != digest {
+            return Err(Error::InvalidSignature(format!(
+                "{} signed {}, expected {digest}",
+                self.signer, self.digest
+            )));
+        }
+        let recovered = Signature::from_raw(&self.signature)
+            .and_then(|sig| sig.recover_address_from_msg(digest))
+            .map_err(|err| Error::InvalidSignature(err.to_string()))?;
+        if recovered != self.signer {
+            return Err(Error::InvalidSignature(format!(
+                "recovered {recovered}, claimed {}",
+                self.signer
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        load(path.as_ref())
+    }
+}
+
+fn load<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let path = path.to_path_buf();
+    let raw = match std::fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(source) => return Err(Error::Io { path, source }),
+    };
+    serde_json::from_str(&raw).map_err(|source| Error::Json { path, source })
+}
+
+/// The owner set of an account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Owners {
+    pub owners: Vec<Address>,
+    /// Zero while no owners are set.
+    pub threshold: usize,
+}
+
+impl Owners {
+    pub fn set_call(&self) -> ThresholdDelegate::setOwnersCall {
+        ThresholdDelegate::setOwnersCall {
+            owners: self.owners.clone(),
+            threshold: U256::from(self.threshold),
+        }
+    }
+}
+
+pub async fn owners<P: Provider>(provider: &P, account: Address) -> Result<Owners> {
+    let delegate = ThresholdDelegate::new(account, provider);
+    let owners = delegate.owners().call().await?;
+    let threshold = delegate.threshold().call().await?;
+    Ok(Owners {
+        owners,
+        threshold: threshold.saturating_to(),
+    })
+}
+
+/// Verifies `partials` against `batch` and returns the signatures of distinct owners in
+/// ascending signer order, the order `execute` checks them in.
+pub fn aggregate(
+    batch: &ThresholdBatch,
+    partials: &[PartialSignature],
+    owners: &Owners,
+) -> Result<Vec<Bytes>> {
+    order_signatures(batch.digest(), partials, &owners.owners, owners.threshold)
+}
+
+/// Verifies that `partials` are signatures over `digest` by at least `threshold` distinct
+/// members of `signers` and returns them in ascending signer order.
+///
+/// Signatures of non-members, of another digest and duplicates are errors rather than skipped,
+/// so a mix-up in the collected files does not go unnoticed.
+pub fn order_signatures(
+    digest: B256,
+    partials: &[PartialSignature],
+    signers: &[Address],
+    threshold: usize,
+) -> Result<Vec<Bytes>> {
+    let mut signed: Vec<&PartialSignature> = Vec::with_capacity(partials.len());
+    for partial in partials {
+        partial.verify(digest)?;
+        if !signers.contains(&partial.signer) {
+            return Err(Error::NotPermitted(format!(
+                "{} is not one of the {} allowed signers",
+                partial.signer,
+                signers.len()
+            )));
+        }
+        if signed.iter().any(|other| other.signer == partial.signer) {
+            return Err(Error::InvalidSignature(format!(
+                "{} signed more than once",
+                partial.signer
+            )));
+        }
+        signed.push(partial);
+    }
+    if threshold == 0 || signed.len() < threshold {
+        return Err(Error::InvalidSignature(format!(
+            "{} of {threshold} required signatures",
+            signed.len()
+        )));
+    }
+    signed.sort_by_key(|partial| partial.signer);
+    Ok(signed
+        .into_iter()
+        .map(|partial| partial.signature.clone())
+        .collect())
+}
+
+/// `ThresholdDelegate`'s ABI, whose errors tell too few signatures from unknown or misordered
+/// owners.
+pub fn abi() -> alloy::json_abi::JsonAbi {
+    ThresholdDelegate::abi::contract()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregates_in_signer_order() {
+        let keys: Vec<PrivateKeySigner> = (1..=4u8)
+            .map(|i| PrivateKeySigner::from_bytes(&B256::with_last_byte(i)).unwrap())
+            .collect();
+        let owners = Owners {
+            owners: keys[..3].iter().map(|key| key.address()).collect(),
+            threshold: 2,
+        };
+        let batch = ThresholdBatch {
+            chain_id: 31337,
+            account: Address::with_last_byte(0xaa),
+            nonce: U256::from(3),
+            calls: vec![Call {
+                to: Address::with_last_byte(0xd0),
+                value: U256::from(1),
+                data: Bytes::new(),
+            }],
+        };
+        let partials: Vec<PartialSignature> =
+            keys.iter().map(|key| batch.sign(key).unwrap()).collect();
+
+        let json = serde_json::to_string(&batch).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ThresholdBatch>(&json).unwrap(),
+            batch
+        );
+
+        let signatures = aggregate(&batch, &partials[..2], &owners).unwrap();
+        let mut expected = partials[..2].to_vec();
+        expected.sort_by_key(|partial| partial.signer);
+        assert_eq!(signatures[0], expected[0].signature);
+        assert_eq!(signatures[1], expected[1].signature);
+
+        assert!(aggregate(&batch, &partials[..1], &owners).is_err());
+        assert!(matches!(
+            aggregate(&batch, &partials[2..], &owners),
+            Err(Error::NotPermitted(_))
+        ));
+        let duplicate = [partials[0].clone(), partials[0].clone()];
+        assert!(aggregate(&batch, &duplicate, &owners).is_err());
+        let next = ThresholdBatch {
+            nonce: U256::from(4),
+            ..batch.clone()
+        };
+        assert!(aggregate(&next, &partials[..2], &owners).is_err());
+    }
+}
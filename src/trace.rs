@@ -0,0 +1,289 @@
+//! Call trees of mined transactions from `debug_traceTransaction`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+};
+
+use crate::{
+    authorization::delegated_to,
+    decode::AbiRegistry,
+    error::{Error, Result},
+};
+use alloy::{
+    consensus::Transaction,
+    eips::eip7702::SignedAuthorization,
+    primitives::{Address, TxHash, U256, utils::format_ether},
+    providers::{Provider, ext::DebugApi},
+    rpc::types::trace::geth::{
+        AccountState, CallConfig, CallFrame, GethDebugTracingOptions, PreStateConfig,
+        PreStateFrame, PreStateMode,
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct CallTree {
+    pub root: CallFrame,
+    /// Delegated EOAs touched by the transaction and the delegate whose code they ran.
+    pub delegations: HashMap<Address, Address>,
+}
+
+/// Traces `hash` with the call tracer and resolves which callees were delegated EOAs.
+///
+/// Delegations set by the transaction's own authorization list take precedence, as far as the
+/// EVM applied them; other accounts are looked up in the state right before the transaction,
+/// from the prestate tracer.
+pub async fn call_tree<P: Provider>(provider: &P, hash: TxHash) -> Result<CallTree> {
+    let tx = provider
+        .get_transaction_by_hash(hash)
+        .await
+        .map_err(Error::rpc)?
+        .ok_or_else(|| Error::UnexpectedResponse(format!("transaction {hash} not found")))?;
+    if tx.block_number.is_none() {
+        return Err(Error::UnexpectedResponse(format!(
+            "transaction {hash} is still pending"
+        )));
+    }
+
+    let root = provider
+        .debug_trace_transaction(
+            hash,
+            GethDebugTracingOptions::call_tracer(CallConfig::default().with_log()),
+        )
+        .await
+        .map_err(Error::rpc)?
+        .try_into_call_frame()?;
+
+    // Earlier transactions in the block may have changed delegations, and so may later ones,
+    // so the state at the end of the block would not do.
+    let prestate = match provider
+        .debug_trace_transaction(
+            hash,
+            GethDebugTracingOptions::prestate_tracer(PreStateConfig::default()),
+        )
+        .await
+        .map_err(Error::rpc)?
+        .try_into_pre_state_frame()?
+    {
+        PreStateFrame::Default(PreStateMode(accounts)) => accounts,
+        PreStateFrame::Diff(diff) => diff.pre,
+    };
+
+    let chain_id = provider.get_chain_id().await.map_err(Error::rpc)?;
+    let authorized = applied_authorizations(
+        tx.inner.authorization_list().unwrap_or_default(),
+        chain_id,
+        tx.inner.signer(),
+        &prestate,
+    );
+
+    let mut delegations = HashMap::new();
+    let mut callees = Vec::new();
+    collect_callees(&root, &mut callees);
+    for callee in callees {
+        if delegations.contains_key(&callee) {
+            continue;
+        }
+        let delegate = match authorized.get(&callee) {
+            Some(delegate) => Some(*delegate).filter(|d| !d.is_zero()),
+            None => {
+                let account = prestate.get(&callee).ok_or_else(|| {
+                    Error::UnexpectedResponse(format!(
+                        "the prestate of {hash} does not include the callee {callee}"
+                    ))
+                })?;
+                account.code.as_ref().and_then(|code| delegated_to(code))
+            }
+        };
+        if let Some(delegate) = delegate {
+            delegations.insert(callee, delegate);
+        }
+    }
+
+    Ok(CallTree { root, delegations })
+}
+
+/// The delegation each authority ends up with after the EVM processed `authorizations`.
+///
+/// Like the EVM, this skips authorizations for another chain, with an unrecoverable signature,
+/// for an authority that holds code other than a delegation, or whose nonce does not match. The
+/// sender's nonce is bumped before the list is processed, and every applied authorization bumps
+/// its authority's nonce. Accounts missing from `prestate` did not exist.
+pub fn applied_authorizations(
+    authorizations: &[SignedAuthorization],
+    chain_id: u64,
+    sender: Address,
+    prestate: &BTreeMap<Address, AccountState>,
+) -> HashMap<Address, Address> {
+    let mut nonces = HashMap::new();
+    let mut applied = HashMap::new();
+    for auth in authorizations {
+        if !auth.chain_id.is_zero() && auth.chain_id != U256::from(chain_id) {
+            continue;
+        }
+        let Ok(authority) = auth.recover_authority() else {
+            continue;
+        };
+        let account = prestate.get(&authority);
+        let code = account.and_then(|account| account.code.as_ref());
+        if code.is_some_and(|code| !code.is_empty() && delegated_to(code).is_none()) {
+            continue;
+        }
+        let nonce = nonces.entry(authority).or_insert_with(|| {
+            let nonce = account
+                .and_then(|account| account.nonce)
+                .unwrap_or_default();
+            nonce + u64::from(authority == sender)
+        });
+        if auth.nonce != *nonce {
+            continue;
+        }
+        *nonce += 1;
+        applied.insert(authority, auth.address);
+    }
+    applied
+}
+
+fn collect_callees(frame: &CallFrame, out: &mut Vec<Address>) {
+    if let Some(to) = frame.to {
+        out.push(to);
+    }
+    for call in &frame.calls {
+        collect_callees(call, out);
+    }
+}
+
+impl CallTree {
+    /// Renders the tree with one frame per line, nested calls indented.
+    pub fn render(&self, abis: &AbiRegistry) -> String {
+        let mut out = String::new();
+        self.render_frame(&self.root, 0, abis, &mut out);
+        out
+    }
+
+    fn render_frame(&self, frame: &CallFrame, depth: usize, abis: &AbiRegistry, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        let to = frame
+            .to
+            .map_or_else(|| "?".to_string(), |to| to.to_string());
+        let _ = write!(out, "{indent}{} {} -> {to}", frame.typ, frame.from);
+
+        // A DELEGATECALL runs the callee's code in the caller's context already.
+        if let Some(delegate) = frame.to.and_then(|to| self.delegations.get(&to))
+            && frame.typ != "DELEGATECALL"
+        {
+            let _ = write!(out, " [EOA delegated to {delegate}]");
+        }
+
+        let call = if frame.typ.starts_with("CREATE") {
+            format!("create ({} bytes)", frame.input.len())
+        } else if frame.input.is_empty() {
+            "receive()".into()
+        } else {
+            abis.decode_call(&frame.input)
+                .unwrap_or_else(|| format!("{}", frame.input))
+        };
+        let _ = write!(out, " {call}");
+
+        if let Some(value) = frame.value.filter(|v| !v.is_zero()) {
+            let _ = write!(out, " value {} ETH", format_ether(value));
+        }
+        let _ = writeln!(out, " gas {}/{}", frame.gas_used, frame.gas);
+
+        if let Some(error) = &frame.error {
+            let reason = frame
+                .output
+                .as_ref()
+                .filter(|data| !data.is_empty())
+                .map(|data| abis.decode_revert(data).to_string())
+                .or_else(|| frame.revert_reason.clone());
+            match reason {
+                Some(reason) => {
+                    let _ = writeln!(out, "{indent}  ! {error}: {reason}");
+                }
+                None => {
+                    let _ = writeln!(out, "{indent}  ! {error}");
+                }
+            }
+        }
+
+        for call in &frame.calls {
+            self.render_frame(call, depth + 1, abis, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authorization::{DELEGATION_PREFIX, sign_unchecked};
+    use alloy::{
+        primitives::{B256, Bytes},
+        signers::local::PrivateKeySigner,
+    };
+
+    fn account(nonce: u64, code: &[u8]) -> AccountState {
+        AccountState::from_account_info(nonce, U256::ZERO, Some(Bytes::copy_from_slice(code)))
+    }
+
+    #[test]
+    fn skips_authorizations_the_evm_skips() {
+        let alice = PrivateKeySigner::from_bytes(&B256::with_last_byte(1)).unwrap();
+        let bob = PrivateKeySigner::from_bytes(&B256::with_last_byte(2)).unwrap();
+        let carol = PrivateKeySigner::from_bytes(&B256::with_last_byte(3)).unwrap();
+        let (first, second) = (Address::with_last_byte(0xd1), Address::with_last_byte(0xd2));
+        let delegated = [&DELEGATION_PREFIX[..], first.as_slice()].concat();
+        let prestate = BTreeMap::from([
+            (alice.address(), account(3, &delegated)),
+            (bob.address(), account(5, &[])),
+            (carol.address(), account(0, &[0x60, 0x00])),
+        ]);
+        let applied = |auths: &[SignedAuthorization], sender| {
+            applied_authorizations(auths, 1, sender, &prestate)
+        };
+
+        // Another chain, a stale nonce and an authority with contract code are skipped.
+        let skipped = [
+            sign_unchecked(&alice, 2, second, 3).unwrap(),
+            sign_unchecked(&bob, 1, second, 4).unwrap(),
+            sign_unchecked(&carol, 1, second, 0).unwrap(),
+        ];
+        assert!(applied(&skipped, Address::ZERO).is_empty());
+
+        // Chain id 0 is valid everywhere, and a delegated authority can be delegated again.
+        let valid = [
+            sign_unchecked(&alice, 0, second, 3).unwrap(),
+            sign_unchecked(&bob, 1, second, 5).unwrap(),
+        ];
+        let found = applied(&valid, Address::ZERO);
+        assert_eq!(found.get(&alice.address()), Some(&second));
+        assert_eq!(found.get(&bob.address()), Some(&second));
+
+        // The sender's nonce is bumped before the list is processed.
+        let own = [sign_unchecked(&bob, 1, second, 5).unwrap()];
+        assert!(applied(&own, bob.address()).is_empty());
+        let own = [sign_unchecked(&bob, 1, second, 6).unwrap()];
+        assert_eq!(
+            applied(&own, bob.address()).get(&bob.address()),
+            Some(&second)
+        );
+
+        // Each applied authorization bumps the nonce, so a repeated one is skipped.
+        let repeated = [
+            sign_unchecked(&alice, 1, second, 3).unwrap(),
+            sign_unchecked(&alice, 1, first, 3).unwrap(),
+        ];
+        assert_eq!(
+            applied(&repeated, Address::ZERO).get(&alice.address()),
+            Some(&second)
+        );
+        let chained = [
+            sign_unchecked(&alice, 1, second, 3).unwrap(),
+            sign_unchecked(&alice, 1, Address::ZERO, 4).unwrap(),
+        ];
+        assert_eq!(
+            applied(&chained, Address::ZERO).get(&alice.address()),
+            Some(&Address::ZERO)
+        );
+    }
+}
@@ -0,0 +1,526 @@
+//! ERC-4337 v0.8 user operations for EOAs delegated with EIP-7702.
+//!
+//! An EOA delegated to a 4337 account (such as `Simple7702Account`) sends [`UserOperation`]s
+//! through an EntryPoint instead of transactions. Under v0.8 the operation can carry the
+//! EOA's authorization: its initCode is the `0x7702` marker, the bundler adds the
+//! authorization to the `handleOps` transaction, and the hash commits to the delegate instead
+//! of the initCode. The hash is an EIP-712 digest in the ("ERC4337", "1") domain of the
+//! EntryPoint, which the account's key signs as is.
+//!
+//! The local flow runs against `MinimalEntryPoint`, a stand-in that mirrors the v0.8 hashing but
+//! not its bytecode, so passing on Anvil alone does not show that hashes match the real
+//! EntryPoint. The unit test checks [`UserOperation::hash`] against alloy's EIP-712 encoding of
+//! the spec; `tests/user_operation.rs` compares it, and the stand-in, with the deployed v0.8
+//! EntryPoint on a fork, which needs a `FORK_URL` and is not run by default.
+//!
+//! [`Bundler`] is a minimal local bundler: it checks pending operations against the EntryPoint
+//! with `eth_call`, drops the invalid ones and submits the rest in one `handleOps`. For an
+//! external bundler, [`send_user_operation`] uses `eth_sendUserOperation`.
+
+use std::fmt;
+
+use alloy::{
+    eips::eip7702::SignedAuthorization,
+    primitives::{Address, B256, Bytes, U256, keccak256},
+    providers::Provider,
+    rpc::types::TransactionReceipt,
+    signers::{SignerSync, local::PrivateKeySigner},
+    sol,
+    sol_types::{Eip712Domain, SolEvent, SolValue, eip712_domain},
+};
+
+use crate::{
+    decode::AbiRegistry,
+    error::{Error, Result},
+    revert::{DecodeRevert, Revert},
+};
+
+sol!(
+    #[allow(missing_docs)]
+    #[sol(rpc, abi)]
+    interface EntryPoint {
+        struct PackedUserOperation {
+            address sender;
+            uint256 nonce;
+            bytes initCode;
+            bytes callData;
+            bytes32 accountGasLimits;
+            uint256 preVerificationGas;
+            bytes32 gasFees;
+            bytes paymasterAndData;
+            bytes signature;
+        }
+
+        event UserOperationEvent(
+            bytes32 indexed userOpHash,
+            address indexed sender,
+            address indexed paymaster,
+            uint256 nonce,
+            bool success,
+            uint256 actualGasCost,
+            uint256 actualGasUsed
+        );
+        event UserOperationRevertReason(
+            bytes32 indexed userOpHash, address indexed sender, uint256 nonce, bytes revertReason
+        );
+
+        error FailedOp(uint256 opIndex, string reason);
+
+        function handleOps(PackedUserOperation[] calldata ops, address payable beneficiary) external;
+
+        function getUserOpHash(PackedUserOperation calldata userOp) external view returns (bytes32);
+
+        function getNonce(address sender, uint192 key) external view returns (uint256);
+
+        function balanceOf(address account) external view returns (uint256);
+
+        function depositTo(address account) external payable;
+    }
+
+    #[allow(missing_docs)]
+    #[sol(rpc, abi)]
+    interface Simple7702Account {
+        struct Call {
+            address to;
+            uint256 value;
+            bytes data;
+        }
+
+        function entryPoint() external view returns (address);
+
+        function execute(address to, uint256 value, bytes calldata data) external payable;
+
+        function executeBatch(Call[] calldata calls) external payable;
+    }
+);
+
+pub use EntryPoint::PackedUserOperation;
+
+/// The canonical EntryPoint v0.8 deployment.
+pub const ENTRY_POINT_V08: Address =
+    alloy::primitives::address!("0x4337084D9E255Ff0702461CF8895CE9E3b5Ff108");
+
+/// The initCode of an operation that carries an EIP-7702 authorization.
+pub const EIP7702_INIT_CODE: [u8; 20] = {
+    let mut marker = [0u8; 20];
+    marker[0] = 0x77;
+    marker[1] = 0x02;
+    marker
+};
+
+const PACKED_USEROP_TYPE: &str = "PackedUserOperation(address sender,uint256 nonce,bytes initCode,\
+    bytes callData,bytes32 accountGasLimits,uint256 preVerificationGas,bytes32 gasFees,\
+    bytes paymasterAndData)";
+
+pub const DEFAULT_CALL_GAS_LIMIT: u128 = 200_000;
+pub const DEFAULT_VERIFICATION_GAS_LIMIT: u128 = 150_000;
+pub const DEFAULT_PRE_VERIFICATION_GAS: u128 = 50_000;
+
+/// A user operation with its gas fields unpacked. Factories and paymasters are not supported.
+#[derive(Debug, Clone)]
+pub struct UserOperation {
+    pub sender: Address,
+    /// `key << 64 | sequence`, see `EntryPoint.getNonce`.
+    pub nonce: U256,
+    pub call_data: Bytes,
+    pub call_gas_limit: u128,
+    pub verification_gas_limit: u128,
+    pub pre_verification_gas: u128,
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+    /// Delegates `sender` in the same `handleOps` transaction.
+    pub authorization: Option<SignedAuthorization>,
+    pub signature: Bytes,
+}
+
+impl UserOperation {
+    /// An unsigned operation with the default gas limits and no fees.
+    pub fn new(sender: Address, nonce: U256, call_data: Bytes) -> Self {
+        Self {
+            sender,
+            nonce,
+            call_data,
+            call_gas_limit: DEFAULT_CALL_GAS_LIMIT,
+            verification_gas_limit: DEFAULT_VERIFICATION_GAS_LIMIT,
+            pre_verification_gas: DEFAULT_PRE_VERIFICATION_GAS,
+            max_fee_per_gas: 0,
+            max_priority_fee_per_gas: 0,
+            authorization: None,
+            signature: Bytes::new(),
+        }
+    }
+
+    pub fn init_code(&self) -> Bytes {
+        match self.authorization {
+            Some(_) => EIP7702_INIT_CODE.into(),
+            None => Bytes::new(),
+        }
+    }
+
+    /// `verificationGasLimit ‖ callGasLimit`, 16 bytes each.
+    pub fn account_gas_limits(&self) -> B256 {
+        pack(self.verification_gas_limit, self.call_gas_limit)
+    }
+
+    /// `maxPriorityFeePerGas ‖ maxFeePerGas`, 16 bytes each.
+    pub fn gas_fees(&self) -> B256 {
+        pack(self.max_priority_fee_per_gas, self.max_fee_per_gas)
+    }
+
+    pub fn pack(&self) -> PackedUserOperation {
+        PackedUserOperation {
+            sender: self.sender,
+            nonce: self.nonce,
+            initCode: self.init_code(),
+            callData: self.call_data.clone(),
+            accountGasLimits: self.account_gas_limits(),
+            preVerificationGas: U256::from(self.pre_verification_gas),
+            gasFees: self.gas_fees(),
+            paymasterAndData: Bytes::new(),
+            signature: self.signature.clone(),
+        }
+    }
+
+    /// The EntryPoint's `getUserOpHash`. With an authorization the initCode is hashed as the
+    /// delegate's address, which the EntryPoint reads from the sender's code.
+    pub fn hash(&self, entry_point: Address, chain_id: u64) -> B256 {
+        let init_code_hash = match &self.authorization {
+            Some(auth) => keccak256(auth.address),
+            None => keccak256([]),
+        };
+        let struct_hash = keccak256(
+            (
+                keccak256(PACKED_USEROP_TYPE),
+                self.sender,
+                self.nonce,
+                init_code_hash,
+                keccak256(&self.call_data),
+                self.account_gas_limits(),
+                U256::from(self.pre_verification_gas),
+                self.gas_fees(),
+                keccak256([]),
+            )
+                .abi_encode(),
+        );
+        let separator = domain(entry_point, chain_id).separator();
+        keccak256([&[0x19, 0x01], separator.as_slice(), struct_hash.as_slice()].concat())
+    }
+
+    /// Signs [`hash`](Self::hash) with the sender's key, as `Simple7702Account` expects.
+    pub fn sign(
+        &self,
+        signer: &PrivateKeySigner,
+        entry_point: Address,
+        chain_id: u64,
+    ) -> Result<Bytes> {
+        Ok(signer
+            .sign_hash_sync(&self.hash(entry_point, chain_id))?
+            .as_bytes()
+            .into())
+    }
+
+    /// The operation as `eth_sendUserOperation` takes it.
+    pub fn to_rpc(&self) -> serde_json::Value {
+        let mut op = serde_json::json!({
+            "sender": self.sender,
+            "nonce": self.nonce,
+            "callData": self.call_data,
+            "callGasLimit": U256::from(self.call_gas_limit),
+            "verificationGasLimit": U256::from(self.verification_gas_limit),
+            "preVerificationGas": U256::from(self.pre_verification_gas),
+            "maxFeePerGas": U256::from(self.max_fee_per_gas),
+            "maxPriorityFeePerGas": U256::from(self.max_priority_fee_per_gas),
+            "signature": self.signature,
+        });
+        if let Some(auth) = &self.authorization {
+            op["factory"] = serde_json::json!("0x7702");
+            op["factoryData"] = serde_json::json!("0x");
+            op["eip7702Auth"] = serde_json::json!(auth);
+        }
+        op
+    }
+}
+
+fn pack(high: u128, low: u128) -> B256 {
+    B256::from((U256::from(high) << 128) | U256::from(low))
+}
+
+pub fn domain(entry_point: Address, chain_id: u64) -> Eip712Domain {
+    eip712_domain! {
+        name: "ERC4337",
+        version: "1",
+        chain_id: chain_id,
+        verifying_contract: entry_point,
+    }
+}
+
+/// The EntryPoint ABI, so that `FailedOp` decodes without a Forge build.
+pub fn abi() -> alloy::json_abi::JsonAbi {
+    EntryPoint::abi::contract()
+}
+
+/// Submits `op` to an external bundler and returns the user operation hash it reports.
+pub async fn send_user_operation<P: Provider>(
+    bundler: &P,
+    op: &UserOperation,
+    entry_point: Address,
+) -> Result<B256> {
+    bundler
+        .raw_request("eth_sendUserOperation".into(), (op.to_rpc(), entry_point))
+        .await
+        .map_err(Error::rpc)
+}
+
+/// What the EntryPoint reported for one operation of a bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpResult {
+    pub user_op_hash: B256,
+    pub sender: Address,
+    pub nonce: U256,
+    /// Whether the operation's call succeeded. Its gas is paid either way.
+    pub success: bool,
+    pub actual_gas_cost: U256,
+    pub revert: Option<Revert>,
+}
+
+impl fmt::Display for OpResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} from {} nonce {}: ",
+            self.user_op_hash, self.sender, self.nonce
+        )?;
+        match (&self.revert, self.success) {
+            (_, true) => write!(f, "ok, paid {} wei", self.actual_gas_cost),
+            (Some(revert), false) => write!(f, "call failed: {revert}"),
+            (None, false) => write!(f, "call failed"),
+        }
+    }
+}
+
+/// Per-operation results of the `handleOps` of `entry_point` in `receipt`.
+pub fn results(
+    receipt: &TransactionReceipt,
+    entry_point: Address,
+    abis: &AbiRegistry,
+) -> Vec<OpResult> {
+    let logs = receipt
+        .inner
+        .logs()
+        .iter()
+        .filter(|log| log.address() == entry_point);
+    let mut results: Vec<OpResult> = Vec::new();
+    let mut reverts = Vec::new();
+    for log in logs {
+        if let Ok(event) = EntryPoint::UserOperationEvent::decode_log_data(log.data()) {
+            results.push(OpResult {
+                user_op_hash: event.userOpHash,
+                sender: event.sender,
+                nonce: event.nonce,
+                success: event.success,
+                actual_gas_cost: event.actualGasCost,
+                revert: None,
+            });
+        } else if let Ok(event) = EntryPoint::UserOperationRevertReason::decode_log_data(log.data())
+        {
+            reverts.push(event);
+        }
+    }
+    for revert in reverts {
+        if let Some(result) = results
+            .iter_mut()
+            .find(|r| r.user_op_hash == revert.userOpHash)
+        {
+            result.revert = Some(abis.decode_revert(&revert.revertReason));
+        }
+    }
+    results
+}
+
+/// An operation the bundler would not submit, with the EntryPoint's `FailedOp` revert.
+#[derive(Debug, Clone)]
+pub struct DroppedOp {
+    pub op: UserOperation,
+    pub user_op_hash: B256,
+    pub revert: Revert,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Bundle {
+    /// `None` when no operation was valid and nothing was sent.
+    pub receipt: Option<TransactionReceipt>,
+    pub results: Vec<OpResult>,
+    pub dropped: Vec<DroppedOp>,
+}
+
+/// A minimal bundler: a mempool and one `handleOps` per [`bundle`](Self::bundle) call.
+#[derive(Debug, Clone)]
+pub struct Bundler {
+    pub entry_point: Address,
+    /// Receives the fees the EntryPoint collects from the operations.
+    pub beneficiary: Address,
+    pub pending: Vec<UserOperation>,
+}
+
+impl Bundler {
+    pub fn new(entry_point: Address, beneficiary: Address) -> Self {
+        Self {
+            entry_point,
+            beneficiary,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, op: UserOperation) {
+        self.pending.push(op);
+    }
+
+    /// Submits the pending operations through `provider`, whose wallet pays for `handleOps`.
+    ///
+    /// Each operation is checked with `eth_call` on top of the ones already accepted, so an
+    /// operation that would make the whole bundle revert is dropped instead. `abis` should
+    /// include [`abi`] to decode the EntryPoint's `FailedOp`. The pending operations are only
+    /// cleared once `handleOps` is mined; on any error they stay for the next call.
+    pub async fn bundle<P: Provider>(
+        &mut self,
+        provider: &P,
+        abis: &AbiRegistry,
+    ) -> Result<Bundle> {
+        let chain_id = provider.get_chain_id().await.map_err(Error::rpc)?;
+        let entry_point = EntryPoint::new(self.entry_point, provider);
+
+        let mut accepted: Vec<UserOperation> = Vec::new();
+        let mut dropped = Vec::new();
+        for op in self.pending.iter().cloned() {
+            let candidate: Vec<UserOperation> =
+                accepted.iter().cloned().chain([op.clone()]).collect();
+            let (ops, auths) = handle_ops_args(&candidate);
+            let mut call = entry_point.handleOps(ops, self.beneficiary);
+            if !auths.is_empty() {
+                call = call.authorization_list(auths);
+            }
+            match call.call().await.decode_revert(abis, "handleOps") {
+                Ok(_) => accepted.push(op),
+                Err(Error::Revert(err)) => dropped.push(DroppedOp {
+                    user_op_hash: op.hash(self.entry_point, chain_id),
+                    op,
+                    revert: err.revert,
+                }),
+                Err(err) => return Err(err),
+            }
+        }
+
+        if accepted.is_empty() {
+            self.pending.clear();
+            return Ok(Bundle {
+                dropped,
+                ..Bundle::default()
+            });
+        }
+        let (ops, auths) = handle_ops_args(&accepted);
+        let mut call = entry_point.handleOps(ops, self.beneficiary);
+        if !auths.is_empty() {
+            call = call.authorization_list(auths);
+        }
+        let receipt = call
+            .send()
+            .await
+            .decode_revert(abis, "handleOps")?
+            .get_receipt()
+            .await?;
+        self.pending.clear();
+        Ok(Bundle {
+            results: results(&receipt, self.entry_point, abis),
+            receipt: Some(receipt),
+            dropped,
+        })
+    }
+}
+
+fn handle_ops_args(ops: &[UserOperation]) -> (Vec<PackedUserOperation>, Vec<SignedAuthorization>) {
+    (
+        ops.iter().map(UserOperation::pack).collect(),
+        ops.iter()
+            .filter_map(|op| op.authorization.clone())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authorization::sign_unchecked;
+    use alloy::{
+        primitives::{Signature, address, b256},
+        sol_types::SolStruct,
+    };
+
+    // The EIP-712 type the EntryPoint hashes: the packed operation without its signature.
+    sol! {
+        struct PackedUserOperation {
+            address sender;
+            uint256 nonce;
+            bytes initCode;
+            bytes callData;
+            bytes32 accountGasLimits;
+            uint256 preVerificationGas;
+            bytes32 gasFees;
+            bytes paymasterAndData;
+        }
+    }
+
+    #[test]
+    fn hashes_like_the_entry_point() {
+        let alice = PrivateKeySigner::random();
+        let delegate = address!("0x00000000000000000000000000000000000000d1");
+        let mut op = UserOperation::new(alice.address(), U256::from(5), Bytes::from(vec![1, 2]));
+        op.max_fee_per_gas = 3;
+        op.max_priority_fee_per_gas = 2;
+        assert_eq!(
+            op.account_gas_limits(),
+            b256!("0x000000000000000000000000000249f000000000000000000000000000030d40")
+        );
+
+        // Without an authorization the hash is the plain EIP-712 struct hash.
+        let typed = PackedUserOperation {
+            sender: op.sender,
+            nonce: op.nonce,
+            initCode: Bytes::new(),
+            callData: op.call_data.clone(),
+            accountGasLimits: op.account_gas_limits(),
+            preVerificationGas: U256::from(op.pre_verification_gas),
+            gasFees: op.gas_fees(),
+            paymasterAndData: Bytes::new(),
+        };
+        let domain = domain(ENTRY_POINT_V08, 1);
+        assert_eq!(
+            op.hash(ENTRY_POINT_V08, 1),
+            typed.eip712_signing_hash(&domain)
+        );
+
+        // With one, the delegate takes the place of the initCode in the hash.
+        op.authorization = Some(sign_unchecked(&alice, 1, delegate, 0).unwrap());
+        assert_eq!(op.pack().initCode, Bytes::from(EIP7702_INIT_CODE));
+        let typed = PackedUserOperation {
+            initCode: delegate.to_vec().into(),
+            ..typed
+        };
+        assert_eq!(
+            op.hash(ENTRY_POINT_V08, 1),
+            typed.eip712_signing_hash(&domain)
+        );
+        assert_ne!(op.hash(ENTRY_POINT_V08, 1), op.hash(ENTRY_POINT_V08, 31337));
+
+        op.signature = op.sign(&alice, ENTRY_POINT_V08, 1).unwrap();
+        let signer = Signature::from_raw(&op.signature)
+            .unwrap()
+            .recover_address_from_prehash(&op.hash(ENTRY_POINT_V08, 1))
+            .unwrap();
+        assert_eq!(signer, alice.address());
+        assert_eq!(op.to_rpc()["factory"], "0x7702");
+        assert_eq!(
+            op.to_rpc()["eip7702Auth"]["address"],
+            serde_json::json!(delegate)
+        );
+    }
+}
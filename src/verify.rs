@@ -0,0 +1,144 @@
+//! Verifies deployed runtime code against local Forge artifacts.
+
+use alloy::{
+    primitives::{Address, B256, Bytes, keccak256},
+    providers::Provider,
+};
+
+use crate::{
+    artifacts::{Artifact, strip_metadata},
+    authorization::delegated_to,
+    error::{Error, Result},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeMatch {
+    /// Identical, including the metadata hash.
+    Exact,
+    /// Identical once the solc metadata is stripped, i.e. built from different source text
+    /// (comments, paths) or compiler settings that do not affect the code.
+    IgnoringMetadata,
+}
+
+#[derive(Debug, Clone)]
+pub struct Verification {
+    pub address: Address,
+    /// Set when `address` is an EOA delegated via EIP-7702; the delegate is what gets verified.
+    pub delegate: Option<Address>,
+    pub code: Bytes,
+    pub codehash: B256,
+    pub matched: Option<(String, CodeMatch)>,
+}
+
+/// Compares runtime code with an artifact's deployed bytecode.
+///
+/// Immutable references are zeroed on both sides first, since the constructor fills them in.
+/// References that point past the end of the code are ignored.
+pub fn compare(code: &[u8], artifact: &Artifact) -> Option<CodeMatch> {
+    let expected = &artifact.deployed_bytecode;
+    if code.len() != expected.object.len() {
+        return None;
+    }
+
+    let mut actual = code.to_vec();
+    let mut reference = expected.object.to_vec();
+    for range in expected.immutable_references.values().flatten() {
+        if range.start >= actual.len() {
+            continue;
+        }
+        let end = (range.start + range.length).min(actual.len());
+        actual[range.start..end].fill(0);
+        reference[range.start..end].fill(0);
+    }
+
+    if actual == reference {
+        Some(CodeMatch::Exact)
+    } else if strip_metadata(&actual) == strip_metadata(&reference) {
+        Some(CodeMatch::IgnoringMetadata)
+    } else {
+        None
+    }
+}
+
+/// Fetches the code at `address` (following an EIP-7702 delegation) and looks for a
+/// matching artifact among `candidates`.
+pub async fn verify_code<P: Provider>(
+    provider: &P,
+    address: Address,
+    candidates: &[(String, Artifact)],
+) -> Result<Verification> {
+    let mut code = provider.get_code_at(address).await.map_err(Error::rpc)?;
+    let delegate = delegated_to(&code);
+    if let Some(delegate) = delegate {
+        code = provider.get_code_at(delegate).await.map_err(Error::rpc)?;
+    }
+    if code.is_empty() {
+        return Err(Error::UnexpectedResponse(format!(
+            "no code at {}",
+            delegate.unwrap_or(address)
+        )));
+    }
+
+    let matched = candidates
+        .iter()
+        .find_map(|(name, artifact)| compare(&code, artifact).map(|m| (name.clone(), m)));
+
+    Ok(Verification {
+        address,
+        delegate,
+        codehash: keccak256(&code),
+        code,
+        matched,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifacts::ImmutableReference;
+
+    #[test]
+    fn ignores_immutables_and_metadata() {
+        let mut artifact = Artifact::load("EventLogger").unwrap();
+        let mut code = artifact.deployed_bytecode.object.to_vec();
+
+        // Pretend bytes 10..42 are an immutable set by the constructor.
+        artifact.deployed_bytecode.immutable_references.insert(
+            "1".into(),
+            vec![ImmutableReference {
+                start: 10,
+                length: 32,
+            }],
+        );
+        code[10..42].fill(0xaa);
+        assert_eq!(compare(&code, &artifact), Some(CodeMatch::Exact));
+
+        let last = code.len() - 3;
+        code[last] ^= 0xff;
+        assert_eq!(compare(&code, &artifact), Some(CodeMatch::IgnoringMetadata));
+
+        code[50] ^= 0xff;
+        assert_eq!(compare(&code, &artifact), None);
+    }
+
+    #[test]
+    fn ignores_immutables_past_the_end_of_truncated_code() {
+        let mut artifact = Artifact::load("EventLogger").unwrap();
+        let code = artifact.deployed_bytecode.object.slice(..40);
+        artifact.deployed_bytecode.object = code.clone();
+        artifact.deployed_bytecode.immutable_references.insert(
+            "1".into(),
+            vec![
+                ImmutableReference {
+                    start: 30,
+                    length: 32,
+                },
+                ImmutableReference {
+                    start: 100,
+                    length: 32,
+                },
+            ],
+        );
+        assert_eq!(compare(&code, &artifact), Some(CodeMatch::Exact));
+    }
+}
@@ -0,0 +1,731 @@
+//! EIP-5792 wallet JSON-RPC for configured accounts: `wallet_sendCalls`,
+//! `wallet_getCallsStatus` and `wallet_getCapabilities`.
+//!
+//! The calls of a `wallet_sendCalls` request become one [`Batch`] for the account's delegate,
+//! sent to the account itself. An account that is not delegated yet is delegated in the same
+//! transaction, with the authorization signed through the [`DelegateRegistry`]. Without
+//! sponsorship the account sends the batch and pays for it. With the `paymasterService`
+//! capability the configured sponsor sends it instead, with the batch signed by the account, in
+//! the style of an ERC-7677 paymaster. Its `url` is not contacted. Sponsoring is only offered
+//! for executors whose signatures bind the chain and the account, since anyone can submit a
+//! signed batch. Every batch is simulated, calls and executor, before it is sent.
+//!
+//! [`WalletRpc::handle`] answers one request. [`serve`] exposes it over HTTP behind a bearer
+//! token, since whoever reaches the server can spend from the accounts.
+
+use std::{collections::HashMap, convert::Infallible, sync::Arc};
+
+use alloy::{
+    network::{TransactionBuilder, TransactionBuilder7702},
+    primitives::{Address, B256, Bytes, U64, U256},
+    providers::{Provider, RootProvider},
+    rpc::{
+        client::RpcClient,
+        types::{TransactionReceipt, TransactionRequest},
+    },
+    signers::local::PrivateKeySigner,
+    sol_types::SolCall,
+    transports::http::reqwest::{
+        self, Url,
+        header::{AUTHORIZATION, HeaderMap, HeaderValue},
+    },
+};
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    Request, Response,
+    body::{Bytes as Body, Incoming},
+    server::conn::http1,
+    service::service_fn,
+};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::{net::TcpListener, sync::Mutex};
+
+use crate::{
+    authorization::delegated_to,
+    batch::{Batch, Executor},
+    contracts::BatchCallAndSponsor::{self, Call},
+    decode::AbiRegistry,
+    erc7821::ERC7821,
+    error::{Error, Result},
+    presubmit::{self, PlannedCall},
+    preview::delegation_override,
+    registry::DelegateRegistry,
+    revert::DecodeRevert,
+};
+
+/// The EIP-5792 version this server speaks.
+pub const VERSION: &str = "2.0.0";
+
+/// A JSON-RPC error, with the EIP-5792 codes where one applies.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[error("{message} ({code})")]
+pub struct WalletError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl WalletError {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL: i64 = -32603;
+    /// The node rejected or reverted the transaction.
+    pub const TRANSACTION_FAILED: i64 = -32000;
+    pub const UNAUTHORIZED: i64 = 4100;
+    pub const UNSUPPORTED_CAPABILITY: i64 = 5700;
+    pub const UNSUPPORTED_CHAIN: i64 = 5710;
+    pub const DUPLICATE_ID: i64 = 5720;
+    pub const UNKNOWN_BUNDLE: i64 = 5730;
+    pub const ATOMICITY_NOT_SUPPORTED: i64 = 5760;
+
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<Error> for WalletError {
+    fn from(err: Error) -> Self {
+        let code = match err {
+            Error::Revert(_) | Error::Rejected { .. } => Self::TRANSACTION_FAILED,
+            // The registry refused the delegate.
+            Error::InvalidAuthorization(_) => Self::UNAUTHORIZED,
+            _ => Self::INTERNAL,
+        };
+        Self::new(code, err.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendCallsRequest {
+    pub version: String,
+    #[serde(default)]
+    pub id: Option<String>,
+    pub from: Address,
+    pub chain_id: U64,
+    #[serde(default)]
+    pub atomic_required: bool,
+    pub calls: Vec<CallRequest>,
+    #[serde(default)]
+    pub capabilities: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallRequest {
+    pub to: Address,
+    #[serde(default)]
+    pub data: Bytes,
+    #[serde(default)]
+    pub value: U256,
+    #[serde(default)]
+    pub capabilities: HashMap<String, Value>,
+}
+
+/// `wallet_getCallsStatus` result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallsStatus {
+    pub version: String,
+    pub id: String,
+    pub chain_id: U64,
+    /// 100 pending, 200 confirmed, 500 reverted on chain.
+    pub status: u16,
+    pub atomic: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipts: Option<Vec<CallsReceipt>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallsReceipt {
+    pub logs: Vec<ReceiptLog>,
+    /// `0x1` on success, `0x0` on revert.
+    pub status: U64,
+    pub block_hash: B256,
+    pub block_number: U64,
+    pub gas_used: U64,
+    pub transaction_hash: B256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReceiptLog {
+    pub address: Address,
+    pub data: Bytes,
+    pub topics: Vec<B256>,
+}
+
+impl CallsReceipt {
+    pub fn from_receipt(receipt: &TransactionReceipt) -> Self {
+        Self {
+            logs: receipt
+                .inner
+                .logs()
+                .iter()
+                .map(|log| ReceiptLog {
+                    address: log.address(),
+                    data: log.data().data.clone(),
+                    topics: log.topics().to_vec(),
+                })
+                .collect(),
+            status: U64::from(receipt.status() as u8),
+            block_hash: receipt.block_hash.unwrap_or_default(),
+            block_number: U64::from(receipt.block_number.unwrap_or_default()),
+            gas_used: U64::from(receipt.gas_used),
+            transaction_hash: receipt.transaction_hash,
+        }
+    }
+}
+
+/// The accounts a [`WalletRpc`] acts for and how it sends their batches.
+#[derive(Debug, Clone)]
+pub struct WalletConfig {
+    pub chain_id: u64,
+    /// The contract accounts are delegated to, speaking `executor`.
+    pub delegate: Address,
+    pub executor: Executor,
+    pub accounts: Vec<PrivateKeySigner>,
+    /// Pays for batches that request `paymasterService`. The capability is only offered with a
+    /// sponsor and an executor that [binds the account](Executor::binds_account).
+    pub sponsor: Option<PrivateKeySigner>,
+    /// Checks `delegate` before an account's authorization is signed.
+    pub registry: DelegateRegistry,
+}
+
+impl WalletConfig {
+    fn account(&self, address: Address) -> std::result::Result<&PrivateKeySigner, WalletError> {
+        self.accounts
+            .iter()
+            .find(|signer| signer.address() == address)
+            .ok_or_else(|| {
+                WalletError::new(
+                    WalletError::UNAUTHORIZED,
+                    format!("{address} is not an account of this wallet"),
+                )
+            })
+    }
+
+    fn sponsor(&self) -> Option<&PrivateKeySigner> {
+        self.sponsor
+            .as_ref()
+            .filter(|_| self.executor.binds_account())
+    }
+
+    fn atomic(&self) -> bool {
+        !matches!(self.executor, Executor::Erc7821 { try_mode: true })
+    }
+}
+
+/// Whether `capabilities` ask for sponsorship. Unknown capabilities are refused unless they
+/// are marked `optional`, as EIP-5792 requires.
+pub fn wants_sponsorship(
+    capabilities: &HashMap<String, Value>,
+    sponsor_available: bool,
+) -> std::result::Result<bool, WalletError> {
+    let mut sponsored = false;
+    for (name, capability) in capabilities {
+        let optional = capability
+            .get("optional")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        match name.as_str() {
+            "paymasterService" if sponsor_available => sponsored = true,
+            _ if optional => {}
+            _ => {
+                return Err(WalletError::new(
+                    WalletError::UNSUPPORTED_CAPABILITY,
+                    format!("unsupported capability {name}"),
+                ));
+            }
+        }
+    }
+    Ok(sponsored)
+}
+
+/// A batch sent for `wallet_sendCalls`.
+#[derive(Debug, Clone, Copy)]
+struct Sent {
+    tx_hash: B256,
+    atomic: bool,
+}
+
+/// Answers EIP-5792 requests for the accounts of `config`.
+///
+/// `provider` must be able to sign for the accounts and the sponsor, e.g. with an
+/// `EthereumWallet` holding all of their keys.
+#[derive(Debug)]
+pub struct WalletRpc<P> {
+    pub config: WalletConfig,
+    provider: P,
+    abis: AbiRegistry,
+    /// Batches by id. Held while sending, so that batches of one account get nonces in order.
+    sent: Mutex<HashMap<String, Sent>>,
+}
+
+impl<P: Provider> WalletRpc<P> {
+    pub fn new(config: WalletConfig, provider: P) -> Self {
+        // The other executors revert with reason strings, which decode without an ABI.
+        let mut abis = AbiRegistry::default();
+        abis.add(&ERC7821::abi::contract());
+        Self {
+            config,
+            provider,
+            abis,
+            sent: Mutex::default(),
+        }
+    }
+
+    /// Dispatches one JSON-RPC method call.
+    pub async fn handle(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> std::result::Result<Value, WalletError> {
+        match method {
+            "wallet_sendCalls" => {
+                let [request]: [SendCallsRequest; 1] = parse(params)?;
+                let id = self.send_calls(request).await?;
+                Ok(json!({ "id": id }))
+            }
+            "wallet_getCallsStatus" => {
+                let [id]: [String; 1] = parse(params)?;
+                Ok(json!(self.calls_status(&id).await?))
+            }
+            "wallet_getCapabilities" => {
+                // The chain ids are optional, so the params may be one or two long.
+                let mut params: Vec<Value> = parse(params)?;
+                let chain_ids = match params.len() {
+                    2 => parse(params.pop().unwrap_or_default())?,
+                    _ => None,
+                };
+                let [address]: [Address; 1] = parse(Value::Array(params))?;
+                self.capabilities(address, chain_ids).await
+            }
+            "eth_chainId" => Ok(json!(U64::from(self.config.chain_id))),
+            "eth_accounts" => Ok(json!(
+                self.config
+                    .accounts
+                    .iter()
+                    .map(PrivateKeySigner::address)
+                    .collect::<Vec<_>>()
+            )),
+            _ => Err(WalletError::new(
+                WalletError::METHOD_NOT_FOUND,
+                format!("method {method} not supported"),
+            )),
+        }
+    }
+
+    pub async fn send_calls(
+        &self,
+        request: SendCallsRequest,
+    ) -> std::result::Result<String, WalletError> {
+        if request.chain_id.to::<u64>() != self.config.chain_id {
+            return Err(WalletError::new(
+                WalletError::UNSUPPORTED_CHAIN,
+                format!("chain {} is not supported", request.chain_id),
+            ));
+        }
+        if request.atomic_required && !self.config.atomic() {
+            return Err(WalletError::new(
+                WalletError::ATOMICITY_NOT_SUPPORTED,
+                "batches run in try mode and are not atomic",
+            ));
+        }
+        let account = self.config.account(request.from)?;
+        let sponsored = wants_sponsorship(&request.capabilities, self.config.sponsor().is_some())?;
+        for call in &request.calls {
+            wants_sponsorship(&call.capabilities, false)?;
+        }
+
+        let mut sent = self.sent.lock().await;
+        if let Some(id) = request.id.as_ref().filter(|id| sent.contains_key(*id)) {
+            return Err(WalletError::new(
+                WalletError::DUPLICATE_ID,
+                format!("batch {id} already exists"),
+            ));
+        }
+
+        let batch = Batch {
+            executor: self.config.executor,
+            calls: request
+                .calls
+                .into_iter()
+                .map(|call| Call {
+                    to: call.to,
+                    value: call.value,
+                    data: call.data,
+                })
+                .collect(),
+        };
+        let tx_hash = self.send_batch(account, &batch, sponsored).await?;
+        // Without an id from the dapp, the transaction hash is as unique as it gets.
+        let id = request.id.unwrap_or_else(|| tx_hash.to_string());
+        sent.insert(
+            id.clone(),
+            Sent {
+                tx_hash,
+                atomic: self.config.atomic(),
+            },
+        );
+        Ok(id)
+    }
+
+    async fn send_batch(
+        &self,
+        account: &PrivateKeySigner,
+        batch: &Batch,
+        sponsored: bool,
+    ) -> Result<B256> {
+        let address = account.address();
+        let code = self
+            .provider
+            .get_code_at(address)
+            .await
+            .map_err(Error::rpc)?;
+        let delegated = delegated_to(&code) == Some(self.config.delegate);
+        let tx_nonce = self
+            .provider
+            .get_transaction_count(address)
+            .await
+            .map_err(Error::rpc)?;
+
+        let (from, input, auth_nonce) = match self.config.sponsor() {
+            Some(sponsor) if sponsored => {
+                let nonce = self.executor_nonce(address).await?;
+                let input = batch.sponsored(account, self.config.chain_id, nonce)?;
+                (sponsor.address(), input, tx_nonce)
+            }
+            None if sponsored => {
+                return Err(Error::Config(format!(
+                    "{:?} signatures do not bind the account, refusing to sponsor",
+                    self.config.executor
+                )));
+            }
+            // The account sends its own authorization, which is checked after its nonce is
+            // bumped for the transaction.
+            _ => (address, batch.direct(), tx_nonce + 1),
+        };
+        let mut tx = TransactionRequest::default()
+            .with_from(from)
+            .with_to(address)
+            .with_input(input.clone());
+        if !delegated {
+            let auth = self
+                .config
+                .registry
+                .sign_authorization(
+                    &self.provider,
+                    account,
+                    self.config.chain_id,
+                    self.config.delegate,
+                    auth_nonce,
+                )
+                .await?;
+            tx = tx.with_authorization_list(vec![auth]);
+        }
+
+        let planned: Vec<PlannedCall> = batch
+            .calls
+            .iter()
+            .map(|call| PlannedCall {
+                allow_failure: !self.config.atomic(),
+                ..call.into()
+            })
+            .collect();
+        presubmit::simulate_sponsored(
+            &self.provider,
+            from,
+            address,
+            (!delegated).then_some(self.config.delegate),
+            input,
+            &planned,
+            &self.abis,
+        )
+        .await?
+        .ensure_submittable()?;
+        Ok(*self
+            .provider
+            .send_transaction(tx)
+            .await
+            .decode_revert(&self.abis, "wallet_sendCalls")?
+            .tx_hash())
+    }
+
+    /// The executor's `nonce()` for `address`, read as if the account were delegated already.
+    /// Storage outlives a change of delegate, so an account that is not delegated yet may still
+    /// hold a nonce an earlier delegate left in the same slot.
+    async fn executor_nonce(&self, address: Address) -> Result<U256> {
+        let tx = TransactionRequest::default()
+            .with_to(address)
+            .with_input(BatchCallAndSponsor::nonceCall {}.abi_encode());
+        let output = self
+            .provider
+            .call(tx)
+            .overrides(delegation_override(address, self.config.delegate))
+            .await
+            .decode_revert(&self.abis, "nonce")?;
+        Ok(BatchCallAndSponsor::nonceCall::abi_decode_returns(&output)?)
+    }
+
+    pub async fn calls_status(&self, id: &str) -> std::result::Result<CallsStatus, WalletError> {
+        let sent = self.sent.lock().await.get(id).copied().ok_or_else(|| {
+            WalletError::new(WalletError::UNKNOWN_BUNDLE, format!("unknown batch {id}"))
+        })?;
+        let receipt = self
+            .provider
+            .get_transaction_receipt(sent.tx_hash)
+            .await
+            .map_err(Error::rpc)?;
+        let (status, receipts) = match receipt {
+            None => (100, None),
+            Some(receipt) => (
+                if receipt.status() { 200 } else { 500 },
+                Some(vec![CallsReceipt::from_receipt(&receipt)]),
+            ),
+        };
+        Ok(CallsStatus {
+            version: VERSION.into(),
+            id: id.into(),
+            chain_id: U64::from(self.config.chain_id),
+            status,
+            atomic: sent.atomic,
+            receipts,
+        })
+    }
+
+    pub async fn capabilities(
+        &self,
+        address: Address,
+        chain_ids: Option<Vec<U64>>,
+    ) -> std::result::Result<Value, WalletError> {
+        self.config.account(address)?;
+        let chain_id = U64::from(self.config.chain_id);
+        if chain_ids.is_some_and(|ids| !ids.contains(&chain_id)) {
+            return Ok(json!({}));
+        }
+        let code = self
+            .provider
+            .get_code_at(address)
+            .await
+            .map_err(Error::rpc)?;
+        // "ready": the wallet delegates the account with the first batch.
+        let atomic = match delegated_to(&code) == Some(self.config.delegate) {
+            _ if !self.config.atomic() => "unsupported",
+            true => "supported",
+            false => "ready",
+        };
+        Ok(json!({
+            format!("{chain_id:#x}"): {
+                "atomic": { "status": atomic },
+                "paymasterService": { "supported": self.config.sponsor().is_some() },
+            }
+        }))
+    }
+
+    /// Answers a JSON-RPC request or batch of requests.
+    pub async fn handle_json(&self, body: &[u8]) -> Value {
+        match serde_json::from_slice::<Value>(body) {
+            Ok(Value::Array(requests)) => {
+                let mut responses = Vec::with_capacity(requests.len());
+                for request in requests {
+                    responses.push(self.handle_request(request).await);
+                }
+                Value::Array(responses)
+            }
+            Ok(request) => self.handle_request(request).await,
+            Err(err) => response(
+                Value::Null,
+                Err(WalletError::new(WalletError::PARSE_ERROR, err.to_string())),
+            ),
+        }
+    }
+
+    async fn handle_request(&self, request: Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            return response(
+                id,
+                Err(WalletError::new(
+                    WalletError::INVALID_REQUEST,
+                    "missing method",
+                )),
+            );
+        };
+        let params = request.get("params").cloned().unwrap_or(json!([]));
+        response(id, self.handle(method, params).await)
+    }
+}
+
+fn parse<T: serde::de::DeserializeOwned>(params: Value) -> std::result::Result<T, WalletError> {
+    serde_json::from_value(params)
+        .map_err(|err| WalletError::new(WalletError::INVALID_PARAMS, err.to_string()))
+}
+
+fn response(id: Value, result: std::result::Result<Value, WalletError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    }
+}
+
+/// Serves `wallet` as JSON-RPC over HTTP on `listener`, until accepting a connection fails.
+///
+/// Every request must carry `Authorization: Bearer <token>`; others get a 401 with an
+/// [`UNAUTHORIZED`](WalletError::UNAUTHORIZED) error. Without it, any local process, or a web
+/// page rebinding a hostname to the loopback address, could spend from the accounts.
+pub async fn serve<P: Provider + 'static>(
+    wallet: Arc<WalletRpc<P>>,
+    listener: TcpListener,
+    token: String,
+) -> Result<()> {
+    if token.is_empty() {
+        return Err(Error::Config(
+            "the wallet server needs a bearer token".into(),
+        ));
+    }
+    let token: Arc<str> = token.into();
+    loop {
+        let (stream, _) = listener.accept().await.map_err(Error::Server)?;
+        let wallet = wallet.clone();
+        let token = token.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |request: Request<Incoming>| {
+                let wallet = wallet.clone();
+                let token = token.clone();
+                async move {
+                    if !authorized(&request, &token) {
+                        let reply = response(
+                            Value::Null,
+                            Err(WalletError::new(
+                                WalletError::UNAUTHORIZED,
+                                "missing or wrong bearer token",
+                            )),
+                        );
+                        return Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(401)
+                                .header("www-authenticate", "Bearer")
+                                .header("content-type", "application/json")
+                                .body(Full::new(Body::from(reply.to_string())))
+                                .expect("static response parts are valid"),
+                        );
+                    }
+                    let body = match request.into_body().collect().await {
+                        Ok(body) => body.to_bytes(),
+                        Err(err) => Body::from(err.to_string()),
+                    };
+                    let reply = wallet.handle_json(&body).await;
+                    Ok::<_, Infallible>(
+                        Response::builder()
+                            .header("content-type", "application/json")
+                            .body(Full::new(Body::from(reply.to_string())))
+                            .expect("static response parts are valid"),
+                    )
+                }
+            });
+            if let Err(err) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                log::warn!("wallet RPC connection failed: {err}");
+            }
+        });
+    }
+}
+
+/// A provider for a wallet [`serve`]d at `url`, sending `token` with every request, as a dapp
+/// would talk to it.
+pub fn connect(url: Url, token: &str) -> Result<RootProvider> {
+    let mut headers = HeaderMap::new();
+    let mut value = HeaderValue::from_str(&format!("Bearer {token}"))
+        .map_err(|err| Error::Config(format!("invalid bearer token: {err}")))?;
+    value.set_sensitive(true);
+    headers.insert(AUTHORIZATION, value);
+    let client = reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .map_err(|err| Error::Config(format!("cannot build the HTTP client: {err}")))?;
+    Ok(RootProvider::new(RpcClient::new_http_with_client(
+        client, url,
+    )))
+}
+
+/// Whether `request` carries the bearer `token`, compared in constant time.
+fn authorized<B>(request: &Request<B>, token: &str) -> bool {
+    let Some(given) = request
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_send_calls_and_capabilities() {
+        let request: SendCallsRequest = serde_json::from_value(json!({
+            "version": "2.0.0",
+            "from": "0x00000000000000000000000000000000000a11ce",
+            "chainId": "0x7a69",
+            "atomicRequired": true,
+            "calls": [
+                { "to": "0x00000000000000000000000000000000000000d1", "value": "0x1" },
+                { "to": "0x00000000000000000000000000000000000000d2", "data": "0xdeadbeef" }
+            ],
+            "capabilities": { "paymasterService": { "url": "https://paymaster.example" } }
+        }))
+        .unwrap();
+        assert_eq!(request.chain_id, U64::from(31337));
+        assert_eq!(request.calls[0].value, U256::from(1));
+        assert!(request.calls[1].value.is_zero());
+
+        assert_eq!(wants_sponsorship(&request.capabilities, true), Ok(true));
+        assert_eq!(
+            wants_sponsorship(&request.capabilities, false)
+                .unwrap_err()
+                .code,
+            WalletError::UNSUPPORTED_CAPABILITY
+        );
+
+        let optional: HashMap<String, Value> = serde_json::from_value(json!({
+            "paymasterService": { "url": "https://paymaster.example", "optional": true },
+            "auxiliaryFunds": { "optional": true }
+        }))
+        .unwrap();
+        assert_eq!(wants_sponsorship(&optional, false), Ok(false));
+    }
+
+    #[test]
+    fn requires_the_bearer_token() {
+        let request = |header: Option<&str>| {
+            let mut request = Request::builder();
+            if let Some(header) = header {
+                request = request.header("authorization", header);
+            }
+            request.body(()).unwrap()
+        };
+        assert!(authorized(&request(Some("Bearer s3cret")), "s3cret"));
+        assert!(!authorized(&request(Some("Bearer s3cre")), "s3cret"));
+        assert!(!authorized(&request(Some("Bearer s3creT")), "s3cret"));
+        assert!(!authorized(&request(Some("s3cret")), "s3cret"));
+        assert!(!authorized(&request(None), "s3cret"));
+    }
+}
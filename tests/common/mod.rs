@@ -0,0 +1,104 @@
+//! Helpers shared by the Anvil integration tests.
+
+#![allow(dead_code)]
+
+use alloy::{
+    eips::eip7702::SignedAuthorization,
+    network::{TransactionBuilder, TransactionBuilder7702},
+    node_bindings::{Anvil, AnvilInstance},
+    primitives::{Address, U256},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+};
+use interact_eth::{
+    authorization::{delegated_to, sign_unchecked},
+    error::Error,
+    registry::{DEFAULT_REGISTRY, DelegateRegistry, Policy},
+    revert::Revert,
+};
+
+// Not every test checks deadlines.
+#[allow(unused_imports)]
+pub use interact_eth::batch::latest_timestamp;
+
+pub const ONE_ETH: U256 = U256::from_limbs([1_000_000_000_000_000_000, 0, 0, 0]);
+
+pub fn prague(chain_id: u64) -> anyhow::Result<AnvilInstance> {
+    Ok(Anvil::new()
+        .arg("--hardfork")
+        .arg("prague")
+        .arg("--chain-id")
+        .arg(chain_id.to_string())
+        .try_spawn()?)
+}
+
+pub fn key(anvil: &AnvilInstance, index: usize) -> PrivateKeySigner {
+    anvil.keys()[index].clone().into()
+}
+
+/// Points `account` at the `name` deployment at `target`, signing through the delegate
+/// registry, in a transaction sent by the provider's wallet.
+pub async fn delegate<P: Provider>(
+    provider: &P,
+    account: &PrivateKeySigner,
+    name: &str,
+    target: Address,
+) -> anyhow::Result<()> {
+    let chain_id = provider.get_chain_id().await?;
+    let mut registry = DelegateRegistry::from_path(DEFAULT_REGISTRY, Policy::Enforce)?;
+    registry.register(name, chain_id, target)?;
+    let nonce = provider.get_transaction_count(account.address()).await?;
+    let auth = registry
+        .sign_authorization(provider, account, chain_id, target, nonce)
+        .await?;
+    send_authorization(provider, account, target, auth).await
+}
+
+/// Like [`delegate`], but without the registry, for delegates a test attacks on purpose.
+pub async fn delegate_unchecked<P: Provider>(
+    provider: &P,
+    account: &PrivateKeySigner,
+    target: Address,
+) -> anyhow::Result<()> {
+    let chain_id = provider.get_chain_id().await?;
+    let nonce = provider.get_transaction_count(account.address()).await?;
+    let auth = sign_unchecked(account, chain_id, target, nonce)?;
+    send_authorization(provider, account, target, auth).await
+}
+
+async fn send_authorization<P: Provider>(
+    provider: &P,
+    account: &PrivateKeySigner,
+    target: Address,
+    auth: SignedAuthorization,
+) -> anyhow::Result<()> {
+    // The delegates may have no `receive`, so skip estimation and accept a reverted call.
+    let tx = TransactionRequest::default()
+        .with_to(account.address())
+        .with_authorization_list(vec![auth])
+        .with_gas_limit(100_000);
+    provider.send_transaction(tx).await?.get_receipt().await?;
+    let code = provider.get_code_at(account.address()).await?;
+    assert_eq!(delegated_to(&code), Some(target));
+    Ok(())
+}
+
+pub fn assert_reverts_with<T>(result: Result<T, Error>, reason: &str) {
+    match result {
+        Err(Error::Revert(err)) => assert_eq!(err.revert, Revert::Reason(reason.into()), "{err}"),
+        Err(err) => panic!("expected a revert with {reason:?}, got: {err}"),
+        Ok(_) => panic!("expected a revert with {reason:?}, but the call succeeded"),
+    }
+}
+
+pub fn assert_custom_error<T>(result: Result<T, Error>, name: &str) {
+    match result {
+        Err(Error::Revert(err)) => match &err.revert {
+            Revert::Custom { name: found, .. } => assert_eq!(found, name, "{err}"),
+            _ => panic!("expected {name}, got: {err}"),
+        },
+        Err(err) => panic!("expected {name}, got: {err}"),
+        Ok(_) => panic!("expected {name}, but the call succeeded"),
+    }
+}
@@ -0,0 +1,125 @@
+//! `DomainBatchExecutor` rejects batches signed for another chain or past their deadline.
+//!
+//! Needs `anvil` in `$PATH` and the `DomainBatchExecutor` artifact (`make contracts`):
+//!
+//! ```text
+//! cargo test --test domain_batch -- --ignored
+//! ```
+
+mod common;
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{Bytes, U256, keccak256},
+    providers::{Provider, ProviderBuilder},
+};
+use common::{ONE_ETH, assert_reverts_with, delegate, key, latest_timestamp, prague};
+use interact_eth::{
+    artifacts,
+    batch::{self, DomainBatch},
+    contracts::{BatchCallAndSponsor::Call, DomainBatchExecutor},
+    decode::AbiRegistry,
+    error::Error,
+    revert::DecodeRevert,
+};
+
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn rejects_cross_chain_replay() -> anyhow::Result<()> {
+    let (anvil_a, anvil_b) = (prague(31337)?, prague(31338)?);
+    let (alice, bob, dave) = (key(&anvil_a, 0), key(&anvil_a, 1), key(&anvil_a, 2));
+    let chain_a = ProviderBuilder::new()
+        .wallet(bob.clone())
+        .connect_http(anvil_a.endpoint_url());
+    let chain_b = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil_b.endpoint_url());
+    let abis = AbiRegistry::from_artifacts()?;
+
+    for provider in [&chain_a, &chain_b] {
+        let executor = artifacts::deploy(provider, "DomainBatchExecutor").await?;
+        delegate(provider, &alice, "DomainBatchExecutor", executor).await?;
+    }
+
+    let batch = DomainBatch {
+        chain_id: chain_a.get_chain_id().await?,
+        account: alice.address(),
+        nonce: U256::ZERO,
+        valid_until: latest_timestamp(&chain_a).await? + 3600,
+        calls: vec![Call {
+            to: dave.address(),
+            value: ONE_ETH,
+            data: Bytes::new(),
+        }],
+    };
+    let hashed = DomainBatchExecutor::new(alice.address(), &chain_a)
+        .hashCalls(batch.calls.iter().cloned().map(Into::into).collect())
+        .call()
+        .await?;
+    assert_eq!(hashed, keccak256(batch::encode_calls(&batch.calls)));
+    let signature = batch.sign(&alice)?;
+    let chain_b_id = chain_b.get_chain_id().await?;
+    let now = latest_timestamp(&chain_b).await?;
+    assert!(matches!(
+        batch.verify(&signature, chain_b_id, now),
+        Err(Error::InvalidSignature(_))
+    ));
+
+    let receipt = DomainBatchExecutor::new(alice.address(), &chain_a)
+        .call_builder(&batch.call(signature.clone()))
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    assert!(receipt.status());
+
+    let replay = DomainBatchExecutor::new(alice.address(), &chain_b)
+        .call_builder(&batch.call(signature))
+        .call()
+        .await
+        .decode_revert(&abis, "replayed batch");
+    assert_reverts_with(replay, "Invalid signature");
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn rejects_expired_batch() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob, dave) = (key(&anvil, 0), key(&anvil, 1), key(&anvil, 2));
+    let provider = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let abis = AbiRegistry::from_artifacts()?;
+
+    let executor = artifacts::deploy(&provider, "DomainBatchExecutor").await?;
+    delegate(&provider, &alice, "DomainBatchExecutor", executor).await?;
+
+    // Every new block is later than the latest one, so this deadline has already passed.
+    let now = latest_timestamp(&provider).await?;
+    let batch = DomainBatch {
+        chain_id: provider.get_chain_id().await?,
+        account: alice.address(),
+        nonce: U256::ZERO,
+        valid_until: now,
+        calls: vec![Call {
+            to: dave.address(),
+            value: ONE_ETH,
+            data: Bytes::new(),
+        }],
+    };
+    let signature = batch.sign(&alice)?;
+    assert!(matches!(
+        batch.verify(&signature, batch.chain_id, now + 1),
+        Err(Error::Expired { .. })
+    ));
+
+    let result = DomainBatchExecutor::new(alice.address(), &provider)
+        .call_builder(&batch.call(signature))
+        .block(BlockNumberOrTag::Pending.into())
+        .call()
+        .await
+        .decode_revert(&abis, "expired batch");
+    assert_reverts_with(result, "Signature expired");
+    Ok(())
+}
@@ -0,0 +1,183 @@
+//! `P256Delegate` executes batches authorized by the account's P-256 key, signed directly or
+//! through a WebAuthn assertion, and nothing else.
+//!
+//! Needs `anvil` in `$PATH` and the `P256Delegate` artifact (`make contracts`):
+//!
+//! ```text
+//! cargo test --test passkey -- --ignored
+//! ```
+
+mod common;
+
+use alloy::{
+    primitives::{Bytes, U256, keccak256},
+    providers::{Provider, ProviderBuilder},
+};
+use common::{ONE_ETH, assert_custom_error, assert_reverts_with, delegate, key, prague};
+use interact_eth::{
+    artifacts,
+    decode::AbiRegistry,
+    layout::erc7201_slot,
+    passkey::{self, Call, P256Delegate, P256Signer},
+    revert::DecodeRevert,
+};
+
+const RP_ID: &str = "wallet.example";
+const ORIGIN: &str = "https://wallet.example";
+
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn passkey_authorizes_batches() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob, dave) = (key(&anvil, 0), key(&anvil, 1), key(&anvil, 2));
+    let passkey = P256Signer::from_bytes(&keccak256("alice's passkey"))?;
+    let stranger = P256Signer::from_bytes(&keccak256("someone else"))?;
+    let sponsor = ProviderBuilder::new()
+        .wallet(bob.clone())
+        .connect_http(anvil.endpoint_url());
+    let own = ProviderBuilder::new()
+        .wallet(alice.clone())
+        .connect_http(anvil.endpoint_url());
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&passkey::abi());
+
+    let target = artifacts::deploy(&sponsor, "P256Delegate").await?;
+    delegate(&sponsor, &alice, "P256Delegate", target).await?;
+    let account = P256Delegate::new(alice.address(), &sponsor);
+
+    let pay_dave = vec![Call {
+        to: dave.address(),
+        value: ONE_ETH,
+        data: Bytes::new(),
+    }];
+    let digest = passkey::digest(anvil.chain_id(), alice.address(), U256::ZERO, &pay_dave);
+    assert_eq!(account.hashBatch(pay_dave.clone()).call().await?, digest);
+    let no_key = account
+        .call_builder(&passkey.sign_batch(
+            anvil.chain_id(),
+            alice.address(),
+            U256::ZERO,
+            &pay_dave,
+        )?)
+        .call()
+        .await
+        .decode_revert(&abis, "execute");
+    assert_custom_error(no_key, "NoPublicKey");
+
+    // Only the account itself sets its key.
+    let set_key = passkey.public_key().set_call();
+    let by_bob = account
+        .call_builder(&set_key)
+        .call()
+        .await
+        .decode_revert(&abis, "setPublicKey from Bob");
+    assert_reverts_with(by_bob, "Invalid authority");
+    P256Delegate::new(alice.address(), &own)
+        .call_builder(&set_key)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    assert_eq!(account.publicKeyX().call().await?, passkey.public_key().x);
+    assert_eq!(account.publicKeyY().call().await?, passkey.public_key().y);
+    // The key sits at the base of the ERC-7201 namespace; slots 0 and 1 stay empty.
+    let base = erc7201_slot("eip7702.p256delegate");
+    assert_eq!(
+        sponsor.get_storage_at(alice.address(), base.into()).await?,
+        U256::from_be_bytes(passkey.public_key().x.0)
+    );
+    for slot in [0, 1] {
+        assert_eq!(
+            sponsor
+                .get_storage_at(alice.address(), U256::from(slot))
+                .await?,
+            U256::ZERO
+        );
+    }
+
+    // A raw signature from the passkey pays Dave; the stranger's and a replay do not.
+    let dave_before = sponsor.get_balance(dave.address()).await?;
+    let forged = account
+        .call_builder(&stranger.sign_batch(
+            anvil.chain_id(),
+            alice.address(),
+            U256::ZERO,
+            &pay_dave,
+        )?)
+        .call()
+        .await
+        .decode_revert(&abis, "execute");
+    assert_custom_error(forged, "InvalidP256Signature");
+    let raw = passkey.sign_batch(anvil.chain_id(), alice.address(), U256::ZERO, &pay_dave)?;
+    account
+        .call_builder(&raw)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    let replay = account
+        .call_builder(&raw)
+        .call()
+        .await
+        .decode_revert(&abis, "execute");
+    assert_custom_error(replay, "InvalidP256Signature");
+
+    // A WebAuthn assertion for the next batch pays Dave again.
+    let digest = account.hashBatch(pay_dave.clone()).call().await?;
+    let response = passkey.sign_webauthn(digest, RP_ID, ORIGIN)?;
+    assert_eq!(response.challenge()?, digest);
+    let assertion = response.assertion(digest)?;
+    passkey
+        .public_key()
+        .verify(assertion.message_hash(), assertion.r, assertion.s)?;
+
+    let pay_bob = vec![Call {
+        to: bob.address(),
+        value: ONE_ETH,
+        data: Bytes::new(),
+    }];
+    let other_batch = account
+        .executeWithPasskey(pay_bob, assertion.clone())
+        .call()
+        .await
+        .decode_revert(&abis, "executeWithPasskey");
+    assert_custom_error(other_batch, "InvalidClientData");
+    let mut absent = assertion.clone();
+    let mut authenticator_data = absent.authenticatorData.to_vec();
+    authenticator_data[32] = 0;
+    absent.authenticatorData = authenticator_data.into();
+    let absent = account
+        .executeWithPasskey(pay_dave.clone(), absent)
+        .call()
+        .await
+        .decode_revert(&abis, "executeWithPasskey");
+    assert_custom_error(absent, "UserNotPresent");
+    let strangers = stranger
+        .sign_webauthn(digest, RP_ID, ORIGIN)?
+        .assertion(digest)?;
+    let strangers = account
+        .executeWithPasskey(pay_dave.clone(), strangers)
+        .call()
+        .await
+        .decode_revert(&abis, "executeWithPasskey");
+    assert_custom_error(strangers, "InvalidP256Signature");
+
+    account
+        .executeWithPasskey(pay_dave.clone(), assertion.clone())
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    assert_eq!(
+        sponsor.get_balance(dave.address()).await? - dave_before,
+        ONE_ETH * U256::from(2)
+    );
+    assert_eq!(account.nonce().call().await?, U256::from(2));
+    let replay = account
+        .executeWithPasskey(pay_dave, assertion)
+        .call()
+        .await
+        .decode_revert(&abis, "executeWithPasskey");
+    assert_custom_error(replay, "InvalidClientData");
+    Ok(())
+}
@@ -0,0 +1,364 @@
+//! `RecoveryDelegate` rotates the signing key only with enough guardian approvals, after the
+//! timelock, and unless the current key cancels first.
+//!
+//! Needs `anvil` in `$PATH` and the `RecoveryDelegate` artifact (`make contracts`):
+//!
+//! ```text
+//! cargo test --test recovery -- --ignored
+//! ```
+
+mod common;
+
+use alloy::{
+    primitives::{Address, Bytes, U256, keccak256},
+    providers::{Provider, ProviderBuilder, ext::AnvilApi},
+    signers::local::PrivateKeySigner,
+    sol_types::SolCall,
+};
+use common::{ONE_ETH, assert_custom_error, assert_reverts_with, delegate, key, prague};
+use interact_eth::{
+    artifacts,
+    decode::AbiRegistry,
+    error::{Error, Result},
+    layout::erc7201_slot,
+    recovery::{self, Call, GuardianConfig, RecoveryDelegate},
+    revert::DecodeRevert,
+    threshold::PartialSignature,
+};
+
+/// Signs `calls` with `signer` for `account` and sends them from the provider's wallet.
+async fn execute<P: Provider>(
+    provider: &P,
+    abis: &AbiRegistry,
+    signer: &PrivateKeySigner,
+    account: Address,
+    calls: Vec<Call>,
+) -> Result<()> {
+    let delegate = RecoveryDelegate::new(account, provider);
+    let nonce = delegate.nonce().call().await?;
+    let chain_id = provider.get_chain_id().await.map_err(Error::rpc)?;
+    let signature = recovery::sign(signer, chain_id, account, nonce, &calls)?;
+    let call = delegate.execute(calls, signature);
+    call.call().await.decode_revert(abis, "execute")?;
+    call.send().await?.get_receipt().await?;
+    Ok(())
+}
+
+async fn approvals<P: Provider>(
+    provider: &P,
+    account: Address,
+    new_signer: Address,
+    guardians: &[PrivateKeySigner],
+) -> anyhow::Result<Vec<PartialSignature>> {
+    let status = recovery::status(provider, account).await?;
+    let recovery = status.recovery(provider.get_chain_id().await?, account, new_signer);
+    Ok(guardians
+        .iter()
+        .map(|guardian| recovery.approve(guardian))
+        .collect::<Result<_>>()?)
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn guardians_rotate_the_signing_key() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob, dave, carol) = (
+        key(&anvil, 0),
+        key(&anvil, 1),
+        key(&anvil, 2),
+        key(&anvil, 3),
+    );
+    let mallory = key(&anvil, 7);
+    let guardians: Vec<PrivateKeySigner> = (4..7).map(|i| key(&anvil, i)).collect();
+    let sponsor = ProviderBuilder::new()
+        .wallet(bob.clone())
+        .connect_http(anvil.endpoint_url());
+    let own = ProviderBuilder::new()
+        .wallet(alice.clone())
+        .connect_http(anvil.endpoint_url());
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&recovery::abi());
+
+    let target = artifacts::deploy(&sponsor, "RecoveryDelegate").await?;
+    delegate(&sponsor, &alice, "RecoveryDelegate", target).await?;
+    let account = RecoveryDelegate::new(alice.address(), &sponsor);
+    assert_eq!(
+        account.RECOVERY().call().await?,
+        keccak256(recovery::RECOVERY_TAG)
+    );
+
+    let config = GuardianConfig {
+        guardians: guardians
+            .iter()
+            .map(|guardian| guardian.address())
+            .collect(),
+        threshold: 2,
+        delay: 86_400,
+    };
+    let no_guardians = account
+        .initiateRecovery(mallory.address(), vec![])
+        .call()
+        .await
+        .decode_revert(&abis, "initiateRecovery");
+    assert_custom_error(no_guardians, "NoGuardians");
+    let by_bob = account
+        .call_builder(&config.set_call())
+        .call()
+        .await
+        .decode_revert(&abis, "setGuardians from Bob");
+    assert_reverts_with(by_bob, "Invalid authority");
+    let instant = GuardianConfig {
+        delay: 0,
+        ..config.clone()
+    };
+    assert!(instant.check(alice.address()).is_err());
+    let no_delay = RecoveryDelegate::new(alice.address(), &own)
+        .call_builder(&instant.set_call())
+        .call()
+        .await
+        .decode_revert(&abis, "setGuardians without a delay");
+    assert_reverts_with(no_delay, "Delay too short");
+    RecoveryDelegate::new(alice.address(), &own)
+        .call_builder(&config.set_call())
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    let status = recovery::status(&sponsor, alice.address()).await?;
+    assert_eq!(status.guardians, config);
+    assert_eq!(status.signer, alice.address());
+    let recovery = status.recovery(anvil.chain_id(), alice.address(), carol.address());
+    assert_eq!(
+        account.hashRecovery(carol.address()).call().await?,
+        recovery.digest()
+    );
+
+    // One guardian, or a guardian and an outsider, cannot start a recovery.
+    let one = approvals(
+        &sponsor,
+        alice.address(),
+        mallory.address(),
+        &guardians[..1],
+    )
+    .await?;
+    let too_few = account
+        .initiateRecovery(mallory.address(), vec![one[0].signature.clone()])
+        .call()
+        .await
+        .decode_revert(&abis, "initiateRecovery");
+    assert_custom_error(too_few, "NotEnoughApprovals");
+    let mut mixed = approvals(
+        &sponsor,
+        alice.address(),
+        mallory.address(),
+        &[guardians[0].clone(), mallory.clone()],
+    )
+    .await?;
+    mixed.sort_by_key(|approval| approval.signer);
+    let outsider = account
+        .initiateRecovery(
+            mallory.address(),
+            mixed.iter().map(|a| a.signature.clone()).collect(),
+        )
+        .call()
+        .await
+        .decode_revert(&abis, "initiateRecovery");
+    assert_custom_error(outsider, "NotAGuardian");
+
+    // Two guardians start a recovery to Mallory; Alice's key cancels it.
+    let to_mallory = status.recovery(anvil.chain_id(), alice.address(), mallory.address());
+    let approved = approvals(
+        &sponsor,
+        alice.address(),
+        mallory.address(),
+        &guardians[..2],
+    )
+    .await?;
+    let initiate = to_mallory.initiate_call(&approved, &config)?;
+    account
+        .call_builder(&initiate)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    let pending = recovery::status(&sponsor, alice.address())
+        .await?
+        .pending
+        .expect("recovery pending");
+    assert_eq!(pending.new_signer, mallory.address());
+    let second = account
+        .call_builder(&initiate)
+        .call()
+        .await
+        .decode_revert(&abis, "initiateRecovery");
+    assert_custom_error(second, "RecoveryPending");
+    let cancel_by_bob = account
+        .cancelRecovery()
+        .call()
+        .await
+        .decode_revert(&abis, "cancelRecovery from Bob");
+    assert_reverts_with(cancel_by_bob, "Invalid authority");
+    let cancel = Call {
+        to: alice.address(),
+        value: U256::ZERO,
+        data: RecoveryDelegate::cancelRecoveryCall {}.abi_encode().into(),
+    };
+    execute(&sponsor, &abis, &alice, alice.address(), vec![cancel]).await?;
+    assert!(
+        recovery::status(&sponsor, alice.address())
+            .await?
+            .pending
+            .is_none()
+    );
+    // The approvals were for that attempt only.
+    let reused = account
+        .call_builder(&initiate)
+        .call()
+        .await
+        .decode_revert(&abis, "initiateRecovery");
+    assert_custom_error(reused, "NotAGuardian");
+
+    // Alice loses her key; the guardians move the account to Carol's after the timelock.
+    let approved = approvals(&sponsor, alice.address(), carol.address(), &guardians[1..]).await?;
+    let status = recovery::status(&sponsor, alice.address()).await?;
+    let initiate = status
+        .recovery(anvil.chain_id(), alice.address(), carol.address())
+        .initiate_call(&approved, &config)?;
+    account
+        .call_builder(&initiate)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    let early = account
+        .finalizeRecovery()
+        .call()
+        .await
+        .decode_revert(&abis, "finalizeRecovery");
+    assert_custom_error(early, "RecoveryTimelocked");
+
+    sponsor.anvil_increase_time(config.delay).await?;
+    sponsor.evm_mine(None).await?;
+    account
+        .finalizeRecovery()
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    let status = recovery::status(&sponsor, alice.address()).await?;
+    assert_eq!(status.signer, carol.address());
+    assert!(status.pending.is_none());
+    // The signing key follows the recovery nonce in the ERC-7201 namespace, not in slot 1.
+    let base = U256::from_be_bytes(erc7201_slot("eip7702.recoverydelegate").0);
+    let signing_key = sponsor
+        .get_storage_at(alice.address(), base + U256::from(1))
+        .await?;
+    assert_eq!(Address::from_word(signing_key.into()), carol.address());
+    assert_eq!(
+        sponsor
+            .get_storage_at(alice.address(), U256::from(1))
+            .await?,
+        U256::ZERO
+    );
+
+    let pay_dave = vec![Call {
+        to: dave.address(),
+        value: ONE_ETH,
+        data: Bytes::new(),
+    }];
+    let stale = execute(&sponsor, &abis, &alice, alice.address(), pay_dave.clone()).await;
+    assert_reverts_with(stale, "Invalid signature");
+    let dave_before = sponsor.get_balance(dave.address()).await?;
+    execute(&sponsor, &abis, &carol, alice.address(), pay_dave).await?;
+    assert_eq!(
+        sponsor.get_balance(dave.address()).await? - dave_before,
+        ONE_ETH
+    );
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn new_guardians_drop_a_pending_recovery() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob, mallory) = (key(&anvil, 0), key(&anvil, 1), key(&anvil, 7));
+    let guardians: Vec<PrivateKeySigner> = (4..7).map(|i| key(&anvil, i)).collect();
+    let sponsor = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let own = ProviderBuilder::new()
+        .wallet(alice.clone())
+        .connect_http(anvil.endpoint_url());
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&recovery::abi());
+
+    let target = artifacts::deploy(&sponsor, "RecoveryDelegate").await?;
+    delegate(&sponsor, &alice, "RecoveryDelegate", target).await?;
+    let account = RecoveryDelegate::new(alice.address(), &sponsor);
+    let config = |guardians: &[PrivateKeySigner]| GuardianConfig {
+        guardians: guardians
+            .iter()
+            .map(|guardian| guardian.address())
+            .collect(),
+        threshold: 2,
+        delay: recovery::MIN_RECOVERY_DELAY,
+    };
+    let set_guardians = |config: GuardianConfig| {
+        let own = &own;
+        let account = alice.address();
+        async move {
+            RecoveryDelegate::new(account, own)
+                .call_builder(&config.set_call())
+                .send()
+                .await?
+                .get_receipt()
+                .await?;
+            anyhow::Ok(())
+        }
+    };
+
+    let first = config(&guardians[..2]);
+    set_guardians(first.clone()).await?;
+    let approved = approvals(
+        &sponsor,
+        alice.address(),
+        mallory.address(),
+        &guardians[..2],
+    )
+    .await?;
+    let status = recovery::status(&sponsor, alice.address()).await?;
+    let initiate = status
+        .recovery(anvil.chain_id(), alice.address(), mallory.address())
+        .initiate_call(&approved, &first)?;
+    account
+        .call_builder(&initiate)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    let before = recovery::status(&sponsor, alice.address()).await?;
+    assert!(before.pending.is_some());
+
+    // Replacing a guardian the attacker controlled drops their recovery and its approvals.
+    set_guardians(config(&guardians[1..])).await?;
+    let after = recovery::status(&sponsor, alice.address()).await?;
+    assert!(after.pending.is_none());
+    assert_eq!(after.recovery_nonce, before.recovery_nonce + U256::from(1));
+    sponsor
+        .anvil_increase_time(recovery::MIN_RECOVERY_DELAY)
+        .await?;
+    sponsor.evm_mine(None).await?;
+    let finalize = account
+        .finalizeRecovery()
+        .call()
+        .await
+        .decode_revert(&abis, "finalizeRecovery");
+    assert_custom_error(finalize, "NoRecoveryPending");
+    let reused = account
+        .call_builder(&initiate)
+        .call()
+        .await
+        .decode_revert(&abis, "initiateRecovery");
+    assert_custom_error(reused, "NotAGuardian");
+    Ok(())
+}
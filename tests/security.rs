@@ -0,0 +1,307 @@
+//! Regression tests for known weaknesses of the bundled contracts.
+//!
+//! Each test runs the attack against the original contract, where it must succeed, and
+//! against its hardened variant, where it must fail. They need `anvil` in `$PATH` and the
+//! Forge artifacts of the contracts without a committed one (`make contracts`):
+//!
+//! ```text
+//! cargo test --test security -- --ignored
+//! ```
+
+mod common;
+
+use alloy::{
+    primitives::{Bytes, U256},
+    providers::{Provider, ProviderBuilder},
+};
+use common::{ONE_ETH, assert_reverts_with, delegate, delegate_unchecked, key, prague};
+use interact_eth::{
+    artifacts, batch,
+    contracts::{
+        BatchCallAndSponsor, BatchCallAndSponsorHardened, SimpleTransfer, SimpleTransferHardened,
+        SponsoredTransfer, SponsoredTransferHardened,
+    },
+    decode::AbiRegistry,
+    revert::DecodeRevert,
+    signed_transfer::{SponsoredIntent, TransferIntent},
+};
+
+/// `SimpleTransfer.transferWithSig` signs a nonce but never consumes it.
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn transfer_with_sig_replays_the_same_signature() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob, dave) = (key(&anvil, 0), key(&anvil, 1), key(&anvil, 2));
+    let provider = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let abis = AbiRegistry::from_artifacts()?;
+
+    let address = artifacts::deploy(&provider, "SimpleTransfer").await?;
+    let intent = TransferIntent {
+        from: alice.address(),
+        to: dave.address(),
+        amount: ONE_ETH,
+        nonce: U256::from(1),
+        contract: address,
+    };
+    let call = intent.call(intent.sign(&alice)?);
+    let contract = SimpleTransfer::new(address, &provider);
+    for _ in 0..2 {
+        let receipt = contract
+            .call_builder(&call)
+            .value(intent.amount)
+            .send()
+            .await?
+            .get_receipt()
+            .await?;
+        assert!(receipt.status(), "the same signed transfer executes again");
+    }
+
+    let address = artifacts::deploy(&provider, "SimpleTransferHardened").await?;
+    let intent = TransferIntent {
+        contract: address,
+        ..intent
+    };
+    let call = intent.call(intent.sign(&alice)?);
+    let contract = SimpleTransferHardened::new(address, &provider);
+    let receipt = contract
+        .call_builder(&call)
+        .value(intent.amount)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    assert!(receipt.status());
+    assert!(
+        contract
+            .nonceUsed(alice.address(), intent.nonce)
+            .call()
+            .await?
+    );
+
+    let replay = contract
+        .call_builder(&call)
+        .value(intent.amount)
+        .call()
+        .await
+        .decode_revert(&abis, "replayed transferWithSig");
+    assert_reverts_with(replay, "Nonce already used");
+    Ok(())
+}
+
+/// `transferWithSig` recovers the payer from the signature and rejects it for any other amount.
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn transfer_with_sig_recovers_the_signer_on_chain() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob, dave) = (key(&anvil, 0), key(&anvil, 1), key(&anvil, 2));
+    let provider = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let abis = AbiRegistry::from_artifacts()?;
+
+    let address = artifacts::deploy(&provider, "SimpleTransfer").await?;
+    let contract = SimpleTransfer::new(address, &provider);
+    let intent = TransferIntent {
+        from: alice.address(),
+        to: dave.address(),
+        amount: ONE_ETH,
+        nonce: U256::from(1),
+        contract: address,
+    };
+    let signature = intent.sign(&alice)?;
+    assert_eq!(intent.recover(&signature)?, alice.address());
+
+    let tampered = TransferIntent {
+        amount: ONE_ETH * U256::from(2),
+        ..intent
+    };
+    let result = contract
+        .call_builder(&tampered.call(signature.clone()))
+        .value(tampered.amount)
+        .call()
+        .await
+        .decode_revert(&abis, "tampered transferWithSig");
+    assert_reverts_with(result, "Invalid signature");
+
+    let dave_before = provider.get_balance(dave.address()).await?;
+    let receipt = contract
+        .call_builder(&intent.call(signature))
+        .value(intent.amount)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    let transfer = receipt
+        .decoded_log::<SimpleTransfer::Transfer>()
+        .ok_or_else(|| anyhow::anyhow!("no Transfer event"))?;
+    assert_eq!(transfer.from, alice.address(), "signer recovered on-chain");
+    assert_eq!(transfer.to, dave.address());
+    assert_eq!(
+        provider.get_balance(dave.address()).await? - dave_before,
+        ONE_ETH
+    );
+    Ok(())
+}
+
+/// `SponsoredTransfer.transfer` pays out of the delegated account for any caller.
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn sponsored_transfer_lets_anyone_drain_the_account() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob, dave, mallory) = (
+        key(&anvil, 0),
+        key(&anvil, 1),
+        key(&anvil, 2),
+        key(&anvil, 3),
+    );
+    let provider = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let attacker = ProviderBuilder::new()
+        .wallet(mallory.clone())
+        .connect_http(anvil.endpoint_url());
+    let abis = AbiRegistry::from_artifacts()?;
+
+    let original = SponsoredTransfer::deploy(&provider).await?;
+    delegate_unchecked(&provider, &alice, *original.address()).await?;
+    let before = provider.get_balance(alice.address()).await?;
+    let receipt = SponsoredTransfer::new(alice.address(), &attacker)
+        .transfer(alice.address(), mallory.address(), ONE_ETH)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    assert!(receipt.status(), "a stranger moves Alice's funds");
+    assert_eq!(
+        before - provider.get_balance(alice.address()).await?,
+        ONE_ETH
+    );
+
+    let hardened = artifacts::deploy(&provider, "SponsoredTransferHardened").await?;
+    delegate(&provider, &alice, "SponsoredTransferHardened", hardened).await?;
+    let chain_id = provider.get_chain_id().await?;
+    let account = SponsoredTransferHardened::new(alice.address(), &provider);
+    let intent = SponsoredIntent {
+        chain_id,
+        account: alice.address(),
+        nonce: account.nonce().call().await?,
+        to: mallory.address(),
+        amount: ONE_ETH,
+    };
+
+    // Mallory can only sign with their own key, which the account does not accept.
+    let forged = intent.call(intent.sign(&mallory)?);
+    let result = SponsoredTransferHardened::new(alice.address(), &attacker)
+        .call_builder(&forged)
+        .call()
+        .await
+        .decode_revert(&abis, "forged transfer");
+    assert_reverts_with(result, "Invalid signature");
+
+    // Alice's own signature, submitted by the sponsor, still works once.
+    let intent = SponsoredIntent {
+        to: dave.address(),
+        ..intent
+    };
+    let call = intent.call(intent.sign(&alice)?);
+    let receipt = account
+        .call_builder(&call)
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    assert!(receipt.status());
+    let replay = account
+        .call_builder(&call)
+        .call()
+        .await
+        .decode_revert(&abis, "replayed transfer");
+    assert_reverts_with(replay, "Invalid signature");
+    Ok(())
+}
+
+/// The `BatchCallAndSponsor` digest binds neither the chain nor the account, so a batch
+/// signed for one chain executes on every chain where the account has the same nonce.
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn batch_signature_replays_on_another_chain() -> anyhow::Result<()> {
+    let (anvil_a, anvil_b) = (prague(31337)?, prague(31338)?);
+    // Both nodes derive the same accounts from the default mnemonic.
+    let (alice, bob, dave, mallory) = (
+        key(&anvil_a, 0),
+        key(&anvil_a, 1),
+        key(&anvil_a, 2),
+        key(&anvil_a, 3),
+    );
+    let chain_a = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil_a.endpoint_url());
+    let chain_b = ProviderBuilder::new()
+        .wallet(mallory)
+        .connect_http(anvil_b.endpoint_url());
+    let abis = AbiRegistry::from_artifacts()?;
+
+    let calls = vec![BatchCallAndSponsor::Call {
+        to: dave.address(),
+        value: ONE_ETH,
+        data: Bytes::new(),
+    }];
+
+    for provider in [&chain_a, &chain_b] {
+        let executor = BatchCallAndSponsor::deploy(provider).await?;
+        delegate(provider, &alice, "BatchCallAndSponsor", *executor.address()).await?;
+    }
+    let signature = batch::sign(&alice, batch::digest(U256::ZERO, &calls))?;
+    for provider in [&chain_a, &chain_b] {
+        let before = provider.get_balance(dave.address()).await?;
+        let receipt = BatchCallAndSponsor::new(alice.address(), provider)
+            .execute_1(calls.clone(), signature.clone())
+            .send()
+            .await?
+            .get_receipt()
+            .await?;
+        assert!(receipt.status(), "the batch executes on both chains");
+        assert_eq!(
+            provider.get_balance(dave.address()).await? - before,
+            ONE_ETH
+        );
+    }
+
+    let mut nonces = Vec::new();
+    for provider in [&chain_a, &chain_b] {
+        let executor = artifacts::deploy(provider, "BatchCallAndSponsorHardened").await?;
+        delegate(provider, &alice, "BatchCallAndSponsorHardened", executor).await?;
+        nonces.push(
+            BatchCallAndSponsorHardened::new(alice.address(), provider)
+                .nonce()
+                .call()
+                .await?,
+        );
+    }
+    assert_eq!(
+        nonces[0], nonces[1],
+        "only the chain id tells the chains apart"
+    );
+
+    let chain_id = chain_a.get_chain_id().await?;
+    let digest = batch::bound_digest(chain_id, alice.address(), nonces[0], &calls);
+    let signature = batch::sign(&alice, digest)?;
+    let calls: Vec<BatchCallAndSponsorHardened::Call> = calls.into_iter().map(Into::into).collect();
+
+    let receipt = BatchCallAndSponsorHardened::new(alice.address(), &chain_a)
+        .execute_1(calls.clone(), signature.clone())
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    assert!(receipt.status());
+    let replay = BatchCallAndSponsorHardened::new(alice.address(), &chain_b)
+        .execute_1(calls, signature)
+        .call()
+        .await
+        .decode_revert(&abis, "replayed batch");
+    assert_reverts_with(replay, "Invalid signature");
+    Ok(())
+}
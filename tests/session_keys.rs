@@ -0,0 +1,242 @@
+//! Session keys on `SessionKeyDelegate` are held to their targets, selectors, value limit and
+//! expiry, and stop working once revoked.
+//!
+//! Needs `anvil` in `$PATH` and the `SessionKeyDelegate` artifact (`make contracts`):
+//!
+//! ```text
+//! cargo test --test session_keys -- --ignored
+//! ```
+
+mod common;
+
+use alloy::{
+    primitives::{Address, Bytes, U256, keccak256},
+    providers::{Provider, ProviderBuilder, ext::AnvilApi},
+    signers::local::PrivateKeySigner,
+    sol_types::SolCall,
+};
+use common::{
+    ONE_ETH, assert_custom_error, assert_reverts_with, delegate, key, latest_timestamp, prague,
+};
+use interact_eth::{
+    artifacts,
+    contracts::EventLogger,
+    decode::AbiRegistry,
+    error::{Error, Result},
+    layout::erc7201_slot,
+    revert::DecodeRevert,
+    session_keys::{self, Call, NO_SELECTOR, SessionKeyDelegate, SessionPolicy},
+};
+
+/// Signs `calls` with `key` for `account` and sends them from the provider's wallet.
+async fn execute<P: Provider>(
+    provider: &P,
+    abis: &AbiRegistry,
+    key: &PrivateKeySigner,
+    account: Address,
+    calls: Vec<Call>,
+) -> Result<()> {
+    let delegate = SessionKeyDelegate::new(account, provider);
+    let nonce = delegate.nonce().call().await?;
+    let chain_id = provider.get_chain_id().await.map_err(Error::rpc)?;
+    let signature = session_keys::sign(key, chain_id, account, nonce, &calls)?;
+    let call = delegate.executeWithSessionKey(calls, signature);
+    call.call()
+        .await
+        .decode_revert(abis, "executeWithSessionKey")?;
+    call.send().await?.get_receipt().await?;
+    Ok(())
+}
+
+fn pay(to: Address, value: U256) -> Call {
+    Call {
+        to,
+        value,
+        data: Bytes::new(),
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn session_key_stays_within_its_policy() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob, dave, carol) = (
+        key(&anvil, 0),
+        key(&anvil, 1),
+        key(&anvil, 2),
+        key(&anvil, 3),
+    );
+    let sponsor = ProviderBuilder::new()
+        .wallet(bob.clone())
+        .connect_http(anvil.endpoint_url());
+    let owner = ProviderBuilder::new()
+        .wallet(alice.clone())
+        .connect_http(anvil.endpoint_url());
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&session_keys::abi());
+
+    let target = artifacts::deploy(&sponsor, "SessionKeyDelegate").await?;
+    delegate(&sponsor, &alice, "SessionKeyDelegate", target).await?;
+    let logger = *EventLogger::deploy(&sponsor).await?.address();
+
+    let policy = SessionPolicy {
+        key: carol.address(),
+        valid_until: latest_timestamp(&sponsor).await? + 3600,
+        value_limit: ONE_ETH,
+        targets: vec![dave.address(), logger],
+        selectors: vec![NO_SELECTOR, EventLogger::emitHelloCall::SELECTOR.into()],
+    };
+    let account = SessionKeyDelegate::new(alice.address(), &owner);
+    account
+        .call_builder(&policy.issue_call())
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+
+    // Nobody but the account can issue keys, not even through its own batch.
+    let issued_by_bob = SessionKeyDelegate::new(alice.address(), &sponsor)
+        .call_builder(&policy.issue_call())
+        .call()
+        .await
+        .decode_revert(&abis, "addSessionKey from Bob");
+    assert_reverts_with(issued_by_bob, "Invalid authority");
+    let self_call = Call {
+        to: alice.address(),
+        value: U256::ZERO,
+        data: policy.issue_call().abi_encode().into(),
+    };
+    assert!(matches!(
+        policy.check(
+            alice.address(),
+            std::slice::from_ref(&self_call),
+            U256::ZERO,
+            0
+        ),
+        Err(Error::NotPermitted(_))
+    ));
+    assert_custom_error(
+        execute(&sponsor, &abis, &carol, alice.address(), vec![self_call]).await,
+        "TargetNotAllowed",
+    );
+
+    // In scope: half the allowance to Dave and a call to the logger.
+    let hello = Call {
+        to: logger,
+        value: U256::ZERO,
+        data: EventLogger::emitHelloCall {}.abi_encode().into(),
+    };
+    let half = ONE_ETH / U256::from(2);
+    let dave_before = sponsor.get_balance(dave.address()).await?;
+    let calls = vec![pay(dave.address(), half), hello.clone()];
+    policy.check(
+        alice.address(),
+        &calls,
+        U256::ZERO,
+        latest_timestamp(&sponsor).await?,
+    )?;
+    execute(&sponsor, &abis, &carol, alice.address(), calls).await?;
+    assert_eq!(
+        sponsor.get_balance(dave.address()).await? - dave_before,
+        half
+    );
+
+    // The nonce sits at the base of the shared executor's ERC-7201 namespace, not in slot 0, and
+    // the digest is tagged with what `session_keys::digest` hashes.
+    let base = erc7201_slot("eip7702.signedbatchexecutor");
+    assert_eq!(
+        sponsor.get_storage_at(alice.address(), base.into()).await?,
+        U256::from(1)
+    );
+    assert_eq!(
+        sponsor.get_storage_at(alice.address(), U256::ZERO).await?,
+        U256::ZERO
+    );
+    assert_eq!(
+        account.BATCH().call().await?,
+        keccak256(session_keys::BATCH_TAG)
+    );
+
+    let listed = session_keys::list(&sponsor, alice.address()).await?;
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].policy, policy);
+    assert_eq!(listed[0].value_spent, half);
+
+    // Out of scope: another target, another selector, more than what is left.
+    let to_bob = vec![pay(bob.address(), U256::from(1))];
+    assert!(matches!(
+        policy.check(alice.address(), &to_bob, half, 0),
+        Err(Error::NotPermitted(_))
+    ));
+    assert_custom_error(
+        execute(&sponsor, &abis, &carol, alice.address(), to_bob).await,
+        "TargetNotAllowed",
+    );
+    let other_selector = vec![Call {
+        to: logger,
+        value: U256::ZERO,
+        data: EventLogger::emitWorldCall {
+            world_name: "Carol".into(),
+        }
+        .abi_encode()
+        .into(),
+    }];
+    assert_custom_error(
+        execute(&sponsor, &abis, &carol, alice.address(), other_selector).await,
+        "SelectorNotAllowed",
+    );
+    let over_limit = vec![pay(dave.address(), half + U256::from(1))];
+    assert!(policy.check(alice.address(), &over_limit, half, 0).is_err());
+    assert_custom_error(
+        execute(&sponsor, &abis, &carol, alice.address(), over_limit).await,
+        "ValueLimitExceeded",
+    );
+
+    // A key that was never issued.
+    assert_custom_error(
+        execute(&sponsor, &abis, &dave, alice.address(), vec![hello.clone()]).await,
+        "UnknownSessionKey",
+    );
+
+    // Past its expiry.
+    sponsor.anvil_increase_time(7200).await?;
+    sponsor.evm_mine(None).await?;
+    assert!(matches!(
+        policy.check(
+            alice.address(),
+            std::slice::from_ref(&hello),
+            half,
+            latest_timestamp(&sponsor).await?
+        ),
+        Err(Error::Expired { .. })
+    ));
+    assert_custom_error(
+        execute(
+            &sponsor,
+            &abis,
+            &carol,
+            alice.address(),
+            vec![hello.clone()],
+        )
+        .await,
+        "SessionKeyExpired",
+    );
+
+    // Revoked.
+    account
+        .call_builder(&session_keys::revoke_call(carol.address()))
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    assert!(
+        session_keys::list(&sponsor, alice.address())
+            .await?
+            .is_empty()
+    );
+    assert_custom_error(
+        execute(&sponsor, &abis, &carol, alice.address(), vec![hello]).await,
+        "UnknownSessionKey",
+    );
+    Ok(())
+}
@@ -0,0 +1,203 @@
+//! `ThresholdDelegate` only executes batches signed by enough distinct owners, in signer order.
+//!
+//! Needs `anvil` in `$PATH` and the `ThresholdDelegate` artifact (`make contracts`):
+//!
+//! ```text
+//! cargo test --test threshold -- --ignored
+//! ```
+
+mod common;
+
+use alloy::{
+    primitives::{Bytes, U256},
+    providers::{Provider, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+    sol_types::SolCall,
+};
+use common::{ONE_ETH, assert_custom_error, assert_reverts_with, delegate, key, prague};
+use interact_eth::{
+    artifacts,
+    decode::AbiRegistry,
+    layout::erc7201_slot,
+    revert::DecodeRevert,
+    threshold::{self, Call, Owners, ThresholdBatch, ThresholdDelegate},
+};
+
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn executes_with_enough_owner_signatures() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (treasury, bob, dave) = (key(&anvil, 0), key(&anvil, 1), key(&anvil, 2));
+    let signers: Vec<PrivateKeySigner> = (4..8).map(|i| key(&anvil, i)).collect();
+    let sponsor = ProviderBuilder::new()
+        .wallet(bob.clone())
+        .connect_http(anvil.endpoint_url());
+    let own = ProviderBuilder::new()
+        .wallet(treasury.clone())
+        .connect_http(anvil.endpoint_url());
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&threshold::abi());
+
+    let target = artifacts::deploy(&sponsor, "ThresholdDelegate").await?;
+    delegate(&sponsor, &treasury, "ThresholdDelegate", target).await?;
+    let account = ThresholdDelegate::new(treasury.address(), &sponsor);
+
+    // Nothing executes before owners are set, and only the account can set them.
+    let batch = ThresholdBatch {
+        chain_id: anvil.chain_id(),
+        account: treasury.address(),
+        nonce: U256::ZERO,
+        calls: vec![Call {
+            to: dave.address(),
+            value: ONE_ETH,
+            data: Bytes::new(),
+        }],
+    };
+    let unconfigured = account
+        .call_builder(&batch.call(vec![]))
+        .call()
+        .await
+        .decode_revert(&abis, "execute");
+    assert_custom_error(unconfigured, "NotConfigured");
+
+    let owners = Owners {
+        owners: signers[..3].iter().map(|owner| owner.address()).collect(),
+        threshold: 2,
+    };
+    let by_bob = account
+        .call_builder(&owners.set_call())
+        .call()
+        .await
+        .decode_revert(&abis, "setOwners from Bob");
+    assert_reverts_with(by_bob, "Invalid authority");
+    ThresholdDelegate::new(treasury.address(), &own)
+        .call_builder(&owners.set_call())
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    assert_eq!(
+        threshold::owners(&sponsor, treasury.address()).await?,
+        owners
+    );
+    assert_eq!(
+        account.hashBatch(batch.calls.clone()).call().await?,
+        batch.digest()
+    );
+    // The threshold sits at the base of the ERC-7201 namespace, not in slot 0.
+    let base = erc7201_slot("eip7702.thresholddelegate");
+    assert_eq!(
+        sponsor
+            .get_storage_at(treasury.address(), base.into())
+            .await?,
+        U256::from(2)
+    );
+    assert_eq!(
+        sponsor
+            .get_storage_at(treasury.address(), U256::ZERO)
+            .await?,
+        U256::ZERO
+    );
+
+    let partials: Vec<_> = signers
+        .iter()
+        .map(|signer| batch.sign(signer))
+        .collect::<Result<_, _>>()?;
+    let mut ordered = partials[..2].to_vec();
+    ordered.sort_by_key(|partial| partial.signer);
+    let signatures = |partials: &[threshold::PartialSignature]| {
+        partials
+            .iter()
+            .map(|partial| partial.signature.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let too_few = account
+        .call_builder(&batch.call(signatures(&ordered[..1])))
+        .call()
+        .await
+        .decode_revert(&abis, "execute");
+    assert_custom_error(too_few, "NotEnoughSignatures");
+    let reversed: Vec<_> = ordered.iter().rev().cloned().collect();
+    let unordered = account
+        .call_builder(&batch.call(signatures(&reversed)))
+        .call()
+        .await
+        .decode_revert(&abis, "execute");
+    assert_custom_error(unordered, "SignersNotAscending");
+    let repeated = [ordered[0].clone(), ordered[0].clone()];
+    let twice = account
+        .call_builder(&batch.call(signatures(&repeated)))
+        .call()
+        .await
+        .decode_revert(&abis, "execute");
+    assert_custom_error(twice, "SignersNotAscending");
+    let mut outsider = vec![partials[0].clone(), partials[3].clone()];
+    outsider.sort_by_key(|partial| partial.signer);
+    let not_owner = account
+        .call_builder(&batch.call(signatures(&outsider)))
+        .call()
+        .await
+        .decode_revert(&abis, "execute");
+    assert_custom_error(not_owner, "NotAnOwner");
+    assert!(threshold::aggregate(&batch, &outsider, &owners).is_err());
+
+    // Aggregated in any order, two owners are enough; the sponsor pays the gas.
+    let collected = [partials[1].clone(), partials[0].clone()];
+    let aggregated = threshold::aggregate(&batch, &collected, &owners)?;
+    let dave_before = sponsor.get_balance(dave.address()).await?;
+    let treasury_before = sponsor.get_balance(treasury.address()).await?;
+    let receipt = account
+        .call_builder(&batch.call(aggregated.clone()))
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    assert!(receipt.status());
+    assert_eq!(
+        sponsor.get_balance(dave.address()).await? - dave_before,
+        ONE_ETH
+    );
+    assert_eq!(
+        treasury_before - sponsor.get_balance(treasury.address()).await?,
+        ONE_ETH
+    );
+
+    let replay = account
+        .call_builder(&batch.call(aggregated))
+        .call()
+        .await
+        .decode_revert(&abis, "replayed execute");
+    assert_custom_error(replay, "NotAnOwner");
+
+    // The owners rotate themselves with a threshold-signed call to the account.
+    let rotated = Owners {
+        owners: signers[1..].iter().map(|owner| owner.address()).collect(),
+        threshold: 3,
+    };
+    let rotation = ThresholdBatch {
+        nonce: U256::from(1),
+        calls: vec![Call {
+            to: treasury.address(),
+            value: U256::ZERO,
+            data: rotated.set_call().abi_encode().into(),
+        }],
+        ..batch
+    };
+    let partials: Vec<_> = signers[..2]
+        .iter()
+        .map(|signer| rotation.sign(signer))
+        .collect::<Result<_, _>>()?;
+    let aggregated = threshold::aggregate(&rotation, &partials, &owners)?;
+    account
+        .call_builder(&rotation.call(aggregated))
+        .send()
+        .await?
+        .get_receipt()
+        .await?;
+    assert_eq!(
+        threshold::owners(&sponsor, treasury.address()).await?,
+        rotated
+    );
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! `trace::call_tree` reports the delegation each transaction ran with, even when the
+//! delegation changes between transactions of the same block.
+//!
+//! Needs `anvil` in `$PATH`:
+//!
+//! ```text
+//! cargo test --test trace -- --ignored
+//! ```
+
+mod common;
+
+use alloy::{
+    network::{TransactionBuilder, TransactionBuilder7702},
+    primitives::TxHash,
+    providers::{Provider, ProviderBuilder, ext::AnvilApi},
+    rpc::types::TransactionRequest,
+    sol_types::SolCall,
+};
+use common::{key, prague};
+use interact_eth::{authorization::sign_unchecked, contracts::EventLogger, trace::call_tree};
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn resolves_delegations_changed_within_a_block() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob) = (key(&anvil, 0), key(&anvil, 1));
+    let provider = ProviderBuilder::new()
+        .wallet(bob.clone())
+        .connect_http(anvil.endpoint_url());
+    let first = *EventLogger::deploy(&provider).await?.address();
+    let second = *EventLogger::deploy(&provider).await?.address();
+    let chain_id = anvil.chain_id();
+
+    // Bob calls Alice three times in one block. The first call points her at `first`, the
+    // second carries an authorization with a stale nonce that the EVM skips, and the third
+    // points her at `second`.
+    let authorizations = [
+        sign_unchecked(&alice, chain_id, first, 0)?,
+        sign_unchecked(&alice, chain_id, second, 0)?,
+        sign_unchecked(&alice, chain_id, second, 1)?,
+    ];
+    let nonce = provider.get_transaction_count(bob.address()).await?;
+    provider.anvil_set_auto_mine(false).await?;
+    let mut hashes: Vec<TxHash> = Vec::new();
+    for (i, auth) in authorizations.into_iter().enumerate() {
+        let tx = TransactionRequest::default()
+            .with_to(alice.address())
+            .with_input(EventLogger::emitHelloCall {}.abi_encode())
+            .with_authorization_list(vec![auth])
+            .with_nonce(nonce + i as u64)
+            .with_gas_limit(200_000);
+        hashes.push(*provider.send_transaction(tx).await?.tx_hash());
+    }
+    provider.anvil_mine(Some(1), None).await?;
+
+    let mut blocks = Vec::new();
+    for hash in &hashes {
+        let receipt = provider
+            .get_transaction_receipt(*hash)
+            .await?
+            .expect("mined");
+        assert!(receipt.status());
+        blocks.push(receipt.block_number);
+    }
+    assert!(blocks.iter().all(|block| *block == blocks[0]), "{blocks:?}");
+
+    let mut delegates = Vec::new();
+    for hash in hashes {
+        let tree = call_tree(&provider, hash).await?;
+        delegates.push(tree.delegations.get(&alice.address()).copied());
+    }
+    assert_eq!(delegates, [Some(first), Some(first), Some(second)]);
+    Ok(())
+}
@@ -0,0 +1,223 @@
+//! User operations from an EIP-7702 account, bundled with `handleOps` by the local bundler, and
+//! `UserOperation::hash` checked against the canonical v0.8 EntryPoint.
+//!
+//! The bundler tests need `anvil` in `$PATH` and the `MinimalEntryPoint` and `Simple7702Account`
+//! artifacts (`make contracts`). The hash test forks a chain where the EntryPoint is deployed at
+//! `ENTRY_POINT_V08`, so it also needs the RPC URL of such a chain, e.g. Sepolia or mainnet:
+//!
+//! ```text
+//! cargo test --test user_operation -- --ignored --skip hashes_like
+//! FORK_URL=<rpc-url> cargo test --test user_operation hashes_like -- --ignored
+//! ```
+
+mod common;
+
+use alloy::{
+    node_bindings::{Anvil, AnvilInstance},
+    primitives::{Address, Bytes, U256, address, aliases::U192},
+    providers::{Provider, ProviderBuilder, ext::AnvilApi},
+    signers::local::PrivateKeySigner,
+    sol_types::{SolCall, SolValue},
+};
+use common::{ONE_ETH, key, prague};
+use interact_eth::{
+    artifacts,
+    authorization::{DELEGATION_PREFIX, sign_unchecked},
+    contracts::EventLogger,
+    decode::AbiRegistry,
+    registry::{DEFAULT_REGISTRY, DelegateRegistry, Policy},
+    user_operation::{
+        self, Bundler, ENTRY_POINT_V08, EntryPoint, Simple7702Account, Simple7702Account::Call,
+        UserOperation,
+    },
+};
+
+/// Deploys the EntryPoint, the account delegate and a logger, and returns their addresses.
+async fn deploy<P: Provider>(provider: &P) -> anyhow::Result<(Address, Address, Address)> {
+    let entry_point = artifacts::deploy(provider, "MinimalEntryPoint").await?;
+    let account =
+        artifacts::deploy_with_args(provider, "Simple7702Account", &entry_point.abi_encode())
+            .await?;
+    let logger = *EventLogger::deploy(provider).await?.address();
+    Ok((entry_point, account, logger))
+}
+
+/// An operation of Alice's that pays Dave one ether and emits `Hello`, signed by `signer`.
+async fn pay_dave<P: Provider>(
+    provider: &P,
+    anvil: &AnvilInstance,
+    entry_point: Address,
+    logger: Address,
+    signer: &PrivateKeySigner,
+) -> anyhow::Result<UserOperation> {
+    let (alice, dave) = (key(anvil, 0), key(anvil, 2));
+    let call_data = Simple7702Account::executeBatchCall {
+        calls: vec![
+            Call {
+                to: dave.address(),
+                value: ONE_ETH,
+                data: Bytes::new(),
+            },
+            Call {
+                to: logger,
+                value: U256::ZERO,
+                data: EventLogger::emitHelloCall {}.abi_encode().into(),
+            },
+        ],
+    }
+    .abi_encode();
+    let nonce = EntryPoint::new(entry_point, provider)
+        .getNonce(alice.address(), U192::ZERO)
+        .call()
+        .await?;
+    let fees = provider.estimate_eip1559_fees().await?;
+    let mut op = UserOperation::new(alice.address(), nonce, call_data.into());
+    op.max_fee_per_gas = fees.max_fee_per_gas;
+    op.max_priority_fee_per_gas = fees.max_priority_fee_per_gas;
+    op.signature = op.sign(signer, entry_point, anvil.chain_id())?;
+    Ok(op)
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn bundles_a_delegation_and_drops_invalid_ops() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob, dave, mallory) = (
+        key(&anvil, 0),
+        key(&anvil, 1),
+        key(&anvil, 2),
+        key(&anvil, 3),
+    );
+    let provider = ProviderBuilder::new()
+        .wallet(bob.clone())
+        .connect_http(anvil.endpoint_url());
+    let chain_id = anvil.chain_id();
+    let (entry_point, account, logger) = deploy(&provider).await?;
+    let mut registry = DelegateRegistry::from_path(DEFAULT_REGISTRY, Policy::Enforce)?;
+    registry.register("Simple7702Account", chain_id, account)?;
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&user_operation::abi());
+
+    // The first operation delegates Alice inside `handleOps`.
+    let mut op = pay_dave(&provider, &anvil, entry_point, logger, &alice).await?;
+    let nonce = provider.get_transaction_count(alice.address()).await?;
+    op.authorization = Some(
+        registry
+            .sign_authorization(&provider, &alice, chain_id, account, nonce)
+            .await?,
+    );
+    op.signature = op.sign(&alice, entry_point, chain_id)?;
+    let mut bundler = Bundler::new(entry_point, bob.address());
+    let dave_before = provider.get_balance(dave.address()).await?;
+    bundler.add(op);
+    let bundle = bundler.bundle(&provider, &abis).await?;
+    let receipt = bundle.receipt.expect("the operation is bundled");
+    assert!(receipt.status());
+    assert!(bundle.dropped.is_empty(), "{:?}", bundle.dropped);
+    assert!(bundle.results.len() == 1 && bundle.results[0].success);
+    assert_eq!(
+        provider.get_balance(dave.address()).await? - dave_before,
+        ONE_ETH
+    );
+    assert!(bundler.pending.is_empty());
+
+    // Alice is delegated now, so her next operation needs no authorization. Mallory's copy of
+    // it is signed with the wrong key and fails validation with AA24.
+    let op = pay_dave(&provider, &anvil, entry_point, logger, &alice).await?;
+    let mut forged = op.clone();
+    forged.signature = forged.sign(&mallory, entry_point, chain_id)?;
+    bundler.add(forged);
+    bundler.add(op);
+    let bundle = bundler.bundle(&provider, &abis).await?;
+    assert_eq!(bundle.dropped.len(), 1);
+    assert!(
+        bundle.dropped[0].revert.to_string().contains("AA24"),
+        "{}",
+        bundle.dropped[0].revert
+    );
+    assert!(bundle.results.len() == 1 && bundle.results[0].success);
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn keeps_pending_ops_when_bundling_fails() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob) = (key(&anvil, 0), key(&anvil, 1));
+    let provider = ProviderBuilder::new()
+        .wallet(bob.clone())
+        .connect_http(anvil.endpoint_url());
+    let (entry_point, account, logger) = deploy(&provider).await?;
+    provider
+        .anvil_set_code(
+            alice.address(),
+            [&DELEGATION_PREFIX[..], account.as_slice()].concat().into(),
+        )
+        .await?;
+    let mut abis = AbiRegistry::from_artifacts()?;
+    abis.add(&user_operation::abi());
+
+    // A bundler key without ether cannot pay for `handleOps`.
+    let broke = PrivateKeySigner::random();
+    let broke = ProviderBuilder::new()
+        .wallet(broke)
+        .connect_http(anvil.endpoint_url());
+    let mut bundler = Bundler::new(entry_point, bob.address());
+    bundler.add(pay_dave(&provider, &anvil, entry_point, logger, &alice).await?);
+    assert!(bundler.bundle(&broke, &abis).await.is_err());
+    assert_eq!(bundler.pending.len(), 1);
+
+    let bundle = bundler.bundle(&provider, &abis).await?;
+    assert!(bundle.results.len() == 1 && bundle.results[0].success);
+    assert!(bundler.pending.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs anvil, `make contracts` and FORK_URL"]
+async fn hashes_like_the_canonical_entry_point() -> anyhow::Result<()> {
+    let fork_url = std::env::var("FORK_URL")?;
+    let anvil = Anvil::new()
+        .fork(fork_url)
+        .arg("--hardfork")
+        .arg("prague")
+        .try_spawn()?;
+    let alice = key(&anvil, 0);
+    let provider = ProviderBuilder::new()
+        .wallet(key(&anvil, 1))
+        .connect_http(anvil.endpoint_url());
+    let chain_id = provider.get_chain_id().await?;
+    anyhow::ensure!(
+        !provider.get_code_at(ENTRY_POINT_V08).await?.is_empty(),
+        "no EntryPoint v0.8 at {ENTRY_POINT_V08} on the forked chain"
+    );
+    let canonical = EntryPoint::new(ENTRY_POINT_V08, &provider);
+    let minimal = artifacts::deploy(&provider, "MinimalEntryPoint").await?;
+    let minimal = EntryPoint::new(minimal, &provider);
+
+    let mut op = UserOperation::new(alice.address(), U256::from(5), Bytes::from(vec![1, 2]));
+    op.max_fee_per_gas = 3;
+    op.max_priority_fee_per_gas = 2;
+    let hash = canonical.getUserOpHash(op.pack()).call().await?;
+    assert_eq!(op.hash(ENTRY_POINT_V08, chain_id), hash);
+    assert_eq!(
+        op.hash(*minimal.address(), chain_id),
+        minimal.getUserOpHash(op.pack()).call().await?
+    );
+
+    // With the `0x7702` initCode both EntryPoints hash the delegate they read from the sender's
+    // code. The authorization is only hashed, never submitted.
+    let delegate = address!("0x00000000000000000000000000000000000000d1");
+    op.authorization = Some(sign_unchecked(&alice, chain_id, delegate, 0)?);
+    let code: Bytes = [&DELEGATION_PREFIX[..], delegate.as_slice()]
+        .concat()
+        .into();
+    provider.anvil_set_code(alice.address(), code).await?;
+    let hash = canonical.getUserOpHash(op.pack()).call().await?;
+    assert_eq!(op.hash(ENTRY_POINT_V08, chain_id), hash);
+    assert_eq!(
+        op.hash(*minimal.address(), chain_id),
+        minimal.getUserOpHash(op.pack()).call().await?
+    );
+    Ok(())
+}
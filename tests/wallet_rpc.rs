@@ -0,0 +1,275 @@
+//! The EIP-5792 wallet server sponsors batches only for account-bound executors, reads the
+//! executor nonce the account really has, and answers only requests with its bearer token.
+//!
+//! Needs `anvil` in `$PATH` and the `BatchCallAndSponsorHardened` artifact (`make contracts`):
+//!
+//! ```text
+//! cargo test --test wallet_rpc -- --ignored
+//! ```
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use alloy::{
+    network::{EthereumWallet, TransactionBuilder, TransactionBuilder7702},
+    node_bindings::AnvilInstance,
+    primitives::{Address, U256},
+    providers::{Provider, ProviderBuilder, RootProvider},
+    rpc::types::TransactionRequest,
+    sol_types::SolCall,
+};
+use common::{ONE_ETH, key, prague};
+use interact_eth::{
+    artifacts,
+    authorization::sign_revocation,
+    batch::Executor,
+    contracts::{BatchCallAndSponsor, BatchCallAndSponsorHardened, EventLogger},
+    registry::{DEFAULT_REGISTRY, DelegateRegistry, Policy},
+    wallet_rpc::{self, WalletConfig, WalletError, WalletRpc},
+};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+
+const TOKEN: &str = "test-token";
+
+/// Serves Alice's account with Bob as the sponsor and returns the server's URL.
+async fn serve(
+    anvil: &AnvilInstance,
+    name: &str,
+    delegate: Address,
+    executor: Executor,
+) -> anyhow::Result<String> {
+    let (alice, bob) = (key(anvil, 0), key(anvil, 1));
+    let mut wallet = EthereumWallet::new(bob.clone());
+    wallet.register_signer(alice.clone());
+    let provider = ProviderBuilder::new()
+        .wallet(wallet)
+        .connect_http(anvil.endpoint_url());
+    let mut registry = DelegateRegistry::from_path(DEFAULT_REGISTRY, Policy::Enforce)?;
+    registry.register(name, anvil.chain_id(), delegate)?;
+    let config = WalletConfig {
+        chain_id: anvil.chain_id(),
+        delegate,
+        executor,
+        accounts: vec![alice],
+        sponsor: Some(bob),
+        registry,
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}", listener.local_addr()?);
+    tokio::spawn(wallet_rpc::serve(
+        Arc::new(WalletRpc::new(config, provider)),
+        listener,
+        TOKEN.into(),
+    ));
+    Ok(url)
+}
+
+async fn capabilities(
+    dapp: &RootProvider,
+    account: Address,
+    chain_id: &str,
+) -> anyhow::Result<Value> {
+    let capabilities: Value = dapp
+        .raw_request("wallet_getCapabilities".into(), (account, [chain_id]))
+        .await?;
+    Ok(capabilities[chain_id].clone())
+}
+
+/// Sends a sponsored batch paying Dave and waits for its status.
+async fn send_sponsored(
+    dapp: &RootProvider,
+    from: Address,
+    chain_id: &str,
+    calls: Value,
+) -> anyhow::Result<Value> {
+    let sent: Value = dapp
+        .raw_request(
+            "wallet_sendCalls".into(),
+            [json!({
+                "version": wallet_rpc::VERSION,
+                "from": from,
+                "chainId": chain_id,
+                "atomicRequired": true,
+                "calls": calls,
+                "capabilities": {
+                    "paymasterService": { "url": "https://paymaster.invalid" }
+                },
+            })],
+        )
+        .await?;
+    let id = sent["id"].as_str().unwrap_or_default().to_string();
+    loop {
+        let status: Value = dapp
+            .raw_request("wallet_getCallsStatus".into(), [&id])
+            .await?;
+        if status["status"] != 100 {
+            return Ok(status);
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn sponsors_a_batch_through_the_server() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob, dave) = (key(&anvil, 0), key(&anvil, 1), key(&anvil, 2));
+    let provider = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let delegate = artifacts::deploy(&provider, "BatchCallAndSponsorHardened").await?;
+    let logger = *EventLogger::deploy(&provider).await?.address();
+    let url = serve(
+        &anvil,
+        "BatchCallAndSponsorHardened",
+        delegate,
+        Executor::BatchCallAndSponsorHardened,
+    )
+    .await?;
+    let chain_id = format!("{:#x}", anvil.chain_id());
+
+    // Without the token the server answers nothing.
+    let stranger = ProviderBuilder::new().connect_http(url.parse()?);
+    let refused: Result<Value, _> = stranger
+        .raw_request(
+            "wallet_getCapabilities".into(),
+            (alice.address(), [&chain_id]),
+        )
+        .await;
+    assert!(refused.is_err(), "requests without the token are refused");
+    let wrong = wallet_rpc::connect(url.parse()?, "wrong-token")?;
+    let refused: Result<Value, _> = wrong.raw_request("eth_chainId".into(), ()).await;
+    assert!(refused.is_err(), "requests with another token are refused");
+
+    let dapp = wallet_rpc::connect(url.parse()?, TOKEN)?;
+    let before = capabilities(&dapp, alice.address(), &chain_id).await?;
+    assert_eq!(before["atomic"]["status"], "ready");
+    assert_eq!(before["paymasterService"]["supported"], true);
+
+    let dave_before = provider.get_balance(dave.address()).await?;
+    let alice_before = provider.get_balance(alice.address()).await?;
+    let status = send_sponsored(
+        &dapp,
+        alice.address(),
+        &chain_id,
+        json!([
+            { "to": dave.address(), "value": ONE_ETH },
+            { "to": logger, "data": EventLogger::emitHelloCall {}.abi_encode() },
+        ]),
+    )
+    .await?;
+    assert_eq!(status["status"], 200, "{status}");
+    assert_eq!(status["atomic"], true);
+    assert_eq!(
+        provider.get_balance(dave.address()).await? - dave_before,
+        ONE_ETH
+    );
+    // Bob paid the gas, so Alice spent exactly what she sent.
+    assert_eq!(
+        alice_before - provider.get_balance(alice.address()).await?,
+        ONE_ETH
+    );
+
+    let after = capabilities(&dapp, alice.address(), &chain_id).await?;
+    assert_eq!(after["atomic"]["status"], "supported");
+    let unknown: Result<Value, _> = dapp
+        .raw_request("wallet_getCapabilities".into(), (Address::ZERO,))
+        .await;
+    assert!(unknown.is_err(), "unknown accounts are unauthorized");
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn reads_the_nonce_an_earlier_delegation_left() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob, dave) = (key(&anvil, 0), key(&anvil, 1), key(&anvil, 2));
+    let provider = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let delegate = artifacts::deploy(&provider, "BatchCallAndSponsorHardened").await?;
+    let url = serve(
+        &anvil,
+        "BatchCallAndSponsorHardened",
+        delegate,
+        Executor::BatchCallAndSponsorHardened,
+    )
+    .await?;
+    let dapp = wallet_rpc::connect(url.parse()?, TOKEN)?;
+    let chain_id = format!("{:#x}", anvil.chain_id());
+    let pay_dave = json!([{ "to": dave.address(), "value": ONE_ETH }]);
+
+    let status = send_sponsored(&dapp, alice.address(), &chain_id, pay_dave.clone()).await?;
+    assert_eq!(status["status"], 200, "{status}");
+
+    // Alice revokes her delegation. The executor's nonce stays behind in her storage.
+    let nonce = provider.get_transaction_count(alice.address()).await?;
+    let revoke = TransactionRequest::default()
+        .with_to(alice.address())
+        .with_authorization_list(vec![sign_revocation(&alice, anvil.chain_id(), nonce)?])
+        .with_gas_limit(100_000);
+    provider
+        .send_transaction(revoke)
+        .await?
+        .get_receipt()
+        .await?;
+    assert!(provider.get_code_at(alice.address()).await?.is_empty());
+    let stored = provider.get_storage_at(alice.address(), U256::ZERO).await?;
+    assert_eq!(stored, U256::from(1));
+
+    // The wallet signs for nonce 1, not 0, and delegates her again in the same transaction.
+    let dave_before = provider.get_balance(dave.address()).await?;
+    let status = send_sponsored(&dapp, alice.address(), &chain_id, pay_dave).await?;
+    assert_eq!(status["status"], 200, "{status}");
+    assert_eq!(
+        provider.get_balance(dave.address()).await? - dave_before,
+        ONE_ETH
+    );
+    assert_eq!(
+        BatchCallAndSponsorHardened::new(alice.address(), &provider)
+            .nonce()
+            .call()
+            .await?,
+        U256::from(2)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and `make contracts`"]
+async fn refuses_to_sponsor_the_legacy_executor() -> anyhow::Result<()> {
+    let anvil = prague(31337)?;
+    let (alice, bob, dave) = (key(&anvil, 0), key(&anvil, 1), key(&anvil, 2));
+    let provider = ProviderBuilder::new()
+        .wallet(bob)
+        .connect_http(anvil.endpoint_url());
+    let delegate = *BatchCallAndSponsor::deploy(&provider).await?.address();
+    let url = serve(
+        &anvil,
+        "BatchCallAndSponsor",
+        delegate,
+        Executor::BatchCallAndSponsor,
+    )
+    .await?;
+    let dapp = wallet_rpc::connect(url.parse()?, TOKEN)?;
+    let chain_id = format!("{:#x}", anvil.chain_id());
+
+    let offered = capabilities(&dapp, alice.address(), &chain_id).await?;
+    assert_eq!(offered["paymasterService"]["supported"], false);
+    let refused = send_sponsored(
+        &dapp,
+        alice.address(),
+        &chain_id,
+        json!([{ "to": dave.address(), "value": ONE_ETH }]),
+    )
+    .await;
+    let err = refused.expect_err("a replayable batch must not be sponsored");
+    let code = err
+        .downcast_ref::<alloy::transports::TransportError>()
+        .and_then(|err| err.as_error_resp())
+        .map(|resp| resp.code);
+    assert_eq!(code, Some(WalletError::UNSUPPORTED_CAPABILITY));
+    Ok(())
+}